@@ -4,6 +4,7 @@ import { useState, useEffect } from "react";
 import { createLibp2p, Libp2p } from "libp2p";
 import * as filters from "@libp2p/websockets/filters";
 import { webSockets } from "@libp2p/websockets";
+import { webRTCStar } from "@libp2p/webrtc-star";
 import { yamux } from "@chainsafe/libp2p-yamux";
 import { noise } from "@chainsafe/libp2p-noise";
 import {
@@ -20,6 +21,7 @@ enable("libp2p:gossipsub,quarry");
 
 const ADDR_KEY = "/maddr/default";
 const NETNAME_KEY = "/netname/default";
+const STAR_KEY = "/star/default";
 const PRIV_KEY = "/pkeyimport/default";
 const TO_KEY = "/toaddr/default";
 const AMOUNT_KEY = "/amount/default";
@@ -31,6 +33,10 @@ function App() {
   const [netname, setNetname] = useState(
     localStorage.getItem(NETNAME_KEY) ?? ""
   );
+  // Address of a webrtc-star signalling server, e.g.
+  // /dns4/star.example.com/tcp/443/wss/p2p-webrtc-star. When given, the
+  // client listens through it and dials other browsers directly over WebRTC.
+  const [star, setStar] = useState(localStorage.getItem(STAR_KEY) ?? "");
   const [loading, setLoading] = useState(false);
 
   async function connectPeer() {
@@ -38,9 +44,16 @@ function App() {
 
     localStorage.setItem(NETNAME_KEY, netname);
     localStorage.setItem(ADDR_KEY, maddr);
+    localStorage.setItem(STAR_KEY, star);
 
+    const rtc = star ? webRTCStar() : null;
     const host = await createLibp2p({
-      transports: [webSockets({ filter: filters.all })],
+      addresses: { listen: star ? [star] : [] },
+      transports: [
+        webSockets({ filter: filters.all }),
+        ...(rtc ? [rtc.transport] : []),
+      ],
+      peerDiscovery: rtc ? [rtc.discovery] : [],
       connectionEncryption: [noise()],
       streamMuxers: [yamux()],
     });
@@ -77,7 +90,7 @@ function App() {
     localStorage.setItem(TO_KEY, to);
     localStorage.setItem(AMOUNT_KEY, amount);
     const msgCid = await quarry?.pushMessage(
-      messages.send({ amount, to, nonce })
+      messages.send({ amount, to, from: key?.addr, nonce })
     );
     const r = await quarry?.waitMessage(msgCid);
     console.log(r);
@@ -111,6 +124,17 @@ function App() {
         onChange={(e) => setNetname(e.target.value)}
       />
 
+      <input
+        id="star"
+        type="text"
+        autoComplete="off"
+        spellCheck="false"
+        placeholder="webrtc-star address (optional)"
+        className="ipt"
+        value={star}
+        onChange={(e) => setStar(e.target.value)}
+      />
+
       <button
         className="btn"
         onClick={connectPeer}
@@ -0,0 +1,105 @@
+import type { NetworkConfig } from "./networks.js";
+
+export type SlotClockOptions = {
+  // Unix time of slot 0, in seconds.
+  genesisTime: number;
+  slotDurationSecs: number;
+  // How far our clock may be off from the rest of the committee. Signing is
+  // only allowed when we're at least this far inside the slot.
+  maxClockSkewMs?: number;
+  // Both clocks return milliseconds, they're only overridden in tests.
+  wallClock?: () => number;
+  monotonicClock?: () => number;
+};
+
+const defaultMaxClockSkewMs = 1000;
+
+// Slot clock anchored on the wall clock but advancing with the monotonic
+// clock, so an NTP step between resyncs can't move us into another slot.
+// Resyncing corrects the drift of the monotonic clock; if the wall clock
+// moved by more than the allowed skew the time is considered unreliable and
+// signing is refused until a later resync agrees with it again.
+export class SlotClock {
+  readonly genesisTime: number;
+  readonly slotDurationMs: number;
+  readonly maxClockSkewMs: number;
+  private wallClock: () => number;
+  private monotonicClock: () => number;
+  private offset: number;
+  private reliable: boolean = true;
+
+  constructor(opts: SlotClockOptions) {
+    if (opts.slotDurationSecs <= 0) {
+      throw new Error("slot duration must be positive");
+    }
+    this.genesisTime = opts.genesisTime;
+    this.slotDurationMs = opts.slotDurationSecs * 1000;
+    this.maxClockSkewMs = opts.maxClockSkewMs ?? defaultMaxClockSkewMs;
+    if (2 * this.maxClockSkewMs >= this.slotDurationMs) {
+      throw new Error("max clock skew leaves no time to sign in a slot");
+    }
+    this.wallClock = opts.wallClock ?? (() => Date.now());
+    this.monotonicClock = opts.monotonicClock ?? (() => performance.now());
+    this.offset = this.wallClock() - this.monotonicClock();
+  }
+
+  // Current time in milliseconds since the unix epoch.
+  now(): number {
+    return this.offset + this.monotonicClock();
+  }
+
+  // Re-anchor on the wall clock and return the drift that was corrected.
+  resync(): number {
+    const drift = this.wallClock() - this.now();
+    this.offset += drift;
+    this.reliable = Math.abs(drift) <= this.maxClockSkewMs;
+    return drift;
+  }
+
+  isReliable(): boolean {
+    return this.reliable;
+  }
+
+  slotStart(slot: number): number {
+    return this.genesisTime * 1000 + slot * this.slotDurationMs;
+  }
+
+  // Slot we're in, -1 before genesis.
+  currentSlot(): number {
+    const sinceGenesis = this.now() - this.genesisTime * 1000;
+    if (sinceGenesis < 0) {
+      return -1;
+    }
+    return Math.floor(sinceGenesis / this.slotDurationMs);
+  }
+
+  msUntilSlot(slot: number): number {
+    return Math.max(0, this.slotStart(slot) - this.now());
+  }
+
+  // Whether a signature for `slot` is safe to produce now, i.e. every
+  // member whose clock is within the max skew of ours agrees it's `slot`.
+  canSign(slot: number): boolean {
+    if (!this.reliable || slot < 0) {
+      return false;
+    }
+    const now = this.now();
+    return (
+      now >= this.slotStart(slot) + this.maxClockSkewMs &&
+      now < this.slotStart(slot + 1) - this.maxClockSkewMs
+    );
+  }
+}
+
+// Slot clock following the block time of a network profile.
+export function networkClock(
+  net: NetworkConfig,
+  genesisTime: number,
+  opts: Omit<SlotClockOptions, "genesisTime" | "slotDurationSecs"> = {}
+): SlotClock {
+  return new SlotClock({
+    ...opts,
+    genesisTime,
+    slotDurationSecs: net.blockDelaySecs,
+  });
+}
@@ -0,0 +1,99 @@
+// Confidence that the data behind a commitment is available, from the
+// distinct shards a client fetched successfully.
+//
+// The adversary withholds the fewest shards that make the data
+// unrecoverable, since withholding more only makes it easier to catch, and
+// serves every other shard. For a 1D Reed-Solomon code of n shards of which
+// any k recover the data, that is m = n - k + 1 shards. For the 2D code
+// extending a w x w square to 2w x 2w, it is (w + 1)^2 of the (2w)^2 shards.
+// Sampling s distinct shards uniformly at random, all of them are served
+// with probability
+//
+//   P(s) = prod_{i < s} (N - m - i) / (N - i)
+//
+// and the confidence is 1 - P(s). Once more than N - m distinct shards were
+// served, the data is recoverable and the confidence is 1. The bound only
+// holds if the adversary can't tell which requests come from the sampler,
+// otherwise it can serve exactly the sampled shards.
+export type Coding =
+  | { kind: "1d"; shards: number; dataShards: number }
+  | { kind: "2d"; width: number };
+
+function shards(coding: Coding): number {
+  return coding.kind === "1d" ? coding.shards : 4 * coding.width * coding.width;
+}
+
+// Shards the adversary withholds.
+export function withheld(coding: Coding): number {
+  return coding.kind === "1d"
+    ? coding.shards - coding.dataShards + 1
+    : (coding.width + 1) * (coding.width + 1);
+}
+
+export function confidence(samples: number, coding: Coding): number {
+  const total = shards(coding);
+  const available = total - withheld(coding);
+  if (samples > available) {
+    return 1;
+  }
+  let fooled = 1;
+  for (let i = 0; i < samples; i++) {
+    fooled *= (available - i) / (total - i);
+  }
+  return 1 - fooled;
+}
+
+// Fewest samples reaching `target`.
+export function samplesFor(target: number, coding: Coding): number {
+  if (target < 0 || target > 1) {
+    throw new Error("target confidence must be between 0 and 1");
+  }
+  let samples = 0;
+  while (confidence(samples, coding) < target) {
+    samples++;
+  }
+  return samples;
+}
+
+// Successful samples per commitment, e.g. the CID of a data root.
+export class ConfidenceTracker {
+  private samples = new Map<string, Set<number>>();
+
+  constructor(readonly coding: Coding, readonly target: number = 0.9999) {
+    if (target < 0 || target > 1) {
+      throw new Error("target confidence must be between 0 and 1");
+    }
+  }
+
+  // Samples to take per commitment to reach the target.
+  get sampleCount(): number {
+    return samplesFor(this.target, this.coding);
+  }
+
+  // Record a shard that was served and verified against the commitment.
+  record(commitment: string, index: number) {
+    if (!Number.isInteger(index) || index < 0 || index >= shards(this.coding)) {
+      throw new Error(`shard ${index} out of range`);
+    }
+    let served = this.samples.get(commitment);
+    if (served === undefined) {
+      served = new Set();
+      this.samples.set(commitment, served);
+    }
+    served.add(index);
+  }
+
+  confidence(commitment: string): number {
+    return confidence(this.samples.get(commitment)?.size ?? 0, this.coding);
+  }
+
+  // Samples still to take before the commitment reaches the target.
+  remaining(commitment: string): number {
+    const served = this.samples.get(commitment)?.size ?? 0;
+    return Math.max(0, this.sampleCount - served);
+  }
+
+  forget(commitment: string) {
+    this.samples.delete(commitment);
+  }
+}
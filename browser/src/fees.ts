@@ -0,0 +1,103 @@
+// Fee strategies decide the fee cap and premium of outgoing messages from
+// the current base fee. A strategy returns null to defer the submission,
+// e.g. when fees are above budget; the caller is expected to retry later.
+export type Fee = {
+  gasFeeCap: bigint;
+  gasPremium: bigint;
+};
+
+// Strategies with `observe` are fed the premium of every message the
+// client sees gossiped, see `createQuarry`.
+export interface FeeStrategy {
+  estimate(baseFee: bigint, gasLimit: number): Fee | null;
+  observe?(premium: bigint): void;
+}
+
+export class FeeDeferredError extends Error {
+  constructor(message: string) {
+    super(message);
+    this.name = "FeeDeferredError";
+  }
+}
+
+const MinGasPremium = 100_000n;
+
+// Cover the base fee rising at its maximum rate of 1/8 per epoch for
+// `epochs` epochs, with a fixed premium on top.
+export function baseFeeTracking({
+  premium = (MinGasPremium * 3n) / 2n,
+  epochs = 20,
+}: { premium?: bigint; epochs?: number } = {}): FeeStrategy {
+  // fixed point with 8 fractional bits
+  const increase = BigInt(Math.round(Math.pow(1 + 1 / 8, epochs) * (1 << 8)));
+  return {
+    estimate(baseFee: bigint): Fee {
+      return {
+        gasFeeCap: (baseFee * increase) / BigInt(1 << 8) + premium,
+        gasPremium: premium,
+      };
+    },
+  };
+}
+
+// EIP-1559 style percentile targeting: the premium is the given percentile
+// of the premiums recently observed, and the fee cap leaves room for the
+// base fee to double. The client observes the messages gossiped to the
+// mempool, which are what an outgoing message competes with for inclusion.
+export class PercentilePremium implements FeeStrategy {
+  private samples: bigint[] = [];
+
+  constructor(
+    readonly percentile: number = 50,
+    readonly window: number = 100,
+    readonly fallback: bigint = (MinGasPremium * 3n) / 2n
+  ) {
+    if (percentile < 0 || percentile > 100) {
+      throw new Error("percentile must be between 0 and 100");
+    }
+  }
+
+  observe(premium: bigint) {
+    this.samples.push(premium);
+    if (this.samples.length > this.window) {
+      this.samples.shift();
+    }
+  }
+
+  premium(): bigint {
+    if (this.samples.length === 0) {
+      return this.fallback;
+    }
+    const sorted = [...this.samples].sort((a, b) =>
+      a < b ? -1 : a > b ? 1 : 0
+    );
+    const index = Math.min(
+      sorted.length - 1,
+      Math.floor((this.percentile / 100) * sorted.length)
+    );
+    const premium = sorted[index];
+    return premium < MinGasPremium ? MinGasPremium : premium;
+  }
+
+  estimate(baseFee: bigint): Fee {
+    const gasPremium = this.premium();
+    return { gasFeeCap: 2n * baseFee + gasPremium, gasPremium };
+  }
+}
+
+// Defer submission when the most a message could cost under `inner`
+// exceeds `budget` attoFIL.
+export function costCap(inner: FeeStrategy, budget: bigint): FeeStrategy {
+  return {
+    estimate(baseFee: bigint, gasLimit: number): Fee | null {
+      const fee = inner.estimate(baseFee, gasLimit);
+      if (fee === null || fee.gasFeeCap * BigInt(gasLimit) > budget) {
+        return null;
+      }
+      return fee;
+    },
+    observe(premium: bigint) {
+      inner.observe?.(premium);
+    },
+  };
+}
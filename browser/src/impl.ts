@@ -15,12 +15,18 @@ import {
   MessageReceipt,
   buildCid,
   serializeSignedMessage,
-  signMessage,
+  signMessageWith,
   estimateMessageGas,
+  signedMessagePremium,
 } from "./messages.js";
 import { BlockMsg, decodeBlockMsg, BlockHeader } from "./chainExchange.js";
 import { toPublic, Key } from "./signer.js";
 import { AMT } from "./amt.js";
+import { getNetwork } from "./networks.js";
+import type { FeeStrategy } from "./fees.js";
+import { localSigner, signerFor } from "./wallet.js";
+import type { MessageSigner } from "./wallet.js";
+import type { GossipRecord, GossipRecorder } from "./recorder.js";
 
 type HelloMsg = [CID[], number, number, CID];
 
@@ -36,9 +42,11 @@ type Unsubscribe = Function;
 export type QuarryClient = {
   subscribeToBlocks: (cb: (blk: BlockMsg) => void) => Unsubscribe;
   importKey: (privKey: string) => Key;
+  addSigner: (signer: MessageSigner) => void;
   pushMessage: (msg: Message) => Promise<CID>;
   waitMessage: (msg: CID) => Promise<any>;
   getHead: () => Promise<BlockHeader>;
+  replay: (records: GossipRecord[]) => void;
 };
 
 type ClientOptions = {
@@ -46,6 +54,9 @@ type ClientOptions = {
   handleHello?: boolean;
   bootstrappers?: string[];
   gossipsub?: GossipsubOpts;
+  feeStrategy?: FeeStrategy;
+  // Record every gossip message received, for debugging.
+  recorder?: GossipRecorder;
 };
 
 export async function createQuarry(
@@ -54,6 +65,8 @@ export async function createQuarry(
 ): Promise<QuarryClient> {
   const log = logger("quarry");
 
+  const net = getNetwork(options.networkName);
+
   const blocks = new Cachestore("/quarry/blocks");
   await blocks.open();
 
@@ -120,25 +133,56 @@ export async function createQuarry(
     return nonceTracker[addr]++;
   }
 
-  // insecure keystore in memory for development. Do not store real private keys in there!
-  const keystore: Map<string, Key> = new Map();
-  const msgTopic = "/fil/msgs/" + options.networkName;
-  const blkTopic = "/fil/blocks/" + options.networkName;
+  // imported keys are kept in memory for development. Do not import real private keys,
+  // add a remote signer instead.
+  const keystore: Map<string, MessageSigner> = new Map();
+  const msgTopic = net.topics.messages;
+  const blkTopic = net.topics.blocks;
 
   // gossip params are setup after Lotus params in order for the node to be treated
   // as similarly as other peers as possible. Need more research into fine tuning these.
   const pubsub = new GossipSub(network, options.gossipsub);
   await pubsub.start();
 
+  // Set while recorded messages are dispatched so they aren't recorded again.
+  let replaying = false;
+
+  // Feed recorded messages to the same listeners as live gossip, in order.
+  function replay(records: GossipRecord[]) {
+    replaying = true;
+    try {
+      for (const { topic, data } of records) {
+        pubsub.dispatchEvent(
+          new CustomEvent("message", { detail: { topic, data } })
+        );
+      }
+    } finally {
+      replaying = false;
+    }
+  }
+
   pubsub.subscribe(blkTopic);
   pubsub.subscribe(msgTopic);
   pubsub.addEventListener("message", async (evt) => {
+    if (options.recorder && !replaying) {
+      const from = "from" in evt.detail ? evt.detail.from.toString() : "";
+      options.recorder.record(evt.detail.topic, from, evt.detail.data);
+    }
     switch (evt.detail.topic) {
       case blkTopic:
         const msg = decodeBlockMsg(evt.detail.data);
         head = msg.header;
         break;
       case msgTopic:
+        if (options.feeStrategy?.observe) {
+          try {
+            options.feeStrategy.observe(
+              signedMessagePremium(evt.detail.data)
+            );
+          } catch (err) {
+            log("ignoring malformed message gossip: %o", err);
+          }
+        }
         break;
     }
   });
@@ -175,9 +219,10 @@ export async function createQuarry(
   }
 
   // dial all the bootstrapper peers
-  if (options.bootstrappers) {
+  const bootstrappers = options.bootstrappers ?? net.bootstrappers;
+  if (bootstrappers.length) {
     await Promise.all(
-      options.bootstrappers.map((addr) => network.dial(multiaddr(addr)))
+      bootstrappers.map((addr) => network.dial(multiaddr(addr)))
     ).catch((err) => console.error("failed to dial bootstrap peers", err));
   }
 
@@ -220,19 +265,28 @@ export async function createQuarry(
   return {
     getHead,
     subscribeToBlocks,
+    replay,
     importKey: function (privKey: string): Key {
-      const key = toPublic(privKey);
-      keystore.set(key.addr, key);
+      const key = toPublic(privKey, net.addressNetwork);
+      keystore.set(key.addr, localSigner(key));
       return key;
     },
+    addSigner: function (signer: MessageSigner) {
+      keystore.set(signer.address, signer);
+    },
     pushMessage: async function (msg: Message): Promise<CID> {
-      const { value: key } = keystore.values().next();
-      msg.from = key.addr;
+      if (!msg.to.startsWith(net.addressNetwork)) {
+        throw new Error(
+          `recipient ${msg.to} is not an address on ${net.networkName}`
+        );
+      }
+      const signer = signerFor(keystore, msg.from);
+      msg.from = signer.address;
       if (msg.nonce === 0) {
-        msg.nonce = getNextNonce(key.addr);
+        msg.nonce = getNextNonce(signer.address);
       }
-      estimateMessageGas(msg, await getHead());
-      const smsg = signMessage(msg, key.priv);
+      estimateMessageGas(msg, await getHead(), options.feeStrategy);
+      const smsg = await signMessageWith(msg, signer);
       const enc = serializeSignedMessage(smsg);
       // re hash the whole thing
       const cid = buildCid(enc);
@@ -2,6 +2,23 @@ import { send } from "./messages.js";
 const messages = { send };
 export { messages };
 export * as signer from "./signer.js";
+export * as networks from "./networks.js";
+export * as fees from "./fees.js";
+export * as msig from "./msig.js";
+export * as paych from "./paych.js";
+export { localSigner, lotusWalletSigner } from "./wallet.js";
+export { SlotClock, networkClock } from "./clock.js";
 export { createQuarry } from "./impl.js";
+export { ConfidenceTracker, confidence, samplesFor } from "./confidence.js";
+export {
+  GossipRecorder,
+  encodeRecords,
+  decodeRecords,
+} from "./recorder.js";
+export type { GossipRecord } from "./recorder.js";
+export type { Coding } from "./confidence.js";
 export type { ChainInfo, QuarryClient } from "./impl.js";
 export type { Key } from "./signer.js";
+export type { NetworkConfig } from "./networks.js";
+export type { SlotClockOptions } from "./clock.js";
+export type { MessageSigner } from "./wallet.js";
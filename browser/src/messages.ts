@@ -1,10 +1,14 @@
-import { encode } from "@ipld/dag-cbor";
+import { decode, encode } from "@ipld/dag-cbor";
 import { addressToBytes, sign } from "./signer.js";
 import { BN } from "bn.js";
 import { Uint8ArrayList } from "uint8arraylist";
 import { CID } from "multiformats";
+import { base64pad } from "multiformats/bases/base64";
 import { blake2b256 } from "@multiformats/blake2/blake2b";
 import type { BlockHeader } from "./chainExchange.js";
+import { baseFeeTracking, FeeDeferredError } from "./fees.js";
+import type { FeeStrategy } from "./fees.js";
+import type { MessageSigner } from "./wallet.js";
 
 // A Filecoin message for sending to miners and include in blocks.
 // It is encoded as a CBOR array.
@@ -24,16 +28,18 @@ export type Message = {
 export function send({
   amount,
   to,
+  from,
   nonce,
 }: {
   amount: string;
   to: string;
+  from?: string;
   nonce?: number;
 }): Message {
   return {
     version: 0,
     to,
-    from: "",
+    from: from ?? "",
     nonce: nonce ?? 0,
     value: amount,
     gasLimit: 0,
@@ -56,10 +62,19 @@ export function encodeMessage(msg: Message) {
     serializeBigNum(msg.gasFeeCap),
     serializeBigNum(msg.gasPremium),
     msg.method,
-    new Uint8Array(),
+    paramsToBytes(msg.params),
   ]);
 }
 
+// Params are base64 encoded like in the Lotus JSON API.
+export function paramsToBytes(params: string): Uint8Array {
+  return params ? base64pad.baseDecode(params) : new Uint8Array();
+}
+
+export function paramsFromBytes(params: Uint8Array): string {
+  return params.length ? base64pad.baseEncode(params) : "";
+}
+
 // Encode Filecoin amount strings for inclusing in a message.
 export function serializeBigNum(num: string): Uint8Array {
   const bn = new BN(num, 10);
@@ -118,6 +133,19 @@ export function signMessage(msg: Message, privKey: Uint8Array): SignedMessage {
   };
 }
 
+export async function signMessageWith(
+  msg: Message,
+  signer: MessageSigner
+): Promise<SignedMessage> {
+  const { cid, data } = toStorageBlock(msg);
+  return {
+    signature: await signer.sign(cid.bytes),
+    msg,
+    bytes: data,
+    cid,
+  };
+}
+
 export function serializeSignedMessage(smsg: SignedMessage): Uint8Array {
   const sigUl = new Uint8ArrayList();
   // secp256k1 sign type is a 0 byte
@@ -136,33 +164,47 @@ export function serializeSignedMessage(smsg: SignedMessage): Uint8Array {
       serializeBigNum(msg.gasFeeCap),
       serializeBigNum(msg.gasPremium),
       msg.method,
-      new Uint8Array(),
+      paramsToBytes(msg.params),
     ],
     sigUl.slice(),
   ]);
 }
 
+// Premium of a signed message as gossiped on the messages topic.
+export function signedMessagePremium(data: Uint8Array): bigint {
+  const [msg] = decode<[any[], Uint8Array]>(data);
+  return decodeBigNum(msg[7]);
+}
+
 const BlockGasLimit = 10_000_000_000;
 const BlockGasTarget = BlockGasLimit / 2;
-const MinGasPremium = 100e3;
 
-export function estimateMessageGas(msg: Message, head: BlockHeader): Message {
+export function estimateMessageGas(
+  msg: Message,
+  head: BlockHeader,
+  strategy: FeeStrategy = baseFeeTracking()
+): Message {
   if (!msg.gasLimit) {
     // Gas Limit is usually estimated by running the transaction through the state manager's VM
     // need to think of a strategy for saving gas here we default to the block gas target which is
     // way higher but should work for most transactions.
     msg.gasLimit = BlockGasTarget / 10;
   }
-  if (!msg.gasPremium) {
-    msg.gasPremium = 1.5 * MinGasPremium + "";
-  }
-  if (!msg.gasFeeCap) {
+  if (!msg.gasPremium || !msg.gasFeeCap) {
     const baseFee = decodeBigNum(head.parentBaseFee);
-    const increaseFactor = Math.pow(1 + 1 / 8, 20);
-
-    const feeInFuture = baseFee * BigInt(Math.round(increaseFactor * (1 << 8)));
-    const feeCap = feeInFuture / BigInt(1 << 8) + BigInt(msg.gasPremium);
-    msg.gasFeeCap = feeCap + "";
+    const fee = strategy.estimate(baseFee, msg.gasLimit);
+    if (fee === null) {
+      throw new FeeDeferredError(
+        `fees at base fee ${baseFee} are above what the strategy allows`
+      );
+    }
+    if (!msg.gasPremium) {
+      msg.gasPremium = fee.gasPremium + "";
+    }
+    if (!msg.gasFeeCap) {
+      const feeCap = fee.gasFeeCap - fee.gasPremium + BigInt(msg.gasPremium);
+      msg.gasFeeCap = feeCap + "";
+    }
   }
   return msg;
 }
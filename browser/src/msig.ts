@@ -0,0 +1,65 @@
+import { encode } from "@ipld/dag-cbor";
+import { addressToBytes } from "./signer.js";
+import {
+  Message,
+  send,
+  serializeBigNum,
+  paramsToBytes,
+  paramsFromBytes,
+} from "./messages.js";
+
+// Methods of the builtin multisig actor.
+export enum MultisigMethod {
+  Propose = 2,
+  Approve = 3,
+  Cancel = 4,
+}
+
+// Wrap `inner` in a proposal to the multisig at `msig`. The proposer's
+// approval is counted right away; the message executes once enough signers
+// approve the transaction ID returned by the proposal.
+export function propose(msig: string, inner: Message): Message {
+  const params = encode([
+    addressToBytes(inner.to),
+    serializeBigNum(inner.value),
+    inner.method,
+    paramsToBytes(inner.params),
+  ]);
+  return {
+    ...send({ amount: "0", to: msig, nonce: inner.nonce }),
+    method: MultisigMethod.Propose,
+    params: paramsFromBytes(params),
+  };
+}
+
+// Approve pending transaction `txnId`. Passing the proposal hash makes the
+// approval fail if the pending transaction isn't the one we expect.
+export function approve(
+  msig: string,
+  txnId: number,
+  proposalHash?: Uint8Array
+): Message {
+  return txnMessage(msig, MultisigMethod.Approve, txnId, proposalHash);
+}
+
+export function cancel(
+  msig: string,
+  txnId: number,
+  proposalHash?: Uint8Array
+): Message {
+  return txnMessage(msig, MultisigMethod.Cancel, txnId, proposalHash);
+}
+
+function txnMessage(
+  msig: string,
+  method: MultisigMethod,
+  txnId: number,
+  proposalHash?: Uint8Array
+): Message {
+  const params = encode([txnId, proposalHash ?? new Uint8Array()]);
+  return {
+    ...send({ amount: "0", to: msig }),
+    method,
+    params: paramsFromBytes(params),
+  };
+}
@@ -0,0 +1,87 @@
+import { Network as AddressNetwork } from "./signer.js";
+
+// Parameters the client needs to know about the Filecoin network it joins.
+// Topics and address prefixes are derived from the profile so a client
+// configured for one network can't publish messages meant for another.
+export type NetworkConfig = {
+  networkName: string;
+  addressNetwork: AddressNetwork;
+  blockDelaySecs: number;
+  bootstrappers: string[];
+  topics: {
+    blocks: string;
+    messages: string;
+  };
+  // Singleton built-in actors, at the same ID on every network.
+  actors: {
+    system: string;
+    init: string;
+    reward: string;
+    cron: string;
+    power: string;
+    market: string;
+    verifiedRegistry: string;
+    burntFunds: string;
+  };
+  // Not bundled yet: the genesis of each network, the addresses of the
+  // attestation contracts and the digests of the SRS they verify against
+  // are only known once Quarry is deployed there. Until then they have to
+  // be set by the caller, and a client without them can't tell two
+  // deployments apart.
+  genesis?: {
+    cid: string;
+    timestamp: number;
+  };
+  contracts?: {
+    attestations: string;
+    registry: string;
+  };
+  // Hex encoded digest of the SRS file for each circuit degree k.
+  srsDigests?: { [k: number]: string };
+};
+
+export const FilecoinMainnet = "testnetnet";
+export const FilecoinCalibnet = "calibrationnet";
+export const FilecoinDevNet = "wallabynet";
+
+function profile(
+  networkName: string,
+  addressNetwork: AddressNetwork,
+  blockDelaySecs: number
+): NetworkConfig {
+  return {
+    networkName,
+    addressNetwork,
+    blockDelaySecs,
+    // Lotus bootstrap nodes don't expose browser transports so there
+    // are no default bootstrappers for now.
+    bootstrappers: [],
+    topics: {
+      blocks: "/fil/blocks/" + networkName,
+      messages: "/fil/msgs/" + networkName,
+    },
+    actors: {
+      system: addressNetwork + "00",
+      init: addressNetwork + "01",
+      reward: addressNetwork + "02",
+      cron: addressNetwork + "03",
+      power: addressNetwork + "04",
+      market: addressNetwork + "05",
+      verifiedRegistry: addressNetwork + "06",
+      burntFunds: addressNetwork + "099",
+    },
+  };
+}
+
+const profiles: { [name: string]: NetworkConfig } = {
+  [FilecoinMainnet]: profile(FilecoinMainnet, AddressNetwork.MAIN, 30),
+  [FilecoinCalibnet]: profile(FilecoinCalibnet, AddressNetwork.TEST, 30),
+  [FilecoinDevNet]: profile(FilecoinDevNet, AddressNetwork.TEST, 30),
+};
+
+// Return the bundled profile for a network name. Unknown names are treated
+// as local devnets such as the one described in the README which use test
+// addresses and the 2k build block time.
+export function getNetwork(networkName: string): NetworkConfig {
+  return profiles[networkName] ?? profile(networkName, AddressNetwork.TEST, 4);
+}
@@ -0,0 +1,164 @@
+import { encode } from "@ipld/dag-cbor";
+import type { CID } from "multiformats";
+import { addressToBytes } from "./signer.js";
+import {
+  Message,
+  send,
+  serializeBigNum,
+  paramsFromBytes,
+} from "./messages.js";
+import type { MessageSigner } from "./wallet.js";
+
+// Methods of the builtin payment channel actor.
+export enum PaychMethod {
+  UpdateChannelState = 2,
+  Settle = 3,
+  Collect = 4,
+}
+
+// Channels are created through the Exec method of the init actor, ID 1.
+const InitExec = 2;
+
+// Signature type byte of secp256k1 signatures.
+const SigTypeSecp256k1 = 1;
+
+// Create a channel from `from` to `to` funded with `amount`. The code CID of
+// the payment channel actor depends on the network and actors version, so it
+// is passed in.
+export function create(
+  from: string,
+  to: string,
+  amount: string,
+  code: CID,
+  nonce?: number
+): Message {
+  const ctorParams = encode([addressToBytes(from), addressToBytes(to)]);
+  return {
+    ...send({ amount, to: from[0] + "01", nonce }),
+    method: InitExec,
+    params: paramsFromBytes(encode([code, ctorParams])),
+  };
+}
+
+// A voucher redeems `amount` in total on its lane, so each voucher
+// supersedes the previous ones of the lane with a lower nonce.
+export type Voucher = {
+  channel: string;
+  lane: number;
+  nonce: number;
+  amount: string;
+  timeLockMin?: number;
+  timeLockMax?: number;
+  minSettleHeight?: number;
+  signature?: Uint8Array;
+};
+
+function voucherFields(voucher: Voucher, signature: Uint8Array | null) {
+  return [
+    addressToBytes(voucher.channel),
+    voucher.timeLockMin ?? 0,
+    voucher.timeLockMax ?? 0,
+    new Uint8Array(),
+    null,
+    voucher.lane,
+    voucher.nonce,
+    serializeBigNum(voucher.amount),
+    voucher.minSettleHeight ?? 0,
+    [],
+    signature,
+  ];
+}
+
+// Bytes the payer signs: the voucher encoded without its signature.
+export function voucherSigningBytes(voucher: Voucher): Uint8Array {
+  return encode(voucherFields(voucher, null));
+}
+
+export async function signVoucher(
+  voucher: Voucher,
+  signer: MessageSigner
+): Promise<Voucher> {
+  return {
+    ...voucher,
+    signature: await signer.sign(voucherSigningBytes(voucher)),
+  };
+}
+
+function signatureBytes(voucher: Voucher): Uint8Array {
+  if (!voucher.signature) {
+    throw new Error("voucher isn't signed");
+  }
+  const bytes = new Uint8Array(voucher.signature.length + 1);
+  bytes[0] = SigTypeSecp256k1;
+  bytes.set(voucher.signature, 1);
+  return bytes;
+}
+
+// Encode a signed voucher, e.g. to hand it to the payee.
+export function serializeVoucher(voucher: Voucher): Uint8Array {
+  return encode(voucherFields(voucher, signatureBytes(voucher)));
+}
+
+// Redeem a signed voucher on chain. Only the payee's latest voucher per lane
+// needs submitting.
+export function updateChannelState(voucher: Voucher): Message {
+  const params = encode([
+    voucherFields(voucher, signatureBytes(voucher)),
+    new Uint8Array(),
+  ]);
+  return {
+    ...send({ amount: "0", to: voucher.channel }),
+    method: PaychMethod.UpdateChannelState,
+    params: paramsFromBytes(params),
+  };
+}
+
+// Start the settlement period, after which the channel can be collected.
+export function settle(channel: string): Message {
+  return {
+    ...send({ amount: "0", to: channel }),
+    method: PaychMethod.Settle,
+  };
+}
+
+export function collect(channel: string): Message {
+  return {
+    ...send({ amount: "0", to: channel }),
+    method: PaychMethod.Collect,
+  };
+}
+
+// Issues vouchers on one lane of a channel as chunks get paid for, each one
+// redeeming everything paid so far.
+export class LanePayer {
+  private nonce = 0;
+  private paid = 0n;
+
+  constructor(
+    private readonly channel: string,
+    private readonly lane: number,
+    private readonly signer: MessageSigner
+  ) {}
+
+  get total(): bigint {
+    return this.paid;
+  }
+
+  async pay(amount: bigint): Promise<Voucher> {
+    if (amount <= 0n) {
+      throw new Error("voucher amount must be positive");
+    }
+    const voucher = await signVoucher(
+      {
+        channel: this.channel,
+        lane: this.lane,
+        nonce: this.nonce + 1,
+        amount: (this.paid + amount).toString(),
+      },
+      this.signer
+    );
+    this.nonce++;
+    this.paid += amount;
+    return voucher;
+  }
+}
@@ -0,0 +1,55 @@
+import { decode, encode } from "@ipld/dag-cbor";
+
+// A gossip message as received: the time it arrived, in milliseconds since
+// the recording started, its topic, the peer it was forwarded by and its
+// payload.
+export type GossipRecord = {
+  time: number;
+  topic: string;
+  from: string;
+  data: Uint8Array;
+};
+
+type EncodedRecord = [number, string, string, Uint8Array];
+
+// Version of the dump format, the first element of the encoded array.
+const RecordingVersion = 1;
+
+// Keeps every gossip message the client receives so a session can be dumped
+// from the field and fed back through the handlers with
+// `QuarryClient.replay`. Only use it for debugging, recordings grow without
+// bound.
+export class GossipRecorder {
+  readonly records: GossipRecord[] = [];
+  private readonly start = Date.now();
+
+  record(topic: string, from: string, data: Uint8Array) {
+    this.records.push({ time: Date.now() - this.start, topic, from, data });
+  }
+
+  encode(): Uint8Array {
+    return encodeRecords(this.records);
+  }
+}
+
+export function encodeRecords(records: GossipRecord[]): Uint8Array {
+  return encode([
+    RecordingVersion,
+    records.map(
+      ({ time, topic, from, data }): EncodedRecord => [time, topic, from, data]
+    ),
+  ]);
+}
+
+export function decodeRecords(bytes: Uint8Array): GossipRecord[] {
+  const [version, records] = decode<[number, EncodedRecord[]]>(bytes);
+  if (version !== RecordingVersion) {
+    throw new Error(`unsupported recording version ${version}`);
+  }
+  return records.map(([time, topic, from, data]) => ({
+    time,
+    topic,
+    from,
+    data,
+  }));
+}
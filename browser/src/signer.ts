@@ -22,7 +22,7 @@ export type Key = {
   addr: string;
 };
 
-export function toPublic(key: string): Key {
+export function toPublic(key: string, net: Network = Network.TEST): Key {
   // the codec needs an M prefix for some reason
   const buf = base64pad.decode("M" + key);
   const point = ec.keyFromPrivate(buf).getPublic();
@@ -37,7 +37,7 @@ export function toPublic(key: string): Key {
   return {
     priv: buf,
     // @ts-ignore-next-line
-    addr: newAddress(AddressType.SECP256K1, addrHash, Network.TEST),
+    addr: newAddress(AddressType.SECP256K1, addrHash, net),
   };
 }
 
@@ -67,7 +67,7 @@ enum AddressType {
   BLS = 3,
 }
 
-enum Network {
+export enum Network {
   MAIN = "f",
   TEST = "t",
 }
@@ -97,11 +97,23 @@ function newAddress(
 
 // Encode an address string to bytes for inclusion in a message.
 export function addressToBytes(addr: string) {
+  const type = Number(addr[1]);
+  if (type === AddressType.ID) {
+    return idAddressToBytes(addr);
+  }
+  if (
+    type !== AddressType.SECP256K1 &&
+    type !== AddressType.ACTOR &&
+    type !== AddressType.BLS
+  ) {
+    throw new Error(`unknown address protocol ${addr[1]}`);
+  }
+
   const addrBytes = base32.decode("b" + addr.slice(2));
   const payload = addrBytes.slice(0, -4);
 
   const byteList = new Uint8ArrayList();
-  byteList.append(new Uint8Array([AddressType.SECP256K1]));
+  byteList.append(new Uint8Array([type]));
   byteList.append(payload);
 
   const bytes = byteList.slice();
@@ -111,3 +123,18 @@ export function addressToBytes(addr: string) {
   }
   return bytes;
 }
+
+// ID addresses carry the actor ID as an unsigned varint and no checksum.
+function idAddressToBytes(addr: string): Uint8Array {
+  let id = BigInt(addr.slice(2));
+  const bytes = [AddressType.ID];
+  do {
+    let byte = Number(id & 0x7fn);
+    id >>= 7n;
+    if (id > 0n) {
+      byte |= 0x80;
+    }
+    bytes.push(byte);
+  } while (id > 0n);
+  return new Uint8Array(bytes);
+}
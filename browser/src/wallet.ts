@@ -0,0 +1,91 @@
+import { base64pad } from "multiformats/bases/base64";
+import { sign } from "./signer.js";
+import type { Key } from "./signer.js";
+
+// Anything able to produce secp256k1 signatures for an address. Messages are
+// signed by signing the bytes of their CID, so remote signers never see the
+// private key and the key never has to be on this host.
+export interface MessageSigner {
+  address: string;
+  sign(data: Uint8Array): Promise<Uint8Array>;
+}
+
+export function localSigner(key: Key): MessageSigner {
+  return {
+    address: key.addr,
+    sign: async (data: Uint8Array) => sign(key.priv, data),
+  };
+}
+
+// The signer for the sender of a message, out of the imported keys and the
+// added signers. Messages without a sender go out from the first key, as
+// they always have.
+export function signerFor(
+  signers: Map<string, MessageSigner>,
+  from: string
+): MessageSigner {
+  if (!from) {
+    const { value: signer } = signers.values().next();
+    if (!signer) {
+      throw new Error(
+        "message has no sender and there is no key or signer to send it from"
+      );
+    }
+    return signer;
+  }
+  const signer = signers.get(from);
+  if (!signer) {
+    throw new Error(
+      `no signer for ${from}, import its key or add a signer for it first`
+    );
+  }
+  return signer;
+}
+
+// Lotus signature types as returned by the wallet API.
+const SigTypeSecp256k1 = 1;
+
+// Sign through the WalletSign method of a Lotus node or lotus-wallet
+// instance. The token needs the sign permission.
+export function lotusWalletSigner({
+  url,
+  token,
+  address,
+}: {
+  url: string;
+  token?: string;
+  address: string;
+}): MessageSigner {
+  return {
+    address,
+    sign: async (data: Uint8Array) => {
+      const headers: { [name: string]: string } = {
+        "Content-Type": "application/json",
+      };
+      if (token) {
+        headers.Authorization = "Bearer " + token;
+      }
+      const res = await fetch(url, {
+        method: "POST",
+        headers,
+        body: JSON.stringify({
+          jsonrpc: "2.0",
+          id: 1,
+          method: "Filecoin.WalletSign",
+          params: [address, base64pad.baseEncode(data)],
+        }),
+      });
+      if (!res.ok) {
+        throw new Error(`wallet responded with ${res.status}`);
+      }
+      const { result, error } = await res.json();
+      if (error) {
+        throw new Error(`wallet failed to sign: ${error.message}`);
+      }
+      if (result.Type !== SigTypeSecp256k1) {
+        throw new Error(`unsupported signature type ${result.Type}`);
+      }
+      return base64pad.baseDecode(result.Data);
+    },
+  };
+}
@@ -0,0 +1,76 @@
+import { expect } from "aegir/chai";
+import { SlotClock, networkClock } from "../src/clock.js";
+import { getNetwork, FilecoinCalibnet } from "../src/networks.js";
+
+function fakeClock(start: number) {
+  const clock = { wall: start, mono: 0 };
+  return {
+    clock,
+    opts: {
+      wallClock: () => clock.wall,
+      monotonicClock: () => clock.mono,
+    },
+  };
+}
+
+describe("slot clock", () => {
+  it("computes slots from genesis", () => {
+    const { clock, opts } = fakeClock(100_000);
+    const slots = new SlotClock({
+      ...opts,
+      genesisTime: 70,
+      slotDurationSecs: 10,
+    });
+    expect(slots.currentSlot()).to.equal(3);
+    clock.mono += 9_999;
+    expect(slots.currentSlot()).to.equal(3);
+    clock.mono += 1;
+    expect(slots.currentSlot()).to.equal(4);
+    expect(slots.msUntilSlot(5)).to.equal(10_000);
+  });
+
+  it("only signs well inside the slot", () => {
+    const { clock, opts } = fakeClock(100_000);
+    const slots = new SlotClock({
+      ...opts,
+      genesisTime: 100,
+      slotDurationSecs: 10,
+      maxClockSkewMs: 500,
+    });
+    expect(slots.canSign(0)).to.be.false;
+    clock.mono += 500;
+    expect(slots.canSign(0)).to.be.true;
+    clock.mono += 9_000;
+    expect(slots.canSign(0)).to.be.false;
+    expect(slots.canSign(1)).to.be.false;
+  });
+
+  it("ignores wall clock steps until resync", () => {
+    const { clock, opts } = fakeClock(100_000);
+    const slots = new SlotClock({
+      ...opts,
+      genesisTime: 100,
+      slotDurationSecs: 10,
+      maxClockSkewMs: 500,
+    });
+    clock.mono += 5_000;
+    clock.wall += 60_000;
+    expect(slots.currentSlot()).to.equal(0);
+
+    expect(slots.resync()).to.equal(55_000);
+    expect(slots.isReliable()).to.be.false;
+    expect(slots.canSign(slots.currentSlot())).to.be.false;
+
+    clock.mono += 1_000;
+    clock.wall += 1_100;
+    expect(slots.resync()).to.equal(100);
+    expect(slots.isReliable()).to.be.true;
+    expect(slots.canSign(slots.currentSlot())).to.be.true;
+  });
+
+  it("follows the network block time", () => {
+    const { opts } = fakeClock(0);
+    const slots = networkClock(getNetwork(FilecoinCalibnet), 0, opts);
+    expect(slots.slotDurationMs).to.equal(30_000);
+  });
+});
@@ -0,0 +1,49 @@
+import { expect } from "aegir/chai";
+import {
+  ConfidenceTracker,
+  confidence,
+  samplesFor,
+  withheld,
+} from "../src/confidence.js";
+import type { Coding } from "../src/confidence.js";
+
+const coding: Coding = { kind: "1d", shards: 8, dataShards: 4 };
+
+describe("availability confidence", () => {
+  it("follows sampling without replacement", () => {
+    expect(withheld(coding)).to.equal(5);
+    expect(confidence(0, coding)).to.equal(0);
+    expect(confidence(1, coding)).to.be.closeTo(5 / 8, 1e-12);
+    expect(confidence(2, coding)).to.be.closeTo(1 - 6 / 56, 1e-12);
+    expect(confidence(3, coding)).to.be.closeTo(1 - 1 / 56, 1e-12);
+    // more shards than the adversary serves
+    expect(confidence(4, coding)).to.equal(1);
+  });
+
+  it("withholds (w + 1)^2 shards of the 2d square", () => {
+    const square: Coding = { kind: "2d", width: 16 };
+    expect(withheld(square)).to.equal(17 * 17);
+    expect(samplesFor(0.99, square)).to.equal(14);
+  });
+
+  it("derives the sample count from the target", () => {
+    expect(samplesFor(0.5, coding)).to.equal(1);
+    expect(samplesFor(0.9, coding)).to.equal(3);
+    expect(() => samplesFor(2, coding)).to.throw();
+  });
+
+  it("tracks distinct samples per commitment", () => {
+    const tracker = new ConfidenceTracker(coding, 0.9);
+    expect(tracker.sampleCount).to.equal(3);
+    tracker.record("a", 1);
+    tracker.record("a", 1);
+    tracker.record("a", 6);
+    tracker.record("b", 2);
+    expect(tracker.confidence("a")).to.equal(confidence(2, coding));
+    expect(tracker.confidence("b")).to.equal(confidence(1, coding));
+    expect(tracker.remaining("a")).to.equal(1);
+    expect(() => tracker.record("a", 8)).to.throw();
+    tracker.forget("a");
+    expect(tracker.confidence("a")).to.equal(0);
+  });
+});
@@ -0,0 +1,89 @@
+import { expect } from "aegir/chai";
+import {
+  baseFeeTracking,
+  costCap,
+  PercentilePremium,
+  FeeDeferredError,
+} from "../src/fees.js";
+import {
+  estimateMessageGas,
+  send,
+  serializeSignedMessage,
+  signMessage,
+  signedMessagePremium,
+} from "../src/messages.js";
+import { toPublic } from "../src/signer.js";
+import type { BlockHeader } from "../src/chainExchange.js";
+
+describe("fees", () => {
+  it("tracks the base fee", () => {
+    const fee = baseFeeTracking({ premium: 10n, epochs: 0 }).estimate(
+      1000n,
+      100
+    );
+    expect(fee).to.deep.equal({ gasFeeCap: 1010n, gasPremium: 10n });
+  });
+
+  it("targets a percentile of observed premiums", () => {
+    const strategy = new PercentilePremium(50, 3);
+    expect(strategy.premium()).to.equal(150_000n);
+    for (const premium of [200_000n, 900_000n, 300_000n, 400_000n]) {
+      strategy.observe(premium);
+    }
+    // 200_000 fell out of the window
+    expect(strategy.premium()).to.equal(400_000n);
+    expect(strategy.estimate(1000n)).to.deep.equal({
+      gasFeeCap: 402_000n,
+      gasPremium: 400_000n,
+    });
+  });
+
+  it("defers when over budget", () => {
+    const strategy = costCap(
+      baseFeeTracking({ premium: 0n, epochs: 0 }),
+      1000n
+    );
+    expect(strategy.estimate(10n, 100)).to.not.be.null;
+    expect(strategy.estimate(11n, 100)).to.be.null;
+  });
+
+  it("fills missing message fees from the strategy", () => {
+    const head = { parentBaseFee: new Uint8Array([0, 100]) } as BlockHeader;
+    const msg = estimateMessageGas(
+      send({ amount: "1", to: "t1izccwid4h3svp5sl2xow6jhuc72qmznv6gkbecq" }),
+      head,
+      baseFeeTracking({ premium: 5n, epochs: 0 })
+    );
+    expect(msg.gasPremium).to.equal("5");
+    expect(msg.gasFeeCap).to.equal("105");
+
+    expect(() =>
+      estimateMessageGas(
+        send({ amount: "1", to: "t1izccwid4h3svp5sl2xow6jhuc72qmznv6gkbecq" }),
+        head,
+        costCap(baseFeeTracking(), 1n)
+      )
+    ).to.throw(FeeDeferredError);
+  });
+
+  it("observes the premium of gossiped messages", () => {
+    const key = toPublic("8EkrelmXXqGwOqnSzPK19VPNo8X2ibvap2sVcF5AZtg=");
+    const msg = {
+      ...send({
+        amount: "1",
+        from: key.addr,
+        to: "t1izccwid4h3svp5sl2xow6jhuc72qmznv6gkbecq",
+      }),
+      gasLimit: 1000,
+      gasFeeCap: "900000",
+      gasPremium: "700000",
+    };
+    const data = serializeSignedMessage(signMessage(msg, key.priv));
+    expect(signedMessagePremium(data)).to.equal(700_000n);
+
+    // through the cost cap to the strategy it wraps
+    const inner = new PercentilePremium(50, 3);
+    costCap(inner, 1n << 64n).observe!(signedMessagePremium(data));
+    expect(inner.premium()).to.equal(700_000n);
+  });
+});
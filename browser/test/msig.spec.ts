@@ -0,0 +1,45 @@
+import { expect } from "aegir/chai";
+import { decode } from "@ipld/dag-cbor";
+import { toHex } from "multiformats/bytes";
+import { approve, propose, MultisigMethod } from "../src/msig.js";
+import { send, paramsToBytes, toStorageBlock } from "../src/messages.js";
+import { addressToBytes } from "../src/signer.js";
+
+describe("msig", () => {
+  const to = "t1izccwid4h3svp5sl2xow6jhuc72qmznv6gkbecq";
+
+  it("encodes id addresses", () => {
+    expect(toHex(addressToBytes("t01000"))).to.equal("00e807");
+    expect(toHex(addressToBytes("t00"))).to.equal("0000");
+  });
+
+  it("wraps a message in a proposal", () => {
+    const msg = propose("t01000", send({ amount: "12", to, nonce: 3 }));
+    expect(msg.to).to.equal("t01000");
+    expect(msg.value).to.equal("0");
+    expect(msg.nonce).to.equal(3);
+    expect(msg.method).to.equal(MultisigMethod.Propose);
+
+    const [target, value, method, params] = decode(
+      paramsToBytes(msg.params)
+    ) as [Uint8Array, Uint8Array, number, Uint8Array];
+    expect(toHex(target)).to.equal(toHex(addressToBytes(to)));
+    expect(toHex(value)).to.equal("000c");
+    expect(method).to.equal(0);
+    expect(params.length).to.equal(0);
+
+    // params end up in the signed message
+    expect(() => toStorageBlock(msg)).to.not.throw();
+  });
+
+  it("approves a pending transaction", () => {
+    const msg = approve("t01000", 7);
+    expect(msg.method).to.equal(MultisigMethod.Approve);
+    const [txnId, hash] = decode(paramsToBytes(msg.params)) as [
+      number,
+      Uint8Array
+    ];
+    expect(txnId).to.equal(7);
+    expect(hash.length).to.equal(0);
+  });
+});
@@ -0,0 +1,44 @@
+import { expect } from "aegir/chai";
+import {
+  getNetwork,
+  FilecoinMainnet,
+  FilecoinCalibnet,
+} from "../src/networks.js";
+import { toPublic } from "../src/signer.js";
+
+describe("networks", () => {
+  it("derives topics from the network name", () => {
+    const net = getNetwork(FilecoinCalibnet);
+    expect(net.topics.blocks).to.equal("/fil/blocks/calibrationnet");
+    expect(net.topics.messages).to.equal("/fil/msgs/calibrationnet");
+  });
+
+  it("uses mainnet addresses on mainnet only", () => {
+    const priv_key = "8EkrelmXXqGwOqnSzPK19VPNo8X2ibvap2sVcF5AZtg=";
+    expect(
+      toPublic(priv_key, getNetwork(FilecoinMainnet).addressNetwork).addr
+    ).to.equal("f1izccwid4h3svp5sl2xow6jhuc72qmznv6gkbecq");
+    expect(
+      toPublic(priv_key, getNetwork(FilecoinCalibnet).addressNetwork).addr
+    ).to.equal("t1izccwid4h3svp5sl2xow6jhuc72qmznv6gkbecq");
+  });
+
+  it("prefixes built-in actor addresses for the network", () => {
+    expect(getNetwork(FilecoinMainnet).actors.power).to.equal("f04");
+    expect(getNetwork(FilecoinCalibnet).actors.burntFunds).to.equal("t099");
+  });
+
+  it("leaves deployment parameters unset until they are known", () => {
+    const net = getNetwork(FilecoinMainnet);
+    expect(net.genesis).to.be.undefined;
+    expect(net.contracts).to.be.undefined;
+    expect(net.srsDigests).to.be.undefined;
+  });
+
+  it("falls back to a local profile for unknown networks", () => {
+    const net = getNetwork("localnet-1234");
+    expect(net.networkName).to.equal("localnet-1234");
+    expect(net.blockDelaySecs).to.equal(4);
+    expect(net.bootstrappers).to.deep.equal([]);
+  });
+});
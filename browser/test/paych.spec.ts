@@ -0,0 +1,61 @@
+import { expect } from "aegir/chai";
+import { decode } from "@ipld/dag-cbor";
+import { toHex } from "multiformats/bytes";
+import {
+  LanePayer,
+  PaychMethod,
+  serializeVoucher,
+  updateChannelState,
+  voucherSigningBytes,
+} from "../src/paych.js";
+import { paramsToBytes } from "../src/messages.js";
+import { sign, toPublic } from "../src/signer.js";
+import { localSigner } from "../src/wallet.js";
+
+describe("paych", () => {
+  const key = toPublic("8EkrelmXXqGwOqnSzPK19VPNo8X2ibvap2sVcF5AZtg=");
+  const channel = "t01234";
+
+  it("issues cumulative vouchers per chunk", async () => {
+    const payer = new LanePayer(channel, 0, localSigner(key));
+    await payer.pay(10n);
+    const voucher = await payer.pay(5n);
+    expect(voucher.nonce).to.equal(2);
+    expect(voucher.amount).to.equal("15");
+    expect(payer.total).to.equal(15n);
+    expect(toHex(voucher.signature as Uint8Array)).to.equal(
+      toHex(sign(key.priv, voucherSigningBytes(voucher)))
+    );
+  });
+
+  it("signs the voucher without its signature", async () => {
+    const payer = new LanePayer(channel, 3, localSigner(key));
+    const voucher = await payer.pay(1n);
+    const unsigned = decode(voucherSigningBytes(voucher)) as unknown[];
+    const signed = decode(serializeVoucher(voucher)) as unknown[];
+    expect(unsigned.length).to.equal(11);
+    expect(unsigned[10]).to.equal(null);
+    expect(unsigned[5]).to.equal(3);
+    expect((signed[10] as Uint8Array)[0]).to.equal(1);
+    expect(signed.slice(0, 10)).to.deep.equal(unsigned.slice(0, 10));
+  });
+
+  it("redeems a voucher on chain", async () => {
+    const voucher = await new LanePayer(channel, 0, localSigner(key)).pay(7n);
+    const msg = updateChannelState(voucher);
+    expect(msg.to).to.equal(channel);
+    expect(msg.method).to.equal(PaychMethod.UpdateChannelState);
+    const [sv, secret] = decode(paramsToBytes(msg.params)) as [
+      unknown[],
+      Uint8Array
+    ];
+    expect(toHex(sv[7] as Uint8Array)).to.equal("0007");
+    expect(secret.length).to.equal(0);
+  });
+
+  it("rejects unsigned vouchers", () => {
+    expect(() =>
+      serializeVoucher({ channel, lane: 0, nonce: 1, amount: "1" })
+    ).to.throw("voucher isn't signed");
+  });
+});
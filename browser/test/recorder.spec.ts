@@ -0,0 +1,30 @@
+import { expect } from "aegir/chai";
+import { encode } from "@ipld/dag-cbor";
+import { toHex } from "multiformats/bytes";
+import {
+  GossipRecorder,
+  decodeRecords,
+  encodeRecords,
+} from "../src/recorder.js";
+
+describe("recorder", () => {
+  it("round trips a recording", () => {
+    const recorder = new GossipRecorder();
+    recorder.record("/fil/blocks/calibnet", "12D3KooW", new Uint8Array([1, 2]));
+    recorder.record("/fil/msgs/calibnet", "", new Uint8Array());
+
+    const records = decodeRecords(recorder.encode());
+    expect(records.length).to.equal(2);
+    expect(records[0].topic).to.equal("/fil/blocks/calibnet");
+    expect(records[0].from).to.equal("12D3KooW");
+    expect(toHex(records[0].data)).to.equal("0102");
+    expect(records[1].time).to.be.at.least(records[0].time);
+    expect(toHex(encodeRecords(records))).to.equal(toHex(recorder.encode()));
+  });
+
+  it("rejects other versions", () => {
+    expect(() => decodeRecords(encode([2, []]))).to.throw(
+      "unsupported recording version 2"
+    );
+  });
+});
@@ -0,0 +1,71 @@
+import { expect } from "aegir/chai";
+import { base64pad } from "multiformats/bases/base64";
+import { toHex } from "multiformats/bytes";
+import {
+  localSigner,
+  lotusWalletSigner,
+  signerFor,
+} from "../src/wallet.js";
+import { signMessage, signMessageWith, send } from "../src/messages.js";
+import { toPublic } from "../src/signer.js";
+
+describe("wallet", () => {
+  const key = toPublic("8EkrelmXXqGwOqnSzPK19VPNo8X2ibvap2sVcF5AZtg=");
+  const msg = () => ({
+    ...send({ amount: "12", to: "t15ihq5ibzwki2b4ep2f46avlkrqzhpqgtga7pdrq" }),
+    from: key.addr,
+  });
+
+  it("signs locally like signMessage", async () => {
+    const smsg = await signMessageWith(msg(), localSigner(key));
+    expect(toHex(smsg.signature)).to.equal(
+      toHex(signMessage(msg(), key.priv).signature)
+    );
+  });
+
+  it("signs through the Lotus wallet API", async () => {
+    const fetch = globalThis.fetch;
+    let request: any;
+    // @ts-ignore
+    globalThis.fetch = async (url: string, init: any) => {
+      request = { url, init, body: JSON.parse(init.body) };
+      return {
+        ok: true,
+        json: async () => ({
+          result: { Type: 1, Data: base64pad.baseEncode(new Uint8Array(65)) },
+        }),
+      };
+    };
+    try {
+      const signer = lotusWalletSigner({
+        url: "http://127.0.0.1:1234/rpc/v0",
+        token: "secret",
+        address: key.addr,
+      });
+      const smsg = await signMessageWith(msg(), signer);
+      expect(smsg.signature.length).to.equal(65);
+      expect(request.init.headers.Authorization).to.equal("Bearer secret");
+      expect(request.body.method).to.equal("Filecoin.WalletSign");
+      expect(request.body.params[0]).to.equal(key.addr);
+      expect(request.body.params[1]).to.equal(
+        base64pad.baseEncode(smsg.cid.bytes)
+      );
+    } finally {
+      globalThis.fetch = fetch;
+    }
+  });
+
+  it("picks the signer of the sender", () => {
+    const other = toPublic("p8yTrUZifp1SF3wamVKR8whzQ9MO9IKJ9mWBpjRuEHw=");
+    const signers = new Map(
+      [key, other].map((key) => [key.addr, localSigner(key)])
+    );
+    expect(signerFor(signers, other.addr).address).to.equal(other.addr);
+    expect(signerFor(signers, key.addr).address).to.equal(key.addr);
+    expect(signerFor(signers, "").address).to.equal(key.addr);
+    expect(() => signerFor(new Map(), "")).to.throw("message has no sender");
+    expect(() =>
+      signerFor(signers, "t15ihq5ibzwki2b4ep2f46avlkrqzhpqgtga7pdrq")
+    ).to.throw("no signer for t15ihq5ibzwki2b4ep2f46avlkrqzhpqgtga7pdrq");
+  });
+});
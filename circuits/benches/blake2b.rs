@@ -0,0 +1,53 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use halo2_proofs::poly::{commitment::ParamsProver, kzg::commitment::ParamsKZG};
+use halo2curves::bn256::Bn256;
+use quarry_circuits::blake2b::{self, block, initial_state, CompressionCircuit};
+use quarry_circuits::proof::{keygen, prove, verify};
+use quarry_circuits::report;
+use rand::rngs::OsRng;
+
+// Proving cost of one compression, the unit a Blake2b transcript is
+// replayed in.
+//
+//     cargo bench --bench blake2b --features blake2b
+fn bench_compression(c: &mut Criterion) {
+    let h = initial_state(64, *b"Halo2-Transcript");
+    let m = block(&[0xa5; 128]);
+    let circuit = CompressionCircuit::new(h, m, 128, true);
+    let instances = blake2b::instances(h, m, 128, true);
+
+    // Most of the rows are the byte table, the lookups of the compression
+    // come on top.
+    let constraints = report::report(&circuit).expect("synthesis should not fail");
+    println!(
+        "compression: {} rows, k = {}",
+        constraints.rows, constraints.min_k
+    );
+
+    let params: ParamsKZG<Bn256> = ParamsKZG::new(constraints.min_k);
+    let pk = keygen(&params, &circuit).expect("keygen should not fail");
+    let mut rng = OsRng;
+
+    c.bench_function("blake2b-compression-prover", |b| {
+        b.iter(|| {
+            prove(&params, &pk, circuit.clone(), &instances, &mut rng)
+                .expect("proof generation should not fail")
+        })
+    });
+
+    let proof = prove(&params, &pk, circuit.clone(), &instances, &mut rng)
+        .expect("proof generation should not fail");
+
+    c.bench_function("blake2b-compression-verifier", |b| {
+        b.iter(|| {
+            assert!(verify(&params, pk.get_vk(), &instances, &proof).is_ok());
+        });
+    });
+}
+
+criterion_group!(
+    name = blake2b;
+    config = Criterion::default().sample_size(10);
+    targets = bench_compression
+);
+criterion_main!(blake2b);
@@ -1,183 +1,27 @@
 use criterion::{criterion_group, criterion_main, Criterion};
-use ecc::{AssignedPoint, EccConfig, GeneralEccChip};
+use ecc::GeneralEccChip;
 use ff::Field;
 use halo2_proofs::{
     arithmetic::{CurveAffine, FieldExt},
     circuit::{Layouter, SimpleFloorPlanner, Value},
-    plonk::{create_proof, keygen_pk, keygen_vk, verify_proof, Circuit, ConstraintSystem, Error},
-    poly::{
-        commitment::ParamsProver,
-        kzg::{
-            commitment::{KZGCommitmentScheme, ParamsKZG},
-            multiopen::{ProverGWC, VerifierGWC},
-            strategy::SingleStrategy,
-        },
-    },
-    transcript::{
-        Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
-    },
+    plonk::{Circuit, ConstraintSystem, Error},
+    poly::{commitment::ParamsProver, kzg::commitment::ParamsKZG},
 };
-use halo2curves::bn256::{Bn256, G1Affine};
+use halo2curves::bn256::{Bn256, Fr};
 use halo2curves::group::{Curve, Group};
-use integer::{
-    rns::Integer, AssignedInteger, IntegerChip, IntegerConfig, IntegerInstructions, Range,
-};
-use maingate::{
-    big_to_fe, fe_to_big, MainGate, MainGateConfig, RangeChip, RangeConfig, RangeInstructions,
-    RegionCtx,
+use integer::{IntegerInstructions, Range};
+use maingate::RegionCtx;
+use quarry_circuits::ecdsa::{
+    mod_n, AssignedEcdsaSig, AssignedPublicKey, EcdsaChip, EcdsaConfig, BIT_LEN_LIMB, MUL_PAIRS,
+    NUMBER_OF_LIMBS,
 };
+use quarry_circuits::proof::{prove, verify};
+use quarry_circuits::report;
+use quarry_circuits::tune::{tune_window_size, WINDOW_SIZES};
 use rand::rngs::OsRng;
 
-const BIT_LEN_LIMB: usize = 68;
-const NUMBER_OF_LIMBS: usize = 4;
 const K: u32 = 18;
 
-#[derive(Clone, Debug)]
-pub struct EcdsaConfig {
-    main_gate_config: MainGateConfig,
-    range_config: RangeConfig,
-}
-
-impl EcdsaConfig {
-    pub fn new(range_config: RangeConfig, main_gate_config: MainGateConfig) -> Self {
-        Self {
-            range_config,
-            main_gate_config,
-        }
-    }
-
-    pub fn ecc_chip_config(&self) -> EccConfig {
-        EccConfig::new(self.range_config.clone(), self.main_gate_config.clone())
-    }
-
-    pub fn integer_chip_config(&self) -> IntegerConfig {
-        IntegerConfig::new(self.range_config.clone(), self.main_gate_config.clone())
-    }
-}
-
-#[derive(Clone, Debug)]
-pub struct EcdsaSig<
-    W: FieldExt,
-    N: FieldExt,
-    const NUMBER_OF_LIMBS: usize,
-    const BIT_LEN_LIMB: usize,
-> {
-    pub r: Integer<W, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>,
-    pub s: Integer<W, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>,
-}
-
-pub struct AssignedEcdsaSig<
-    W: FieldExt,
-    N: FieldExt,
-    const NUMBER_OF_LIMBS: usize,
-    const BIT_LEN_LIMB: usize,
-> {
-    pub r: AssignedInteger<W, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>,
-    pub s: AssignedInteger<W, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>,
-}
-
-pub struct AssignedPublicKey<
-    W: FieldExt,
-    N: FieldExt,
-    const NUMBER_OF_LIMBS: usize,
-    const BIT_LEN_LIMB: usize,
-> {
-    pub point: AssignedPoint<W, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>,
-}
-
-pub struct EcdsaChip<
-    E: CurveAffine,
-    N: FieldExt,
-    const NUMBER_OF_LIMBS: usize,
-    const BIT_LEN_LIMB: usize,
->(GeneralEccChip<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>);
-
-impl<E: CurveAffine, N: FieldExt, const NUMBER_OF_LIMBS: usize, const BIT_LEN_LIMB: usize>
-    EcdsaChip<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>
-{
-    pub fn new(ecc_chip: GeneralEccChip<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>) -> Self {
-        Self(ecc_chip)
-    }
-
-    pub fn scalar_field_chip(
-        &self,
-    ) -> &IntegerChip<E::ScalarExt, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB> {
-        self.0.scalar_field_chip()
-    }
-
-    fn ecc_chip(&self) -> GeneralEccChip<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB> {
-        self.0.clone()
-    }
-}
-
-impl<E: CurveAffine, N: FieldExt, const NUMBER_OF_LIMBS: usize, const BIT_LEN_LIMB: usize>
-    EcdsaChip<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>
-{
-    pub fn verify(
-        &self,
-        ctx: &mut RegionCtx<'_, N>,
-        sig: &AssignedEcdsaSig<E::Scalar, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>,
-        pk: &AssignedPublicKey<E::Base, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>,
-        msg_hash: &AssignedInteger<E::Scalar, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>,
-    ) -> Result<(), Error> {
-        let ecc_chip = self.ecc_chip();
-        let scalar_chip = ecc_chip.scalar_field_chip();
-        let base_chip = ecc_chip.base_field_chip();
-
-        // 1. check 0 < r, s < n
-
-        // since `assert_not_zero` already includes a in-field check, we can just
-        // call `assert_not_zero`
-        scalar_chip.assert_not_zero(ctx, &sig.r)?;
-        scalar_chip.assert_not_zero(ctx, &sig.s)?;
-
-        // 2. w = s^(-1) (mod n)
-        let (s_inv, _) = scalar_chip.invert(ctx, &sig.s)?;
-
-        // 3. u1 = m' * w (mod n)
-        let u1 = scalar_chip.mul(ctx, msg_hash, &s_inv)?;
-
-        // 4. u2 = r * w (mod n)
-        let u2 = scalar_chip.mul(ctx, &sig.r, &s_inv)?;
-
-        // 5. compute Q = u1*G + u2*pk
-        let e_gen = ecc_chip.assign_point(ctx, Value::known(E::generator()))?;
-        let g1 = ecc_chip.mul(ctx, &e_gen, &u1, 2)?;
-        let g2 = ecc_chip.mul(ctx, &pk.point, &u2, 2)?;
-        let q = ecc_chip.add(ctx, &g1, &g2)?;
-
-        // 6. reduce q_x in E::ScalarExt
-        // assuming E::Base/E::ScalarExt have the same number of limbs
-        let q_x = q.x();
-        let q_x_reduced_in_q = base_chip.reduce(ctx, q_x)?;
-        let q_x_reduced_in_r = scalar_chip.reduce_external(ctx, &q_x_reduced_in_q)?;
-
-        // 7. check if Q.x == r (mod n)
-        scalar_chip.assert_strict_equal(ctx, &q_x_reduced_in_r, &sig.r)?;
-
-        Ok(())
-    }
-}
-
-#[derive(Clone, Debug)]
-struct EcdsaVerifyConfig {
-    pub main_gate_config: MainGateConfig,
-    range_config: RangeConfig,
-}
-
-impl EcdsaVerifyConfig {
-    pub fn ecc_chip_config(&self) -> EccConfig {
-        EccConfig::new(self.range_config.clone(), self.main_gate_config.clone())
-    }
-
-    pub fn config_range<N: FieldExt>(&self, layouter: &mut impl Layouter<N>) -> Result<(), Error> {
-        let range_chip = RangeChip::<N>::new(self.range_config.clone());
-        range_chip.load_table(layouter)?;
-
-        Ok(())
-    }
-}
-
 #[derive(Default, Clone, Copy)]
 struct EcdsaVerifyCircuit<E: CurveAffine> {
     public_key: Value<E>,
@@ -188,7 +32,7 @@ struct EcdsaVerifyCircuit<E: CurveAffine> {
 }
 
 impl<E: CurveAffine, N: FieldExt> Circuit<N> for EcdsaVerifyCircuit<E> {
-    type Config = EcdsaVerifyConfig;
+    type Config = EcdsaConfig;
     type FloorPlanner = SimpleFloorPlanner;
 
     fn without_witnesses(&self) -> Self {
@@ -196,23 +40,7 @@ impl<E: CurveAffine, N: FieldExt> Circuit<N> for EcdsaVerifyCircuit<E> {
     }
 
     fn configure(meta: &mut ConstraintSystem<N>) -> Self::Config {
-        let (rns_base, rns_scalar) = GeneralEccChip::<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>::rns();
-        let main_gate_config = MainGate::<N>::configure(meta);
-        let mut overflow_bit_lens: Vec<usize> = vec![];
-        overflow_bit_lens.extend(rns_base.overflow_lengths());
-        overflow_bit_lens.extend(rns_scalar.overflow_lengths());
-        let composition_bit_lens = vec![BIT_LEN_LIMB / NUMBER_OF_LIMBS];
-
-        let range_config = RangeChip::<N>::configure(
-            meta,
-            &main_gate_config,
-            composition_bit_lens,
-            overflow_bit_lens,
-        );
-        EcdsaVerifyConfig {
-            main_gate_config,
-            range_config,
-        }
+        EcdsaConfig::configure::<E, N>(meta)
     }
 
     fn synthesize(
@@ -230,12 +58,12 @@ impl<E: CurveAffine, N: FieldExt> Circuit<N> for EcdsaVerifyCircuit<E> {
                 let ctx = &mut RegionCtx::new(region, offset);
 
                 ecc_chip.assign_aux_generator(ctx, Value::known(self.aux_generator))?;
-                ecc_chip.assign_aux(ctx, self.window_size, 1)?;
+                ecc_chip.assign_aux(ctx, self.window_size, MUL_PAIRS)?;
                 Ok(())
             },
         )?;
 
-        let ecdsa_chip = EcdsaChip::new(ecc_chip.clone());
+        let ecdsa_chip = EcdsaChip::new(ecc_chip.clone(), self.window_size);
         let scalar_chip = ecc_chip.scalar_field_chip();
         let offset = 0;
 
@@ -280,10 +108,6 @@ impl<E: CurveAffine, N: FieldExt> Circuit<N> for EcdsaVerifyCircuit<E> {
     }
 }
 
-fn mod_n<C: CurveAffine>(x: C::Base) -> C::Scalar {
-    let x_big = fe_to_big(x);
-    big_to_fe(x_big)
-}
 fn run<C: CurveAffine>(c: &mut Criterion, scheme: &str) {
     let g = C::generator();
 
@@ -325,65 +149,58 @@ fn run<C: CurveAffine>(c: &mut Criterion, scheme: &str) {
 
     let aux_generator = C::CurveExt::random(OsRng).to_affine();
 
-    let empty_circuit = EcdsaVerifyCircuit::<C> {
-        public_key: Value::unknown(),
-        signature: Value::unknown(),
-        msg_hash: Value::unknown(),
+    let circuit = |window_size| EcdsaVerifyCircuit::<C> {
+        public_key: Value::known(public_key),
+        signature: Value::known((r, s)),
+        msg_hash: Value::known(msg_hash),
         aux_generator,
-        window_size: 2,
-        ..Default::default()
+        window_size,
     };
 
     // Initialize the polynomial commitment parameters
     let params: ParamsKZG<Bn256> = ParamsKZG::new(K);
 
-    // Initialize the proving key
-    let vk = keygen_vk(&params, &empty_circuit).expect("keygen_vk should not fail");
-    let pk = keygen_pk(&params, vk, &empty_circuit).expect("keygen_pk should not fail");
+    // Initialize the proving key at the window size proving fastest
+    let mut rng = OsRng;
+    let choice = tune_window_size(&params, WINDOW_SIZES, 3, &circuit, &[], &mut rng)
+        .expect("some window size should fit");
+    let pk = choice.pk;
+    let circuit = circuit(choice.window_size);
+
+    // Keep an eye on the size of the circuit next to its timings.
+    let constraints = report::report::<_, Fr>(&Circuit::<Fr>::without_witnesses(&circuit))
+        .expect("synthesis should not fail");
+    println!(
+        "{}: window size {}, {} rows, {} copies, {} gates",
+        scheme,
+        choice.window_size,
+        constraints.rows,
+        constraints.copies,
+        constraints.gates.len()
+    );
+    assert!(
+        constraints.min_k <= K,
+        "{} needs k = {}",
+        scheme,
+        constraints.min_k
+    );
 
     let prover_name = scheme.to_string() + "-prover";
     let verifier_name = scheme.to_string() + "-verifier";
-    let mut rng = OsRng;
-    let circuit = EcdsaVerifyCircuit::<C> {
-        public_key: Value::known(public_key),
-        signature: Value::known((r, s)),
-        msg_hash: Value::known(msg_hash),
-        aux_generator,
-        window_size: 2,
-        ..Default::default()
-    };
-
-    // Create a proof
-    let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
 
     c.bench_function(&prover_name, |b| {
         b.iter(|| {
-            create_proof::<KZGCommitmentScheme<_>, ProverGWC<_>, _, _, _, _>(
-                &params,
-                &pk,
-                &[circuit],
-                &[&[&[]]],
-                &mut rng,
-                &mut transcript,
-            )
-            .expect("proof generation should not fail")
+            prove(&params, &pk, circuit, &[], &mut rng).expect("proof generation should not fail")
         })
     });
 
-    let proof = transcript.finalize();
+    // Create a proof
+    let proof =
+        prove(&params, &pk, circuit, &[], &mut rng).expect("proof generation should not fail");
 
     c.bench_function(&verifier_name, |b| {
         b.iter(|| {
-            let strategy = SingleStrategy::new(&params);
-            let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
-            assert!(verify_proof::<_, VerifierGWC<_>, _, _, _>(
-                &params,
-                pk.get_vk(),
-                strategy,
-                &[&[&[]]],
-                &mut transcript
-            )
-            .is_ok());
+            assert!(verify(&params, pk.get_vk(), &[], &proof).is_ok());
         });
     });
 }
@@ -0,0 +1,66 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use ff::Field;
+use halo2_proofs::{
+    circuit::Value,
+    poly::{commitment::ParamsProver, kzg::commitment::ParamsKZG},
+};
+use halo2curves::bn256::{Bn256, Fr};
+use quarry_circuits::erasure::{self, EncodingCircuit};
+use quarry_circuits::proof::{keygen, prove, verify};
+use quarry_circuits::report;
+use rand::rngs::OsRng;
+
+// Proving cost of the encoding proof against the blob size, at rate 1/2.
+fn bench_encoding<const DATA: usize, const SHARDS: usize>(c: &mut Criterion) {
+    let empty_circuit = EncodingCircuit::<DATA, SHARDS> {
+        data: Value::unknown(),
+    };
+
+    // The rows grow with DATA * SHARDS, so the size matters more than the
+    // timings.
+    let constraints = report::report(&empty_circuit).expect("synthesis should not fail");
+    println!(
+        "{} elements: {} rows, k = {}",
+        DATA, constraints.rows, constraints.min_k
+    );
+
+    let params: ParamsKZG<Bn256> = ParamsKZG::new(constraints.min_k);
+    let pk = keygen(&params, &empty_circuit).expect("keygen should not fail");
+
+    let mut rng = OsRng;
+    let data: [Fr; DATA] = [(); DATA].map(|_| Fr::random(rng));
+    let instances = erasure::instances(&data, SHARDS);
+    let circuit = EncodingCircuit::<DATA, SHARDS>::new(data);
+
+    let prover_name = format!("encoding-{}-prover", DATA);
+    let verifier_name = format!("encoding-{}-verifier", DATA);
+
+    c.bench_function(&prover_name, |b| {
+        b.iter(|| {
+            prove(&params, &pk, circuit, &instances, &mut rng)
+                .expect("proof generation should not fail")
+        })
+    });
+
+    let proof = prove(&params, &pk, circuit, &instances, &mut rng)
+        .expect("proof generation should not fail");
+
+    c.bench_function(&verifier_name, |b| {
+        b.iter(|| {
+            assert!(verify(&params, pk.get_vk(), &instances, &proof).is_ok());
+        });
+    });
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    bench_encoding::<4, 8>(c);
+    bench_encoding::<16, 32>(c);
+    bench_encoding::<64, 128>(c);
+}
+
+criterion_group!(
+    name = erasure;
+    config = Criterion::default().sample_size(10);
+    targets = criterion_benchmark
+);
+criterion_main!(erasure);
@@ -1,29 +1,18 @@
 use ff::Field;
 use halo2_proofs::{
     circuit::{Layouter, SimpleFloorPlanner, Value},
-    plonk::{
-        create_proof, keygen_pk, keygen_vk, verify_proof, Advice, Circuit, Column,
-        ConstraintSystem, Error, Instance,
-    },
-    poly::{
-        commitment::ParamsProver,
-        kzg::{
-            commitment::{KZGCommitmentScheme, ParamsKZG},
-            multiopen::{ProverGWC, VerifierGWC},
-            strategy::SingleStrategy,
-        },
-    },
-    transcript::{
-        Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
-    },
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance},
+    poly::{commitment::ParamsProver, kzg::commitment::ParamsKZG},
 };
-use halo2curves::bn256::{Bn256, Fr, G1Affine};
+use halo2curves::bn256::{Bn256, Fr};
 
 use criterion::{criterion_group, criterion_main, Criterion};
 use halo2_gadgets::poseidon::{
     primitives::{self as poseidon, ConstantLength, Spec},
     Hash, Pow5Chip, Pow5Config,
 };
+use quarry_circuits::poseidon::PoseidonSpec;
+use quarry_circuits::proof::{keygen, prove, verify};
 use rand::rngs::OsRng;
 use std::convert::TryInto;
 use std::marker::PhantomData;
@@ -117,27 +106,6 @@ where
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-struct MySpec<const WIDTH: usize, const RATE: usize>;
-
-impl<const WIDTH: usize, const RATE: usize> Spec<Fr, WIDTH, RATE> for MySpec<WIDTH, RATE> {
-    fn full_rounds() -> usize {
-        8
-    }
-
-    fn partial_rounds() -> usize {
-        56
-    }
-
-    fn sbox(val: Fr) -> Fr {
-        val.pow_vartime(&[5])
-    }
-
-    fn secure_mds() -> usize {
-        0
-    }
-}
-
 const K: u32 = 7;
 
 fn bench_poseidon<S, const WIDTH: usize, const RATE: usize>(name: &str, c: &mut Criterion)
@@ -153,8 +121,7 @@ where
     };
 
     // Initialize the proving key
-    let vk = keygen_vk(&params, &empty_circuit).expect("keygen_vk should not fail");
-    let pk = keygen_pk(&params, vk, &empty_circuit).expect("keygen_pk should not fail");
+    let pk = keygen(&params, &empty_circuit).expect("keygen should not fail");
 
     let prover_name = name.to_string() + "-prover";
     let verifier_name = name.to_string() + "-verifier";
@@ -172,46 +139,29 @@ where
         _spec: PhantomData,
     };
 
-    // Create a proof
-    let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
-
     c.bench_function(&prover_name, |b| {
         b.iter(|| {
-            create_proof::<KZGCommitmentScheme<_>, ProverGWC<_>, _, _, _, _>(
-                &params,
-                &pk,
-                &[circuit],
-                &[&[&[output]]],
-                &mut rng,
-                &mut transcript,
-            )
-            .expect("proof generation should not fail")
+            prove(&params, &pk, circuit, &[output], &mut rng)
+                .expect("proof generation should not fail")
         })
     });
 
-    let proof = transcript.finalize();
+    // Create a proof
+    let proof = prove(&params, &pk, circuit, &[output], &mut rng)
+        .expect("proof generation should not fail");
 
     c.bench_function(&verifier_name, |b| {
         b.iter(|| {
-            let strategy = SingleStrategy::new(&params);
-            let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
-            assert!(verify_proof::<_, VerifierGWC<_>, _, _, _>(
-                &params,
-                pk.get_vk(),
-                strategy,
-                &[&[&[output]]],
-                &mut transcript
-            )
-            .is_ok());
+            assert!(verify(&params, pk.get_vk(), &[output], &proof).is_ok());
         });
     });
 }
 
 fn criterion_benchmark(c: &mut Criterion) {
-    bench_poseidon::<MySpec<3, 2>, 3, 2>("WIDTH = 3, RATE = 2", c);
-    bench_poseidon::<MySpec<9, 8>, 9, 8>("WIDTH = 9, RATE = 8", c);
-    bench_poseidon::<MySpec<12, 11>, 12, 11>("WIDTH = 12, RATE = 11", c);
-    bench_poseidon::<MySpec<25, 24>, 25, 24>("WIDTH = 25, RATE = 24", c);
+    bench_poseidon::<PoseidonSpec<3, 2>, 3, 2>("WIDTH = 3, RATE = 2", c);
+    bench_poseidon::<PoseidonSpec<9, 8>, 9, 8>("WIDTH = 9, RATE = 8", c);
+    bench_poseidon::<PoseidonSpec<12, 11>, 12, 11>("WIDTH = 12, RATE = 11", c);
+    bench_poseidon::<PoseidonSpec<25, 24>, 25, 24>("WIDTH = 25, RATE = 24", c);
 }
 
 criterion_group!(
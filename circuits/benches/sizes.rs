@@ -0,0 +1,230 @@
+// Proof sizes of the production circuits, next to the timings of the other
+// benches. Sizes only depend on the shape of a circuit, so unlike timings
+// they can be compared across machines: `--record` writes them to
+// `sizes.baseline` and `--check` fails when one grew by more than
+// `THRESHOLD_PERCENT` over it or has no baseline.
+//
+//     cargo bench --bench sizes -- --check
+use ff::Field;
+use halo2_proofs::{
+    arithmetic::CurveAffine,
+    plonk::Circuit,
+    poly::{commitment::ParamsProver, kzg::commitment::ParamsKZG},
+};
+use halo2curves::bn256::{Bn256, Fr};
+use halo2curves::group::Curve;
+use halo2curves::secp256k1::Secp256k1Affine;
+use quarry_circuits::{
+    committee::{self, CommitteeCircuit},
+    compose::Composite,
+    ecdsa,
+    erasure::{self, EncodingCircuit},
+    mmr::{self, InclusionCircuit, Mmr},
+    policy::QuorumPolicy,
+    proof::{keygen, prove, verify},
+    randomness::{seed_commitment, CommitReveal},
+    report::report,
+    rollup::{Transition, TransitionCircuit},
+    secret::Secret,
+};
+use rand::{rngs::StdRng, SeedableRng};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+const THRESHOLD_PERCENT: usize = 5;
+
+fn proof_size<C: Circuit<Fr>>(circuit: C, instances: &[Fr], rng: &mut StdRng) -> usize {
+    let k = report(&circuit).expect("synthesis should not fail").min_k;
+    let params: ParamsKZG<Bn256> = ParamsKZG::new(k);
+    let pk = keygen(&params, &circuit.without_witnesses()).expect("keygen should not fail");
+    let proof = prove(&params, &pk, circuit, instances, &mut *rng)
+        .expect("proof generation should not fail");
+    verify(&params, pk.get_vk(), instances, &proof).expect("proof should verify");
+    proof.len()
+}
+
+fn committee(rng: &mut StdRng) -> usize {
+    type E = Secp256k1Affine;
+    const N_MAX: usize = 4;
+
+    let secrets = (0..3)
+        .map(|_| Secret::new(<E as CurveAffine>::ScalarExt::random(&mut *rng)))
+        .collect::<Vec<_>>();
+    let members = secrets
+        .iter()
+        .map(|sk| (E::generator() * sk.expose()).to_affine())
+        .collect::<Vec<_>>();
+    let msg_hash = <E as CurveAffine>::ScalarExt::from(7);
+    let prev_attestation = Fr::zero();
+    let active = [true, false, true];
+    let signatures = secrets
+        .iter()
+        .zip(active)
+        .map(|(sk, active)| active.then(|| ecdsa::sign::<E>(sk, msg_hash, &mut *rng)))
+        .collect::<Vec<_>>();
+    let policy = QuorumPolicy::k_of_n(2, 0..3);
+    let instances = committee::instances::<E, Fr, N_MAX>(
+        &members,
+        msg_hash,
+        prev_attestation,
+        &active,
+        &policy,
+        1,
+    );
+    let aux_generator =
+        (E::generator() * <E as CurveAffine>::ScalarExt::random(&mut *rng)).to_affine();
+    let circuit = CommitteeCircuit::<E, Fr, N_MAX>::new(
+        &members,
+        &signatures,
+        policy,
+        msg_hash,
+        prev_attestation,
+        1,
+        aux_generator,
+        2,
+        &mut *rng,
+    )
+    .expect("the committee fits the circuit");
+    proof_size(circuit, &instances, rng)
+}
+
+fn randomness(rng: &mut StdRng) -> usize {
+    const N: usize = 4;
+    let reveals = (0..N)
+        .map(|_| (Fr::random(&mut *rng), Fr::random(&mut *rng)))
+        .collect::<Vec<_>>();
+    let mut round = CommitReveal::new(N);
+    for (member, (seed, salt)) in reveals.iter().enumerate() {
+        round.commit(member, seed_commitment(*seed, *salt));
+    }
+    for (member, (seed, salt)) in reveals.iter().enumerate() {
+        round.reveal(member, *seed, *salt);
+    }
+    let instances = round.instances().unwrap();
+    proof_size(round.circuit::<N>().unwrap(), &instances, rng)
+}
+
+fn mmr_inclusion(rng: &mut StdRng) -> usize {
+    let mut mmr = Mmr::new();
+    for leaf in 1..=5 {
+        mmr.push(Fr::from(leaf));
+    }
+    let instances = mmr::layout()
+        .encode(&[("root", &[mmr.root().unwrap()]), ("leaf", &[Fr::from(3)])])
+        .unwrap();
+    proof_size(
+        InclusionCircuit::new(&mmr.prove(2), Fr::from(3)),
+        &instances,
+        rng,
+    )
+}
+
+fn rollup_transition(rng: &mut StdRng) -> usize {
+    let transition = Transition {
+        prev_state_root: Fr::from(1),
+        new_state_root: Fr::from(2),
+        batch_commitment: Fr::from(3),
+    };
+    proof_size(
+        Composite(TransitionCircuit::new(transition)),
+        &transition.instances(),
+        rng,
+    )
+}
+
+fn erasure_encoding(rng: &mut StdRng) -> usize {
+    let data = [(); 4].map(|_| Fr::random(&mut *rng));
+    let instances = erasure::instances(&data, 8);
+    proof_size(EncodingCircuit::<4, 8>::new(data), &instances, rng)
+}
+
+fn baseline_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("sizes.baseline")
+}
+
+fn read_baseline() -> BTreeMap<String, usize> {
+    let baseline = match fs::read_to_string(baseline_path()) {
+        Ok(baseline) => baseline,
+        Err(_) => return BTreeMap::new(),
+    };
+    baseline
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let (name, size) = line.split_once(' ').expect("baseline line has a name");
+            (
+                name.to_string(),
+                size.trim().parse().expect("size is a number"),
+            )
+        })
+        .collect()
+}
+
+fn write_baseline(sizes: &BTreeMap<String, usize>) {
+    let mut baseline =
+        "# Proof bytes, written by cargo bench --bench sizes -- --record\n".to_string();
+    for (name, size) in sizes {
+        baseline.push_str(&format!("{} {}\n", name, size));
+    }
+    fs::write(baseline_path(), baseline).unwrap();
+}
+
+fn main() {
+    // cargo passes --bench to every bench without the criterion harness.
+    let args = env::args()
+        .skip(1)
+        .filter(|arg| arg != "--bench")
+        .collect::<Vec<_>>();
+    let mode = match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        [] => None,
+        [mode @ ("--check" | "--record")] => Some(mode),
+        _ => {
+            eprintln!("usage: cargo bench --bench sizes -- [--check|--record]");
+            process::exit(2);
+        }
+    };
+
+    let mut rng = StdRng::seed_from_u64(692);
+    let sizes = [
+        ("committee", committee as fn(&mut StdRng) -> usize),
+        ("randomness", randomness),
+        ("mmr-inclusion", mmr_inclusion),
+        ("rollup-transition", rollup_transition),
+        ("erasure-encoding", erasure_encoding),
+    ]
+    .into_iter()
+    .map(|(name, size)| {
+        let size = size(&mut rng);
+        println!("{}: {} bytes", name, size);
+        (name.to_string(), size)
+    })
+    .collect::<BTreeMap<_, _>>();
+
+    match mode {
+        Some("--record") => write_baseline(&sizes),
+        Some(_) => {
+            let baseline = read_baseline();
+            let mut regressed = false;
+            for (name, &size) in sizes.iter() {
+                match baseline.get(name) {
+                    Some(&before) if size > before + before * THRESHOLD_PERCENT / 100 => {
+                        eprintln!("{}: proof grew from {} to {} bytes", name, before, size);
+                        regressed = true;
+                    }
+                    Some(_) => {}
+                    None => {
+                        eprintln!("{} has no baseline, record it with --record", name);
+                        regressed = true;
+                    }
+                }
+            }
+            if regressed {
+                process::exit(1);
+            }
+        }
+        None => {}
+    }
+}
@@ -0,0 +1,175 @@
+// The proving API over the two commitment schemes, so callers and tests can
+// take either through `Prover` and `Verifier` instead of the functions in
+// `proof` and `ipa`. A backend fixes the field circuits are over and the
+// types of its parameters and keys; circuits stay generic over the field and
+// never see the commitment scheme. `Kzg` and `Ipa` both wrap the halo2 fork
+// this crate builds against.
+use crate::error::QuarryError;
+#[cfg(feature = "kzg")]
+use crate::transcript::TranscriptKind;
+use halo2_proofs::{arithmetic::FieldExt, plonk::Circuit};
+use rand::RngCore;
+
+pub trait Backend {
+    type Field: FieldExt;
+    type Params;
+    type ProvingKey;
+    type VerifyingKey;
+}
+
+pub trait Prover: Backend {
+    // Generate the proving key of a circuit without witnesses.
+    fn keygen<C: Circuit<Self::Field>>(
+        &self,
+        params: &Self::Params,
+        circuit: &C,
+    ) -> Result<Self::ProvingKey, QuarryError>;
+
+    fn verifying_key<'a>(&self, pk: &'a Self::ProvingKey) -> &'a Self::VerifyingKey;
+
+    // Prove a single circuit with a single instance column.
+    fn prove<C: Circuit<Self::Field>>(
+        &self,
+        params: &Self::Params,
+        pk: &Self::ProvingKey,
+        circuit: C,
+        instances: &[Self::Field],
+        rng: impl RngCore,
+    ) -> Result<Vec<u8>, QuarryError>;
+}
+
+pub trait Verifier: Backend {
+    fn verify(
+        &self,
+        params: &Self::Params,
+        vk: &Self::VerifyingKey,
+        instances: &[Self::Field],
+        proof: &[u8],
+    ) -> Result<(), QuarryError>;
+}
+
+// KZG over BN254, with the transcript the verifier expects.
+#[cfg(feature = "kzg")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Kzg {
+    pub transcript: TranscriptKind,
+}
+
+#[cfg(feature = "kzg")]
+mod pse_kzg {
+    use super::{Backend, Kzg, Prover, Verifier};
+    use crate::error::QuarryError;
+    use crate::proof;
+    use halo2_proofs::{
+        plonk::{Circuit, ProvingKey, VerifyingKey},
+        poly::kzg::commitment::ParamsKZG,
+    };
+    use halo2curves::bn256::{Bn256, Fr, G1Affine};
+    use rand::RngCore;
+
+    impl Backend for Kzg {
+        type Field = Fr;
+        type Params = ParamsKZG<Bn256>;
+        type ProvingKey = ProvingKey<G1Affine>;
+        type VerifyingKey = VerifyingKey<G1Affine>;
+    }
+
+    impl Prover for Kzg {
+        fn keygen<C: Circuit<Fr>>(
+            &self,
+            params: &Self::Params,
+            circuit: &C,
+        ) -> Result<Self::ProvingKey, QuarryError> {
+            proof::keygen(params, circuit)
+        }
+
+        fn verifying_key<'a>(&self, pk: &'a Self::ProvingKey) -> &'a Self::VerifyingKey {
+            pk.get_vk()
+        }
+
+        fn prove<C: Circuit<Fr>>(
+            &self,
+            params: &Self::Params,
+            pk: &Self::ProvingKey,
+            circuit: C,
+            instances: &[Fr],
+            rng: impl RngCore,
+        ) -> Result<Vec<u8>, QuarryError> {
+            proof::prove_with(params, pk, circuit, instances, rng, self.transcript)
+        }
+    }
+
+    impl Verifier for Kzg {
+        fn verify(
+            &self,
+            params: &Self::Params,
+            vk: &Self::VerifyingKey,
+            instances: &[Fr],
+            proof: &[u8],
+        ) -> Result<(), QuarryError> {
+            proof::verify_with(params, vk, instances, proof, self.transcript)
+        }
+    }
+}
+
+// IPA over Vesta, always with the Blake2b transcript.
+#[cfg(feature = "ipa")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Ipa;
+
+#[cfg(feature = "ipa")]
+mod pse_ipa {
+    use super::{Backend, Ipa, Prover, Verifier};
+    use crate::error::QuarryError;
+    use crate::ipa;
+    use halo2_proofs::{
+        plonk::{Circuit, ProvingKey, VerifyingKey},
+        poly::ipa::commitment::ParamsIPA,
+    };
+    use halo2curves::pasta::{EqAffine, Fp};
+    use rand::RngCore;
+
+    impl Backend for Ipa {
+        type Field = Fp;
+        type Params = ParamsIPA<EqAffine>;
+        type ProvingKey = ProvingKey<EqAffine>;
+        type VerifyingKey = VerifyingKey<EqAffine>;
+    }
+
+    impl Prover for Ipa {
+        fn keygen<C: Circuit<Fp>>(
+            &self,
+            params: &Self::Params,
+            circuit: &C,
+        ) -> Result<Self::ProvingKey, QuarryError> {
+            ipa::keygen(params, circuit)
+        }
+
+        fn verifying_key<'a>(&self, pk: &'a Self::ProvingKey) -> &'a Self::VerifyingKey {
+            pk.get_vk()
+        }
+
+        fn prove<C: Circuit<Fp>>(
+            &self,
+            params: &Self::Params,
+            pk: &Self::ProvingKey,
+            circuit: C,
+            instances: &[Fp],
+            rng: impl RngCore,
+        ) -> Result<Vec<u8>, QuarryError> {
+            ipa::prove(params, pk, circuit, instances, rng)
+        }
+    }
+
+    impl Verifier for Ipa {
+        fn verify(
+            &self,
+            params: &Self::Params,
+            vk: &Self::VerifyingKey,
+            instances: &[Fp],
+            proof: &[u8],
+        ) -> Result<(), QuarryError> {
+            ipa::verify(params, vk, instances, proof)
+        }
+    }
+}
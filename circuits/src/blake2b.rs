@@ -0,0 +1,550 @@
+// Blake2b compression in a circuit, for replaying the Blake2b transcripts of
+// proofs made before the Poseidon transcript, e.g. in an aggregation circuit
+// verifying them. Words are 8 little endian bytes and every operation on
+// them goes through the shared byte table of `bytes`: XOR is a lookup per
+// byte, the rotations by 32, 24 and 16 bits only permute the bytes, and the
+// rotation by 63 rotates every byte by 7 and recombines neighbouring bits
+// with masks. Additions mod 2^64 are the one gate of the chip, which checks
+// the sum of the composed words against the sum bytes and a carry.
+//
+// A compression is about 10k lookups on top of the 2^17 rows of the table,
+// which is why the module is behind the `blake2b` feature.
+use crate::bytes::{self, ByteChip, ByteConfig, ByteOp, ByteTable, Constants};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+    plonk::{
+        Advice, Circuit, Column, ConstraintSystem, Error, Expression, Fixed, Instance, Selector,
+    },
+    poly::Rotation,
+};
+use halo2curves::bn256::Fr;
+
+pub const IV: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+const SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+const ROUNDS: usize = 12;
+
+// Columns of the state words each mixing step works on, then the schedule
+// words it adds.
+const MIXES: [[usize; 4]; 8] = [
+    [0, 4, 8, 12],
+    [1, 5, 9, 13],
+    [2, 6, 10, 14],
+    [3, 7, 11, 15],
+    [0, 5, 10, 15],
+    [1, 6, 11, 12],
+    [2, 7, 8, 13],
+    [3, 4, 9, 14],
+];
+
+// Chaining value before the first block of an unkeyed hash with `out_len`
+// bytes of output, as in RFC 7693. halo2's Blake2b transcript uses 64 bytes
+// and the personalization "Halo2-Transcript".
+pub fn initial_state(out_len: usize, personal: [u8; 16]) -> [u64; 8] {
+    assert!(out_len > 0 && out_len <= 64, "output of 1 to 64 bytes");
+    let mut h = IV;
+    h[0] ^= 0x01010000 ^ out_len as u64;
+    h[6] ^= u64::from_le_bytes(personal[..8].try_into().unwrap());
+    h[7] ^= u64::from_le_bytes(personal[8..].try_into().unwrap());
+    h
+}
+
+// Message block from up to 128 bytes, zero padded.
+pub fn block(bytes: &[u8]) -> [u64; 16] {
+    assert!(bytes.len() <= 128, "a block is 128 bytes");
+    let mut padded = [0u8; 128];
+    padded[..bytes.len()].copy_from_slice(bytes);
+    let mut m = [0; 16];
+    for (word, chunk) in m.iter_mut().zip(padded.chunks(8)) {
+        *word = u64::from_le_bytes(chunk.try_into().unwrap());
+    }
+    m
+}
+
+// Working vector after the chaining value, `t` bytes hashed so far and
+// whether this is the last block. It only depends on public parameters.
+fn initial_vector(h: &[u64; 8], t: u128, last: bool) -> [u64; 16] {
+    let mut v = [0; 16];
+    v[..8].copy_from_slice(h);
+    v[8..].copy_from_slice(&IV);
+    v[12] ^= t as u64;
+    v[13] ^= (t >> 64) as u64;
+    if last {
+        v[14] = !v[14];
+    }
+    v
+}
+
+// The compression function F of RFC 7693.
+pub fn compress(h: [u64; 8], m: [u64; 16], t: u128, last: bool) -> [u64; 8] {
+    let mut v = initial_vector(&h, t, last);
+    for round in 0..ROUNDS {
+        let s = SIGMA[round % 10];
+        for (i, &[a, b, c, d]) in MIXES.iter().enumerate() {
+            let (x, y) = (m[s[2 * i]], m[s[2 * i + 1]]);
+            v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+            v[d] = (v[d] ^ v[a]).rotate_right(32);
+            v[c] = v[c].wrapping_add(v[d]);
+            v[b] = (v[b] ^ v[c]).rotate_right(24);
+            v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+            v[d] = (v[d] ^ v[a]).rotate_right(16);
+            v[c] = v[c].wrapping_add(v[d]);
+            v[b] = (v[b] ^ v[c]).rotate_right(63);
+        }
+    }
+    let mut out = h;
+    for (i, word) in out.iter_mut().enumerate() {
+        *word ^= v[i] ^ v[i + 8];
+    }
+    out
+}
+
+// A 64-bit word as little endian bytes, each known to be below 256.
+#[derive(Clone, Debug)]
+pub struct Word<F: FieldExt>(pub [AssignedCell<F, F>; 8]);
+
+impl<F: FieldExt> Word<F> {
+    fn from_bytes(bytes: Vec<AssignedCell<F, F>>) -> Self {
+        Word(bytes.try_into().expect("a word is 8 bytes"))
+    }
+
+    pub fn value(&self) -> Value<u64> {
+        self.0.iter().rev().fold(Value::known(0), |word, byte| {
+            word.zip(byte.value())
+                .map(|(word, byte)| word << 8 | bytes::byte(byte) as u64)
+        })
+    }
+
+    // Rotate right by a multiple of 8 bits, which only moves bytes.
+    fn rotate_bytes(&self, bytes: usize) -> Self {
+        Word::from_bytes((0..8).map(|i| self.0[(i + bytes) % 8].clone()).collect())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Blake2bConfig {
+    bytes: ByteConfig,
+    // Bytes of the summed words, one word per row.
+    words: [Column<Advice>; 8],
+    carry: Column<Advice>,
+    q_add: Selector,
+}
+
+#[derive(Clone, Debug)]
+pub struct Blake2bChip<F: FieldExt> {
+    config: Blake2bConfig,
+    bytes: ByteChip<F>,
+}
+
+// A constant word from the constant bytes of the compression.
+fn constant_word<F: FieldExt>(
+    constants: &mut Constants<F>,
+    chip: &ByteChip<F>,
+    layouter: &mut impl Layouter<F>,
+    value: u64,
+) -> Result<Word<F>, Error> {
+    Ok(Word::from_bytes(constants.bytes(
+        chip,
+        layouter,
+        &value.to_le_bytes(),
+    )?))
+}
+
+impl<F: FieldExt> Blake2bChip<F> {
+    pub fn new(config: Blake2bConfig) -> Self {
+        let bytes = ByteChip::new(config.bytes.clone());
+        Self { config, bytes }
+    }
+
+    // Like `ByteChip::configure`, `constant` is the constants column of the
+    // circuit.
+    pub fn configure(meta: &mut ConstraintSystem<F>, constant: Column<Fixed>) -> Blake2bConfig {
+        let bytes = ByteChip::configure(meta, constant);
+        Self::configure_bytes(meta, bytes)
+    }
+
+    pub fn configure_with(
+        meta: &mut ConstraintSystem<F>,
+        table: ByteTable,
+        constant: Column<Fixed>,
+    ) -> Blake2bConfig {
+        let bytes = ByteChip::configure_with(meta, table, constant);
+        Self::configure_bytes(meta, bytes)
+    }
+
+    fn configure_bytes(meta: &mut ConstraintSystem<F>, bytes: ByteConfig) -> Blake2bConfig {
+        let words = [(); 8].map(|_| meta.advice_column());
+        for column in words.iter() {
+            meta.enable_equality(*column);
+        }
+        let carry = meta.advice_column();
+        let q_add = meta.selector();
+
+        // Rows 0 to 2 hold the summands, row 3 the sum. The sum bytes are
+        // range checked through the table, so with a carry below 3 the sum
+        // is the one mod 2^64.
+        meta.create_gate("word sum", |meta| {
+            let q_add = meta.query_selector(q_add);
+            let mut word = |row: i32| {
+                words
+                    .iter()
+                    .enumerate()
+                    .map(|(i, column)| {
+                        meta.query_advice(*column, Rotation(row))
+                            * Expression::Constant(F::from_u128(1 << (8 * i)))
+                    })
+                    .reduce(|acc, byte| acc + byte)
+                    .unwrap()
+            };
+            let (x, y, z, sum) = (word(0), word(1), word(2), word(3));
+            let carry = meta.query_advice(carry, Rotation::cur());
+
+            vec![
+                q_add.clone()
+                    * (x + y + z
+                        - sum
+                        - carry.clone() * Expression::Constant(F::from_u128(1 << 64))),
+                q_add
+                    * carry.clone()
+                    * (carry.clone() - Expression::Constant(F::one()))
+                    * (carry - Expression::Constant(F::from(2))),
+            ]
+        });
+
+        Blake2bConfig {
+            bytes,
+            words,
+            carry,
+            q_add,
+        }
+    }
+
+    // Load the byte table, once per circuit. Circuits sharing the table of
+    // another gadget load that one instead.
+    pub fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        self.bytes.load(layouter)
+    }
+
+    // Witness a word and range check its bytes.
+    pub fn assign_word(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<u64>,
+    ) -> Result<Word<F>, Error> {
+        let bytes = layouter.assign_region(
+            || "load word",
+            |mut region| {
+                self.config
+                    .words
+                    .iter()
+                    .enumerate()
+                    .map(|(i, column)| {
+                        let byte = value.map(|word| F::from((word >> (8 * i)) & 0xff));
+                        region.assign_advice(|| "byte", *column, 0, || byte)
+                    })
+                    .collect::<Result<Vec<_>, Error>>()
+            },
+        )?;
+        for (i, byte) in bytes.iter().enumerate() {
+            self.bytes.apply(
+                layouter.namespace(|| format!("range byte {}", i)),
+                ByteOp::And,
+                byte,
+                byte,
+            )?;
+        }
+        Ok(Word::from_bytes(bytes))
+    }
+
+    fn add(&self, mut layouter: impl Layouter<F>, summands: &[&Word<F>]) -> Result<Word<F>, Error> {
+        assert!(summands.len() == 2 || summands.len() == 3);
+        let total = summands.iter().fold(Value::known(0u128), |total, word| {
+            total
+                .zip(word.value())
+                .map(|(total, word)| total + word as u128)
+        });
+
+        let bytes = layouter.assign_region(
+            || "add",
+            |mut region| {
+                self.config.q_add.enable(&mut region, 0)?;
+                for (row, word) in summands.iter().enumerate() {
+                    for (byte, column) in word.0.iter().zip(self.config.words) {
+                        byte.copy_advice(|| "summand", &mut region, column, row)?;
+                    }
+                }
+                if summands.len() == 2 {
+                    for column in self.config.words {
+                        region.assign_advice_from_constant(|| "zero", column, 2, F::zero())?;
+                    }
+                }
+                region.assign_advice(
+                    || "carry",
+                    self.config.carry,
+                    0,
+                    || total.map(|total| F::from((total >> 64) as u64)),
+                )?;
+                self.config
+                    .words
+                    .iter()
+                    .enumerate()
+                    .map(|(i, column)| {
+                        let byte = total.map(|total| F::from(((total >> (8 * i)) & 0xff) as u64));
+                        region.assign_advice(|| "sum", *column, 3, || byte)
+                    })
+                    .collect::<Result<Vec<_>, Error>>()
+            },
+        )?;
+        for (i, byte) in bytes.iter().enumerate() {
+            self.bytes.apply(
+                layouter.namespace(|| format!("range sum {}", i)),
+                ByteOp::And,
+                byte,
+                byte,
+            )?;
+        }
+        Ok(Word::from_bytes(bytes))
+    }
+
+    fn xor(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &Word<F>,
+        b: &Word<F>,
+    ) -> Result<Word<F>, Error> {
+        let bytes =
+            a.0.iter()
+                .zip(b.0.iter())
+                .enumerate()
+                .map(|(i, (a, b))| {
+                    self.bytes.apply(
+                        layouter.namespace(|| format!("xor {}", i)),
+                        ByteOp::Xor,
+                        a,
+                        b,
+                    )
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+        Ok(Word::from_bytes(bytes))
+    }
+
+    // Rotation right by 63, i.e. left by 1: every byte moves up a bit and
+    // takes the top bit of the byte below. Rotating each byte right by 7
+    // puts both where they belong, the masks pick them and since the bits
+    // are disjoint XOR combines them.
+    fn rotate_63(
+        &self,
+        mut layouter: impl Layouter<F>,
+        constants: &mut Constants<F>,
+        a: &Word<F>,
+    ) -> Result<Word<F>, Error> {
+        let seven = constants.byte(&self.bytes, &mut layouter, 7)?;
+        let high = constants.byte(&self.bytes, &mut layouter, 0xfe)?;
+        let low = constants.byte(&self.bytes, &mut layouter, 0x01)?;
+
+        let rotated =
+            a.0.iter()
+                .enumerate()
+                .map(|(i, byte)| {
+                    self.bytes.apply(
+                        layouter.namespace(|| format!("rotate {}", i)),
+                        ByteOp::RotateRight,
+                        byte,
+                        &seven,
+                    )
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+        let bytes = (0..8)
+            .map(|i| {
+                let mut layouter = layouter.namespace(|| format!("combine {}", i));
+                let up = self.bytes.apply(
+                    layouter.namespace(|| "high bits"),
+                    ByteOp::And,
+                    &rotated[i],
+                    &high,
+                )?;
+                let carried = self.bytes.apply(
+                    layouter.namespace(|| "low bit"),
+                    ByteOp::And,
+                    &rotated[(i + 7) % 8],
+                    &low,
+                )?;
+                self.bytes
+                    .apply(layouter.namespace(|| "combine"), ByteOp::Xor, &up, &carried)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok(Word::from_bytes(bytes))
+    }
+
+    fn mix(
+        &self,
+        mut layouter: impl Layouter<F>,
+        constants: &mut Constants<F>,
+        v: &mut [Word<F>],
+        [a, b, c, d]: [usize; 4],
+        x: &Word<F>,
+        y: &Word<F>,
+    ) -> Result<(), Error> {
+        v[a] = self.add(layouter.namespace(|| "a + b + x"), &[&v[a], &v[b], x])?;
+        v[d] = self
+            .xor(layouter.namespace(|| "d ^ a"), &v[d], &v[a])?
+            .rotate_bytes(4);
+        v[c] = self.add(layouter.namespace(|| "c + d"), &[&v[c], &v[d]])?;
+        v[b] = self
+            .xor(layouter.namespace(|| "b ^ c"), &v[b], &v[c])?
+            .rotate_bytes(3);
+        v[a] = self.add(layouter.namespace(|| "a + b + y"), &[&v[a], &v[b], y])?;
+        v[d] = self
+            .xor(layouter.namespace(|| "d ^ a"), &v[d], &v[a])?
+            .rotate_bytes(2);
+        v[c] = self.add(layouter.namespace(|| "c + d"), &[&v[c], &v[d]])?;
+        let b_xor_c = self.xor(layouter.namespace(|| "b ^ c"), &v[b], &v[c])?;
+        v[b] = self.rotate_63(layouter.namespace(|| "rotate 63"), constants, &b_xor_c)?;
+        Ok(())
+    }
+
+    // Compress block `m` into the chaining value `h`, after `t` bytes and
+    // with `last` set for the final block. Both are parameters of the
+    // circuit rather than witnesses, as the length of a transcript is fixed
+    // by the proof it belongs to.
+    pub fn compress(
+        &self,
+        mut layouter: impl Layouter<F>,
+        h: &[Word<F>],
+        m: &[Word<F>],
+        t: u128,
+        last: bool,
+    ) -> Result<Vec<Word<F>>, Error> {
+        assert_eq!(h.len(), 8, "the chaining value is 8 words");
+        assert_eq!(m.len(), 16, "a block is 16 words");
+        let mut constants = Constants::new();
+
+        // The lower half of the working vector only depends on t and last.
+        let mut v = h.to_vec();
+        for word in initial_vector(&[0; 8], t, last)[8..].iter() {
+            v.push(constant_word(
+                &mut constants,
+                &self.bytes,
+                &mut layouter,
+                *word,
+            )?);
+        }
+
+        for round in 0..ROUNDS {
+            let s = SIGMA[round % 10];
+            for (i, columns) in MIXES.iter().enumerate() {
+                self.mix(
+                    layouter.namespace(|| format!("round {} mix {}", round, i)),
+                    &mut constants,
+                    &mut v,
+                    *columns,
+                    &m[s[2 * i]],
+                    &m[s[2 * i + 1]],
+                )?;
+            }
+        }
+
+        (0..8)
+            .map(|i| {
+                let mut layouter = layouter.namespace(|| format!("output {}", i));
+                let halves = self.xor(layouter.namespace(|| "halves"), &v[i], &v[i + 8])?;
+                self.xor(layouter.namespace(|| "chain"), &h[i], &halves)
+            })
+            .collect()
+    }
+}
+
+// One compression with the output bytes as public inputs, for the tests and
+// the bench.
+#[derive(Clone, Debug)]
+pub struct CompressionCircuit {
+    pub h: Value<[u64; 8]>,
+    pub m: Value<[u64; 16]>,
+    pub t: u128,
+    pub last: bool,
+}
+
+impl CompressionCircuit {
+    pub fn new(h: [u64; 8], m: [u64; 16], t: u128, last: bool) -> Self {
+        Self {
+            h: Value::known(h),
+            m: Value::known(m),
+            t,
+            last,
+        }
+    }
+}
+
+// The output bytes of the compression, in order.
+pub fn instances(h: [u64; 8], m: [u64; 16], t: u128, last: bool) -> Vec<Fr> {
+    compress(h, m, t, last)
+        .iter()
+        .flat_map(|word| word.to_le_bytes())
+        .map(|byte| Fr::from(byte as u64))
+        .collect()
+}
+
+impl Circuit<Fr> for CompressionCircuit {
+    type Config = (Blake2bConfig, Column<Instance>);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            h: Value::unknown(),
+            m: Value::unknown(),
+            t: self.t,
+            last: self.last,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        let constant = meta.fixed_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        (Blake2bChip::configure(meta, constant), instance)
+    }
+
+    fn synthesize(
+        &self,
+        (config, instance): Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let chip = Blake2bChip::new(config);
+        chip.load(&mut layouter)?;
+
+        let h = (0..8)
+            .map(|i| chip.assign_word(layouter.namespace(|| "h"), self.h.map(|h| h[i])))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let m = (0..16)
+            .map(|i| chip.assign_word(layouter.namespace(|| "m"), self.m.map(|m| m[i])))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let out = chip.compress(layouter.namespace(|| "compress"), &h, &m, self.t, self.last)?;
+
+        for (row, byte) in out.iter().flat_map(|word| word.0.iter()).enumerate() {
+            layouter.constrain_instance(byte.cell(), instance, row)?;
+        }
+        Ok(())
+    }
+}
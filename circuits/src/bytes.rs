@@ -0,0 +1,274 @@
+// Byte operations through one shared lookup table, for the bit-oriented
+// hash gadgets (Keccak, SHA-256, Blake2). Every operation is a row of a
+// single tagged table (op, a, b, result), so a composite circuit pays for
+// four table columns and one lookup argument however many gadgets use it,
+// instead of a set of fixed columns per gadget.
+//
+// The table has 2^17 rows for XOR and AND plus 2^11 for rotations, so a
+// circuit loading it needs k >= 18.
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Fixed, Selector, TableColumn},
+    poly::Rotation,
+};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+// Tag 0 is the all-zero row, which disabled lookups resolve to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ByteOp {
+    Xor = 1,
+    And = 2,
+    // Rotate `a` right by `b` bits, with `b` below 8.
+    RotateRight = 3,
+}
+
+impl ByteOp {
+    pub fn apply(self, a: u8, b: u8) -> u8 {
+        match self {
+            ByteOp::Xor => a ^ b,
+            ByteOp::And => a & b,
+            ByteOp::RotateRight => a.rotate_right(b as u32),
+        }
+    }
+
+    fn operands(self) -> impl Iterator<Item = (u8, u8)> {
+        let bs = match self {
+            ByteOp::Xor | ByteOp::And => 0..=255u8,
+            ByteOp::RotateRight => 0..=7,
+        };
+        (0..=255u8).flat_map(move |a| bs.clone().map(move |b| (a, b)))
+    }
+}
+
+pub const TABLE_ROWS: usize = 1 + 2 * 256 * 256 + 256 * 8;
+
+#[derive(Clone, Copy, Debug)]
+pub struct ByteTable {
+    op: TableColumn,
+    a: TableColumn,
+    b: TableColumn,
+    result: TableColumn,
+}
+
+#[derive(Clone, Debug)]
+pub struct ByteConfig {
+    // a, b, result
+    columns: [Column<Advice>; 3],
+    op: Column<Fixed>,
+    q_lookup: Selector,
+    table: ByteTable,
+}
+
+impl ByteConfig {
+    pub fn table(&self) -> ByteTable {
+        self.table
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ByteChip<F: FieldExt> {
+    config: ByteConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> ByteChip<F> {
+    pub fn new(config: ByteConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    // Allocate the table and the columns to look operations up from. Gadgets
+    // with their own layout can share an existing table through
+    // `configure_with`. Rotation amounts are loaded from `constant`, the
+    // constants column the circuit already has, e.g. the one of
+    // `poseidon::configure`.
+    pub fn configure(meta: &mut ConstraintSystem<F>, constant: Column<Fixed>) -> ByteConfig {
+        let table = ByteTable {
+            op: meta.lookup_table_column(),
+            a: meta.lookup_table_column(),
+            b: meta.lookup_table_column(),
+            result: meta.lookup_table_column(),
+        };
+        Self::configure_with(meta, table, constant)
+    }
+
+    pub fn configure_with(
+        meta: &mut ConstraintSystem<F>,
+        table: ByteTable,
+        constant: Column<Fixed>,
+    ) -> ByteConfig {
+        let columns = [(); 3].map(|_| meta.advice_column());
+        for column in columns.iter() {
+            meta.enable_equality(*column);
+        }
+        let op = meta.fixed_column();
+        // enabling it again is a no-op
+        meta.enable_constant(constant);
+        let q_lookup = meta.complex_selector();
+
+        meta.lookup("byte operation", |meta| {
+            let q_lookup = meta.query_selector(q_lookup);
+            let op = meta.query_fixed(op, Rotation::cur());
+            let [a, b, result] = columns.map(|column| meta.query_advice(column, Rotation::cur()));
+
+            vec![
+                (q_lookup.clone() * op, table.op),
+                (q_lookup.clone() * a, table.a),
+                (q_lookup.clone() * b, table.b),
+                (q_lookup * result, table.result),
+            ]
+        });
+
+        ByteConfig {
+            columns,
+            op,
+            q_lookup,
+            table,
+        }
+    }
+
+    // Load the table, once per circuit.
+    pub fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        let table = self.config.table;
+        layouter.assign_table(
+            || "byte operations",
+            |mut region| {
+                let rows = [(0, 0, 0, 0)].into_iter().chain(
+                    [ByteOp::Xor, ByteOp::And, ByteOp::RotateRight]
+                        .into_iter()
+                        .flat_map(|op| {
+                            op.operands()
+                                .map(move |(a, b)| (op as u64, a, b, op.apply(a, b)))
+                        }),
+                );
+                for (offset, (op, a, b, result)) in rows.enumerate() {
+                    for (column, value) in [
+                        (table.op, op),
+                        (table.a, a as u64),
+                        (table.b, b as u64),
+                        (table.result, result as u64),
+                    ] {
+                        region.assign_cell(
+                            || "byte operation",
+                            column,
+                            offset,
+                            || Value::known(F::from(value)),
+                        )?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+
+    // Apply `op` to two assigned bytes. The lookup also range checks both
+    // operands and the result.
+    pub fn apply(
+        &self,
+        mut layouter: impl Layouter<F>,
+        op: ByteOp,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let [a_column, b_column, result_column] = self.config.columns;
+
+        layouter.assign_region(
+            || format!("{:?}", op),
+            |mut region| {
+                self.config.q_lookup.enable(&mut region, 0)?;
+                region.assign_fixed(
+                    || "op",
+                    self.config.op,
+                    0,
+                    || Value::known(F::from(op as u64)),
+                )?;
+                a.copy_advice(|| "a", &mut region, a_column, 0)?;
+                b.copy_advice(|| "b", &mut region, b_column, 0)?;
+
+                let result = a
+                    .value()
+                    .zip(b.value())
+                    .map(|(a, b)| F::from(op.apply(byte(a), byte(b)) as u64));
+                region.assign_advice(|| "result", result_column, 0, || result)
+            },
+        )
+    }
+
+    pub fn rotate_right(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        bits: u8,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        assert!(bits < 8, "rotation by a byte or more");
+        let b = self.constant(layouter.namespace(|| "load rotation"), bits)?;
+        self.apply(layouter.namespace(|| "rotate"), ByteOp::RotateRight, a, &b)
+    }
+
+    // Load a constant operand, e.g. a rotation amount or a mask. Gadgets
+    // using the same one many times can load it once and pass it to `apply`.
+    pub fn constant(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: u8,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let [_, b_column, _] = self.config.columns;
+        layouter.assign_region(
+            || "load constant",
+            |mut region| {
+                region.assign_advice_from_constant(
+                    || "constant",
+                    b_column,
+                    0,
+                    F::from(value as u64),
+                )
+            },
+        )
+    }
+}
+
+// Constant bytes loaded so far, so a gadget loads every one once per use,
+// e.g. per compression.
+pub(crate) struct Constants<F: FieldExt>(HashMap<u8, AssignedCell<F, F>>);
+
+impl<F: FieldExt> Constants<F> {
+    pub(crate) fn new() -> Self {
+        Self(HashMap::new())
+    }
+
+    pub(crate) fn byte(
+        &mut self,
+        chip: &ByteChip<F>,
+        layouter: &mut impl Layouter<F>,
+        value: u8,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        if let Some(cell) = self.0.get(&value) {
+            return Ok(cell.clone());
+        }
+        let cell = chip.constant(layouter.namespace(|| format!("constant {}", value)), value)?;
+        self.0.insert(value, cell.clone());
+        Ok(cell)
+    }
+
+    pub(crate) fn bytes(
+        &mut self,
+        chip: &ByteChip<F>,
+        layouter: &mut impl Layouter<F>,
+        values: &[u8],
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        values
+            .iter()
+            .map(|value| self.byte(chip, layouter, *value))
+            .collect()
+    }
+}
+
+// Witnessed bytes that don't fit are truncated here and rejected by the
+// lookup.
+pub(crate) fn byte<F: FieldExt>(value: &F) -> u8 {
+    value.to_repr().as_ref()[0]
+}
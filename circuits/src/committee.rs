@@ -0,0 +1,799 @@
+// Threshold attestation circuit for committees of up to `N_MAX` members. The
+// seats that signed must satisfy the committee's quorum policy.
+//
+// Committees change size without a new keygen: every seat always verifies
+// one signature, but seats whose `active` bit is unset verify against the
+// padding key instead of the member key. The padding key has a publicly
+// known secret so anyone can fill unused seats, and those seats are not
+// counted as signers. Empty seats in the member set hold the padding key as
+// well, and the member set itself is bound by a Poseidon root in the public
+// inputs.
+//
+// Attestations form a hash chain: each one exposes the digest of the
+// attestation of the previous epoch it builds on and its own digest over
+// that and the rest of the statement. A verifier only keeps the latest
+// digest and accepts the next attestation iff its `prev_attestation` equals
+// it, which rules out forks without storing the history. The epoch is part
+// of the digest but not checked against the previous one, so a skipped
+// epoch only shows in the public epoch, which the verifier compares with
+// the one it accepted last if it has to rule gaps out.
+//
+// The circuit is a composite of four parts, laid out and exposing their
+// public inputs in this order: `SignaturesPart` verifies a signature per
+// seat, `MembersPart` hashes the verified keys into the members root and the
+// signer tags, `PolicyPart` checks the seats that signed against the quorum
+// policy and `ChainPart` computes the attestation digest. Each part hands
+// the cells the later ones need on through the wires of the composite.
+use crate::compose::{self, Composite, Shared, SubCircuit, Wires};
+use crate::ecdsa::{
+    self, AssignedEcdsaSig, AssignedPublicKey, EcdsaChip, EcdsaConfig, BIT_LEN_LIMB, MUL_PAIRS,
+    NUMBER_OF_LIMBS,
+};
+use crate::error::CircuitError;
+use crate::layout::InstanceLayout;
+use crate::merkle::{IncrementalTree, MerkleChip};
+use crate::policy::{self, QuorumPolicy};
+use crate::poseidon::{self, HASH_ROWS};
+use crate::secret::Secret;
+use crate::spec::StatementSpec;
+use ecc::GeneralEccChip;
+use ff::Field;
+use halo2_proofs::{
+    arithmetic::{CurveAffine, FieldExt},
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    plonk::{Circuit, ConstraintSystem, Error},
+};
+use integer::{IntegerInstructions, Range};
+use maingate::{big_to_fe, fe_to_big, MainGate, MainGateInstructions, RegionCtx, Term};
+use rand::RngCore;
+
+// Rows of the instance column, see `layout`. They follow the order of the
+// parts exposing them.
+pub const MSG_HASH: usize = 0;
+pub const SIGNERS: usize = 1;
+// Seat `i` signed iff bit `i` is set.
+pub const SIGNERS_BITMAP: usize = 2;
+pub const MEMBERS_ROOT: usize = 3;
+pub const EPOCH: usize = 4;
+// First of `N_MAX` rows holding one signer tag per seat, zero for seats that
+// didn't sign.
+pub const SIGNER_TAGS: usize = 5;
+
+pub const fn policy_commitment_row(n_max: usize) -> usize {
+    SIGNER_TAGS + n_max
+}
+
+// Digest of the attestation the proof builds on, zero for the first one,
+// and that of the proof itself; they follow the policy commitment.
+pub const fn prev_attestation_row(n_max: usize) -> usize {
+    policy_commitment_row(n_max) + 1
+}
+
+pub const fn attestation_row(n_max: usize) -> usize {
+    prev_attestation_row(n_max) + 1
+}
+
+pub fn padding_secret<E: CurveAffine>() -> E::ScalarExt {
+    E::ScalarExt::one()
+}
+
+pub fn padding_key<E: CurveAffine>() -> E {
+    E::generator()
+}
+
+#[derive(Clone, Debug)]
+pub struct CommitteeCircuit<E: CurveAffine, N: FieldExt, const N_MAX: usize> {
+    // Member keys, empty seats hold the padding key.
+    pub members: Vec<Value<E>>,
+    // Signatures on `msg_hash`, seats that didn't sign carry a signature by
+    // the padding key.
+    pub signatures: Vec<Value<(E::Scalar, E::Scalar)>>,
+    pub active: Vec<Value<bool>>,
+    // Only the shape of the policy is part of the circuit, see `policy`.
+    pub policy: QuorumPolicy,
+    pub msg_hash: Value<E::Scalar>,
+    // Attestation digests are native.
+    pub prev_attestation: Value<N>,
+    pub epoch: u64,
+    pub aux_generator: E,
+    pub window_size: usize,
+}
+
+impl<E: CurveAffine, N: FieldExt, const N_MAX: usize> CommitteeCircuit<E, N, N_MAX> {
+    // Build the witness from the member set and the signatures collected for
+    // `msg_hash`, indexed by seat.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        members: &[E],
+        signatures: &[Option<(E::Scalar, E::Scalar)>],
+        policy: QuorumPolicy,
+        msg_hash: E::Scalar,
+        prev_attestation: N,
+        epoch: u64,
+        aux_generator: E,
+        window_size: usize,
+        mut rng: impl RngCore,
+    ) -> Result<Self, CircuitError> {
+        if members.len() > N_MAX {
+            return Err(CircuitError::InvalidWitness(format!(
+                "{} members in a committee of {} seats",
+                members.len(),
+                N_MAX
+            )));
+        }
+        if members.len() != signatures.len() {
+            return Err(CircuitError::InvalidWitness(format!(
+                "{} signatures for {} members",
+                signatures.len(),
+                members.len()
+            )));
+        }
+        if policy.max_seat().map_or(false, |seat| seat >= N_MAX) {
+            return Err(CircuitError::InvalidWitness(
+                "policy refers to a seat outside the committee".to_string(),
+            ));
+        }
+
+        let padding = ecdsa::sign::<E>(&Secret::new(padding_secret::<E>()), msg_hash, &mut rng);
+        let seat = |i: usize| (members.get(i), signatures.get(i).copied().flatten());
+
+        Ok(Self {
+            members: (0..N_MAX)
+                .map(|i| Value::known(*seat(i).0.unwrap_or(&padding_key::<E>())))
+                .collect(),
+            signatures: (0..N_MAX)
+                .map(|i| Value::known(seat(i).1.unwrap_or(padding)))
+                .collect(),
+            active: (0..N_MAX)
+                .map(|i| Value::known(seat(i).1.is_some()))
+                .collect(),
+            policy,
+            msg_hash: Value::known(msg_hash),
+            prev_attestation: Value::known(prev_attestation),
+            epoch,
+            aux_generator,
+            window_size,
+        })
+    }
+}
+
+pub type CommitteeParts<E, N, const N_MAX: usize> = (
+    SignaturesPart<E, N_MAX>,
+    MembersPart<N_MAX>,
+    PolicyPart,
+    ChainPart<N>,
+);
+
+impl<E: CurveAffine, N: FieldExt, const N_MAX: usize> CommitteeCircuit<E, N, N_MAX> {
+    pub fn parts(&self) -> CommitteeParts<E, N, N_MAX> {
+        (
+            SignaturesPart {
+                members: self.members.clone(),
+                signatures: self.signatures.clone(),
+                active: self.active.clone(),
+                msg_hash: self.msg_hash,
+                aux_generator: self.aux_generator,
+                window_size: self.window_size,
+            },
+            MembersPart { epoch: self.epoch },
+            PolicyPart {
+                policy: self.policy.clone(),
+            },
+            ChainPart {
+                prev_attestation: self.prev_attestation,
+            },
+        )
+    }
+}
+
+impl<E: CurveAffine, N: FieldExt, const N_MAX: usize> Circuit<N> for CommitteeCircuit<E, N, N_MAX> {
+    type Config = <Composite<CommitteeParts<E, N, N_MAX>> as Circuit<N>>::Config;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            members: vec![Value::unknown(); N_MAX],
+            signatures: vec![Value::unknown(); N_MAX],
+            active: vec![Value::unknown(); N_MAX],
+            policy: self.policy.clone(),
+            msg_hash: Value::unknown(),
+            prev_attestation: Value::unknown(),
+            epoch: self.epoch,
+            aux_generator: self.aux_generator,
+            window_size: self.window_size,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<N>) -> Self::Config {
+        <Composite<CommitteeParts<E, N, N_MAX>> as Circuit<N>>::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<N>) -> Result<(), Error> {
+        Composite(self.parts()).synthesize(config, layouter)
+    }
+}
+
+// Verifies the signature of every seat on `msg_hash` and counts the seats
+// that signed. Hands on the native coordinates of the member keys as
+// "member x" and "member y", the seat bits as "seats", and the native
+// reduction of `msg_hash` and the bitmap as "msg hash" and "bitmap".
+#[derive(Clone, Debug)]
+pub struct SignaturesPart<E: CurveAffine, const N_MAX: usize> {
+    pub members: Vec<Value<E>>,
+    pub signatures: Vec<Value<(E::Scalar, E::Scalar)>>,
+    pub active: Vec<Value<bool>>,
+    pub msg_hash: Value<E::Scalar>,
+    pub aux_generator: E,
+    pub window_size: usize,
+}
+
+impl<E: CurveAffine, N: FieldExt, const N_MAX: usize> SubCircuit<N> for SignaturesPart<E, N_MAX> {
+    type Config = EcdsaConfig;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            members: vec![Value::unknown(); N_MAX],
+            signatures: vec![Value::unknown(); N_MAX],
+            active: vec![Value::unknown(); N_MAX],
+            msg_hash: Value::unknown(),
+            aux_generator: self.aux_generator,
+            window_size: self.window_size,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<N>, shared: &Shared<N>) -> Self::Config {
+        assert!(N_MAX.is_power_of_two(), "N_MAX must be a power of two");
+        assert!(
+            N_MAX < N::CAPACITY as usize,
+            "signer bitmap doesn't fit in a field element"
+        );
+        EcdsaConfig::configure_with::<E, N>(meta, shared.main_gate.clone())
+    }
+
+    fn instances(&self) -> usize {
+        3
+    }
+
+    // The rows of the ECC chip depend on the window size and the limb
+    // layout of halo2wrong, so they are measured.
+    fn rows(&self) -> usize {
+        compose::measured_rows::<N, _>(self)
+    }
+
+    fn synthesize(
+        &self,
+        config: &Self::Config,
+        shared: &Shared<N>,
+        mut layouter: impl Layouter<N>,
+        offset: usize,
+        wires: &mut Wires<N>,
+    ) -> Result<(), Error> {
+        let mut ecc_chip =
+            GeneralEccChip::<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>::new(config.ecc_chip_config());
+        let main_gate = MainGate::<N>::new(shared.main_gate.clone());
+
+        layouter.assign_region(
+            || "assign aux values",
+            |region| {
+                let offset = 0;
+                let ctx = &mut RegionCtx::new(region, offset);
+
+                ecc_chip.assign_aux_generator(ctx, Value::known(self.aux_generator))?;
+                ecc_chip.assign_aux(ctx, self.window_size, MUL_PAIRS)?;
+                Ok(())
+            },
+        )?;
+
+        let ecdsa_chip = EcdsaChip::new(ecc_chip.clone(), self.window_size);
+        let scalar_chip = ecc_chip.scalar_field_chip();
+
+        let (members, seats, msg_hash, signers, bitmap) = layouter.assign_region(
+            || "verify signatures",
+            |region| {
+                let offset = 0;
+                let ctx = &mut RegionCtx::new(region, offset);
+
+                let padding = ecc_chip.assign_constant(ctx, padding_key::<E>())?;
+                let generator = ecdsa_chip.assign_generator(ctx)?;
+                let msg_hash = ecc_chip.new_unassigned_scalar(self.msg_hash);
+                let msg_hash = scalar_chip.assign_integer(ctx, msg_hash, Range::Remainder)?;
+
+                let one = main_gate.assign_constant(ctx, N::one())?;
+                let mut members = Vec::with_capacity(N_MAX);
+                let mut seats = Vec::with_capacity(N_MAX);
+                let mut signers = main_gate.assign_constant(ctx, N::zero())?;
+                for i in 0..N_MAX {
+                    let member = ecc_chip.assign_point(ctx, self.members[i])?;
+                    let active = main_gate.assign_bit(
+                        ctx,
+                        self.active[i].map(|active| if active { N::one() } else { N::zero() }),
+                    )?;
+                    let key = ecc_chip.select(ctx, &active, &member, &padding)?;
+
+                    // Empty seats can't be counted as signers since anyone can
+                    // sign with the padding key.
+                    let diff = main_gate.sub(ctx, member.x().native(), padding.x().native())?;
+                    let diff = main_gate.select(ctx, &diff, &one, &active)?;
+                    main_gate.assert_not_zero(ctx, &diff)?;
+
+                    let r = self.signatures[i].map(|signature| signature.0);
+                    let s = self.signatures[i].map(|signature| signature.1);
+                    let r = scalar_chip.assign_integer(
+                        ctx,
+                        ecc_chip.new_unassigned_scalar(r),
+                        Range::Remainder,
+                    )?;
+                    let s = scalar_chip.assign_integer(
+                        ctx,
+                        ecc_chip.new_unassigned_scalar(s),
+                        Range::Remainder,
+                    )?;
+
+                    ecdsa_chip.verify_with_generator(
+                        ctx,
+                        &generator,
+                        &AssignedEcdsaSig { r, s },
+                        &AssignedPublicKey { point: key },
+                        &msg_hash,
+                    )?;
+
+                    signers = main_gate.add(ctx, &signers, &active)?;
+                    members.push(member);
+                    seats.push(active);
+                }
+
+                let mut power = N::one();
+                let terms = seats
+                    .iter()
+                    .map(|active| {
+                        let term = Term::Assigned(active, power);
+                        power = power.double();
+                        term
+                    })
+                    .collect::<Vec<_>>();
+                let bitmap = main_gate.compose(ctx, &terms, N::zero())?;
+
+                Ok((members, seats, msg_hash, signers, bitmap))
+            },
+        )?;
+
+        let msg_hash = msg_hash.native().clone();
+        shared.expose(layouter.namespace(|| "msg hash"), msg_hash.clone(), offset)?;
+        shared.expose(layouter.namespace(|| "signers"), signers, offset + 1)?;
+        shared.expose(layouter.namespace(|| "bitmap"), bitmap.clone(), offset + 2)?;
+
+        wires.put(
+            "member x",
+            members
+                .iter()
+                .map(|member| member.x().native().clone())
+                .collect(),
+        );
+        wires.put(
+            "member y",
+            members
+                .iter()
+                .map(|member| member.y().native().clone())
+                .collect(),
+        );
+        wires.put("seats", seats);
+        wires.put("msg hash", vec![msg_hash]);
+        wires.put("bitmap", vec![bitmap]);
+
+        config.config_range(&mut layouter)
+    }
+}
+
+// Hashes the member keys into the members root and derives the signer tag of
+// every seat that signed. Hands on the root and the epoch as "members root"
+// and "epoch".
+#[derive(Clone, Debug)]
+pub struct MembersPart<const N_MAX: usize> {
+    pub epoch: u64,
+}
+
+impl<N: FieldExt, const N_MAX: usize> SubCircuit<N> for MembersPart<N_MAX> {
+    type Config = ();
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(_: &mut ConstraintSystem<N>, _: &Shared<N>) -> Self::Config {}
+
+    fn instances(&self) -> usize {
+        2 + N_MAX
+    }
+
+    // Leaves, inner nodes and signer tags are a permutation each, then the
+    // epoch and at most two rows per selected tag.
+    fn rows(&self) -> usize {
+        (3 * N_MAX - 1) * HASH_ROWS + 1 + 1 + 2 * N_MAX
+    }
+
+    fn synthesize(
+        &self,
+        _: &Self::Config,
+        shared: &Shared<N>,
+        mut layouter: impl Layouter<N>,
+        offset: usize,
+        wires: &mut Wires<N>,
+    ) -> Result<(), Error> {
+        let main_gate = MainGate::<N>::new(shared.main_gate.clone());
+        let merkle_chip = MerkleChip::new(shared.poseidon.clone());
+        let seats = wires.get("seats")?.to_vec();
+
+        let leaves = wires
+            .get("member x")?
+            .iter()
+            .zip(wires.get("member y")?)
+            .enumerate()
+            .map(|(i, (x, y))| {
+                merkle_chip.hash_pair(
+                    layouter.namespace(|| format!("member {}", i)),
+                    x.clone(),
+                    y.clone(),
+                )
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let root = merkle_chip.root(layouter.namespace(|| "members root"), &leaves)?;
+
+        let epoch = layouter.assign_region(
+            || "load epoch",
+            |region| {
+                let ctx = &mut RegionCtx::new(region, 0);
+                main_gate.assign_value(ctx, Value::known(N::from(self.epoch)))
+            },
+        )?;
+
+        // Tags only depend on the member and the epoch, so a member signing in
+        // several proofs of the same epoch is counted once. They are not
+        // nullifiers: anyone knowing the member keys can compute them, so
+        // they link a member's signatures within an epoch.
+        let tags = leaves
+            .iter()
+            .enumerate()
+            .map(|(i, leaf)| {
+                merkle_chip.hash_pair(
+                    layouter.namespace(|| format!("signer tag {}", i)),
+                    leaf.clone(),
+                    epoch.clone(),
+                )
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let tags = layouter.assign_region(
+            || "select signer tags",
+            |region| {
+                let offset = 0;
+                let ctx = &mut RegionCtx::new(region, offset);
+
+                let zero = main_gate.assign_constant(ctx, N::zero())?;
+                tags.iter()
+                    .zip(seats.iter())
+                    .map(|(tag, active)| main_gate.select(ctx, tag, &zero, active))
+                    .collect::<Result<Vec<_>, Error>>()
+            },
+        )?;
+
+        shared.expose(layouter.namespace(|| "members root"), root.clone(), offset)?;
+        shared.expose(layouter.namespace(|| "epoch"), epoch.clone(), offset + 1)?;
+        for (i, tag) in tags.into_iter().enumerate() {
+            shared.expose(
+                layouter.namespace(|| format!("signer tag {}", i)),
+                tag,
+                offset + 2 + i,
+            )?;
+        }
+
+        wires.put("members root", vec![root]);
+        wires.put("epoch", vec![epoch]);
+        Ok(())
+    }
+}
+
+// Requires the seats that signed to satisfy the policy and commits to it.
+// Hands on the commitment as "policy commitment".
+#[derive(Clone, Debug)]
+pub struct PolicyPart {
+    // Only the shape of the policy is part of the circuit, see `policy`.
+    pub policy: QuorumPolicy,
+}
+
+impl<N: FieldExt> SubCircuit<N> for PolicyPart {
+    type Config = ();
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(_: &mut ConstraintSystem<N>, _: &Shared<N>) -> Self::Config {}
+
+    fn instances(&self) -> usize {
+        1
+    }
+
+    // The policy, the quorum check and a permutation per element of the
+    // encoding after the first.
+    fn rows(&self) -> usize {
+        let encoding = self.policy.encoding::<N>().len();
+        self.policy.rows() + 1 + (encoding - 1) * HASH_ROWS
+    }
+
+    fn synthesize(
+        &self,
+        _: &Self::Config,
+        shared: &Shared<N>,
+        mut layouter: impl Layouter<N>,
+        offset: usize,
+        wires: &mut Wires<N>,
+    ) -> Result<(), Error> {
+        let main_gate = MainGate::<N>::new(shared.main_gate.clone());
+        let seats = wires.get("seats")?;
+
+        let policy_encoding = layouter.assign_region(
+            || "quorum",
+            |region| {
+                let ctx = &mut RegionCtx::new(region, 0);
+                let (quorum, policy_encoding) = self.policy.assign(&main_gate, ctx, seats)?;
+                main_gate.assert_one(ctx, &quorum)?;
+                Ok(policy_encoding)
+            },
+        )?;
+        let policy_commitment = policy::commit_assigned(
+            &shared.poseidon,
+            layouter.namespace(|| "policy commitment"),
+            &policy_encoding,
+        )?;
+
+        shared.expose(
+            layouter.namespace(|| "policy commitment"),
+            policy_commitment.clone(),
+            offset,
+        )?;
+        wires.put("policy commitment", vec![policy_commitment]);
+        Ok(())
+    }
+}
+
+// Chains the attestation to the one it builds on, over the cells the other
+// parts handed on.
+#[derive(Clone, Debug)]
+pub struct ChainPart<N: FieldExt> {
+    pub prev_attestation: Value<N>,
+}
+
+impl<N: FieldExt> SubCircuit<N> for ChainPart<N> {
+    type Config = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            prev_attestation: Value::unknown(),
+        }
+    }
+
+    fn configure(_: &mut ConstraintSystem<N>, _: &Shared<N>) -> Self::Config {}
+
+    fn instances(&self) -> usize {
+        2
+    }
+
+    // The previous digest, and a digest of six elements needs three
+    // permutations.
+    fn rows(&self) -> usize {
+        1 + 3 * HASH_ROWS
+    }
+
+    fn synthesize(
+        &self,
+        _: &Self::Config,
+        shared: &Shared<N>,
+        mut layouter: impl Layouter<N>,
+        offset: usize,
+        wires: &mut Wires<N>,
+    ) -> Result<(), Error> {
+        let main_gate = MainGate::<N>::new(shared.main_gate.clone());
+        let wire = |name| Ok::<_, Error>(wires.get(name)?[0].clone());
+
+        let prev_attestation = layouter.assign_region(
+            || "load prev attestation",
+            |region| {
+                let offset = 0;
+                let ctx = &mut RegionCtx::new(region, offset);
+                main_gate.assign_value(ctx, self.prev_attestation)
+            },
+        )?;
+        let attestation = poseidon::hash_assigned(
+            &shared.poseidon,
+            layouter.namespace(|| "attestation"),
+            [
+                prev_attestation.clone(),
+                wire("members root")?,
+                wire("msg hash")?,
+                wire("bitmap")?,
+                wire("policy commitment")?,
+                wire("epoch")?,
+            ],
+        )?;
+
+        shared.expose(
+            layouter.namespace(|| "prev attestation"),
+            prev_attestation,
+            offset,
+        )?;
+        shared.expose(
+            layouter.namespace(|| "attestation"),
+            attestation,
+            offset + 1,
+        )
+    }
+}
+
+// Members are committed to by the native field reduction of their
+// coordinates, matching what the circuit hashes.
+pub fn member_leaf<E: CurveAffine, N: FieldExt>(member: &E) -> N {
+    let coordinates = member.coordinates().unwrap();
+    let x: N = big_to_fe(fe_to_big(*coordinates.x()));
+    let y: N = big_to_fe(fe_to_big(*coordinates.y()));
+    poseidon::hash([x, y])
+}
+
+// Registry of the member set, one leaf per seat in seat order with the
+// empty seats holding the leaf of the padding key. Seats join by pushing
+// their leaf, and its proofs are the paths the members root is checked
+// against.
+pub fn registry<E: CurveAffine, N: FieldExt, const N_MAX: usize>(
+    members: &[E],
+) -> Result<IncrementalTree<N>, CircuitError> {
+    assert!(N_MAX.is_power_of_two(), "N_MAX must be a power of two");
+    let mut registry = IncrementalTree::new(
+        N_MAX.trailing_zeros() as usize,
+        member_leaf::<E, N>(&padding_key::<E>()),
+    );
+    for member in members {
+        registry.push(member_leaf::<E, N>(member)).ok_or_else(|| {
+            CircuitError::InvalidWitness(format!(
+                "{} members in a committee of {} seats",
+                members.len(),
+                N_MAX
+            ))
+        })?;
+    }
+    Ok(registry)
+}
+
+// Members past the last seat are left out, as in the circuit.
+pub fn members_root<E: CurveAffine, N: FieldExt, const N_MAX: usize>(members: &[E]) -> N {
+    registry::<E, N, N_MAX>(&members[..members.len().min(N_MAX)])
+        .expect("members fit the seats")
+        .root()
+}
+
+// Public per-epoch tag of a member, hashed from its key rather than a
+// secret, see `MembersPart`.
+pub fn signer_tag<E: CurveAffine, N: FieldExt>(member: &E, epoch: u64) -> N {
+    poseidon::hash([member_leaf::<E, N>(member), N::from(epoch)])
+}
+
+// Digest of an attestation, chained to the one it builds on.
+pub fn attestation_digest<N: FieldExt>(
+    prev_attestation: N,
+    members_root: N,
+    msg_hash: N,
+    signers_bitmap: N,
+    policy_commitment: N,
+    epoch: u64,
+) -> N {
+    poseidon::hash([
+        prev_attestation,
+        members_root,
+        msg_hash,
+        signers_bitmap,
+        policy_commitment,
+        N::from(epoch),
+    ])
+}
+
+// Pack the seats that signed into the `SIGNERS_BITMAP` public input.
+pub fn encode_bitmap<N: FieldExt>(active: &[bool]) -> N {
+    active.iter().rev().fold(N::zero(), |acc, &active| {
+        acc.double() + if active { N::one() } else { N::zero() }
+    })
+}
+
+// Recover which of the `N_MAX` seats signed from the `SIGNERS_BITMAP` public
+// input, so participation can be tracked per member rather than only by count.
+pub fn decode_bitmap<N: FieldExt, const N_MAX: usize>(bitmap: N) -> Vec<bool> {
+    let bits = fe_to_big(bitmap);
+    (0..N_MAX as u64).map(|i| bits.bit(i)).collect()
+}
+
+// Layout of the rows above.
+pub fn layout(n_max: usize) -> InstanceLayout {
+    InstanceLayout::new("committee", 3)
+        .field("msg_hash", 1)
+        .field("signers", 1)
+        .field("signers_bitmap", 1)
+        .field("members_root", 1)
+        .field("epoch", 1)
+        .field("signer_tags", n_max)
+        .field("policy_commitment", 1)
+        .field("prev_attestation", 1)
+        .field("attestation", 1)
+}
+
+pub fn spec(n_max: usize) -> StatementSpec {
+    StatementSpec::new(layout(n_max))
+        .relation(
+            "signatures",
+            "every active seat signed msg_hash with its key, signers counts \
+             the active seats and the bitmap marks them",
+            &["verify signatures"],
+        )
+        .relation(
+            "members",
+            "members_root is the Merkle root over the keys of all seats",
+            &["member 0", "members root"],
+        )
+        .relation(
+            "signer tags",
+            "the signer tag of an active seat hashes its key with the epoch, \
+             inactive seats have a zero tag",
+            &["load epoch", "signer tag 0", "select signer tags"],
+        )
+        .relation(
+            "policy",
+            "the active seats satisfy the quorum policy policy_commitment \
+             commits to",
+            &["quorum", "policy commitment"],
+        )
+        .relation(
+            "chain",
+            "attestation hashes prev_attestation with members_root, msg_hash, \
+             the bitmap, policy_commitment and the epoch",
+            &["load prev attestation", "attestation"],
+        )
+}
+
+// Public inputs of `CommitteeCircuit`, `active` is indexed by seat. The
+// attestation digest the next proof chains to is at `attestation_row`.
+pub fn instances<E: CurveAffine, N: FieldExt, const N_MAX: usize>(
+    members: &[E],
+    msg_hash: E::Scalar,
+    prev_attestation: N,
+    active: &[bool],
+    policy: &QuorumPolicy,
+    epoch: u64,
+) -> Vec<N> {
+    let signers = active.iter().filter(|&&active| active).count();
+    let signer_tags = (0..N_MAX)
+        .map(|i| match (members.get(i), active.get(i)) {
+            (Some(member), Some(true)) => signer_tag::<E, N>(member, epoch),
+            _ => N::zero(),
+        })
+        .collect::<Vec<_>>();
+    let root = members_root::<E, N, N_MAX>(members);
+    let msg_hash = big_to_fe(fe_to_big(msg_hash));
+    let bitmap = encode_bitmap(active);
+    let policy_commitment = policy.commitment();
+    let attestation = attestation_digest(
+        prev_attestation,
+        root,
+        msg_hash,
+        bitmap,
+        policy_commitment,
+        epoch,
+    );
+    layout(N_MAX)
+        .encode(&[
+            ("msg_hash", &[msg_hash]),
+            ("signers", &[N::from(signers as u64)]),
+            ("signers_bitmap", &[bitmap]),
+            ("members_root", &[root]),
+            ("epoch", &[N::from(epoch)]),
+            ("signer_tags", &signer_tags),
+            ("policy_commitment", &[policy_commitment]),
+            ("prev_attestation", &[prev_attestation]),
+            ("attestation", &[attestation]),
+        ])
+        .expect("committee instances match their layout")
+}
@@ -0,0 +1,324 @@
+// Filecoin piece commitments (CommP) and their binding to attested data roots.
+//
+// CommP is the root of a binary SHA-254 Merkle tree (SHA-256 with the two
+// most significant bits of the digest cleared) over the fr32 padded data.
+// Attested data is identified by a Poseidon root, so the committee signs a
+// `PieceBinding` linking the two off-circuit, which lets attested data go
+// straight into storage deals. Deals over an aggregate of pieces bind the
+// aggregate, and a sub-piece is shown to be in the deal with a
+// `SubPieceProof`.
+use crate::poseidon;
+use halo2_proofs::arithmetic::FieldExt;
+use halo2curves::bn256::Fr;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+
+// Multicodec codes for fil-commitment-unsealed and sha2-256-trunc254-padded.
+const FIL_COMMITMENT_UNSEALED: u64 = 0xf101;
+const SHA2_256_TRUNC254_PADDED: u64 = 0x1012;
+
+const NODE_SIZE: usize = 32;
+const MIN_PIECE_SIZE: usize = 128;
+
+// Size of a piece once `size` bytes of payload are padded and fr32 expanded.
+pub fn padded_piece_size(size: usize) -> usize {
+    let padded = (size + 126) / 127 * 128;
+    padded.next_power_of_two().max(MIN_PIECE_SIZE)
+}
+
+// Insert two zero bits after every 254 bits of input.
+pub fn fr32_pad(unpadded: &[u8]) -> Vec<u8> {
+    assert_eq!(
+        unpadded.len() % 127,
+        0,
+        "input must be a multiple of 127 bytes"
+    );
+    let mut padded = vec![0u8; unpadded.len() / 127 * 128];
+    for (input, output) in unpadded.chunks(127).zip(padded.chunks_mut(128)) {
+        for i in 0..127 * 8 {
+            let bit = (input[i / 8] >> (i % 8)) & 1;
+            let j = i + 2 * (i / 254);
+            output[j / 8] |= bit << (j % 8);
+        }
+    }
+    padded
+}
+
+// Inverse of `fr32_pad`, dropping the two padding bits after every 254.
+pub fn fr32_unpad(padded: &[u8]) -> Vec<u8> {
+    assert_eq!(
+        padded.len() % 128,
+        0,
+        "input must be a multiple of 128 bytes"
+    );
+    let mut unpadded = vec![0u8; padded.len() / 128 * 127];
+    for (input, output) in padded.chunks(128).zip(unpadded.chunks_mut(127)) {
+        for i in 0..127 * 8 {
+            let j = i + 2 * (i / 254);
+            let bit = (input[j / 8] >> (j % 8)) & 1;
+            output[i / 8] |= bit << (i % 8);
+        }
+    }
+    unpadded
+}
+
+fn hash_nodes(left: &[u8], right: &[u8]) -> [u8; NODE_SIZE] {
+    let mut hasher = Sha256::new();
+    hasher.update(left);
+    hasher.update(right);
+    let mut digest: [u8; NODE_SIZE] = hasher.finalize().into();
+    digest[NODE_SIZE - 1] &= 0b0011_1111;
+    digest
+}
+
+// Compute the CommP of a payload, zero padding it to the next piece size.
+pub fn compute(payload: &[u8]) -> [u8; NODE_SIZE] {
+    let piece_size = padded_piece_size(payload.len());
+    let mut unpadded = payload.to_vec();
+    unpadded.resize(piece_size / 128 * 127, 0);
+
+    let mut level = fr32_pad(&unpadded)
+        .chunks(NODE_SIZE)
+        .map(|node| node.try_into().unwrap())
+        .collect::<Vec<[u8; NODE_SIZE]>>();
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| hash_nodes(&pair[0], &pair[1]))
+            .collect();
+    }
+    level[0]
+}
+
+fn is_piece_size(size: u64) -> bool {
+    size.is_power_of_two() && size >= MIN_PIECE_SIZE as u64
+}
+
+// Height of the tree of a piece, in nodes above the 32 byte leaves.
+fn height(piece_size: u64) -> usize {
+    (piece_size / NODE_SIZE as u64).trailing_zeros() as usize
+}
+
+// zeros[h] is the CommP of a zero piece of height h; fr32 padding maps zero
+// bytes to zero bytes.
+fn zero_nodes(height: usize) -> Vec<[u8; NODE_SIZE]> {
+    let mut zeros = vec![[0u8; NODE_SIZE]];
+    for h in 0..height {
+        zeros.push(hash_nodes(&zeros[h], &zeros[h]));
+    }
+    zeros
+}
+
+// Aggregate piece over sub-pieces, as in PoDSI deals. Sub-pieces are placed
+// in order, each at the next offset aligned to its own size and the gaps are
+// zero pieces, so the CommP of every sub-piece is a node of the aggregate
+// tree and its inclusion is a Merkle path. Only the nodes over a sub-piece
+// are kept, which makes aggregates of real sector sizes cheap.
+#[derive(Clone, Debug)]
+pub struct Aggregate {
+    piece_size: u64,
+    zeros: Vec<[u8; NODE_SIZE]>,
+    // levels[h][j] is the node of height h at index j, for the nodes over at
+    // least one sub-piece.
+    levels: Vec<BTreeMap<u64, [u8; NODE_SIZE]>>,
+    // Offset and padded size of every sub-piece.
+    pieces: Vec<(u64, u64)>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SubPieceProof {
+    // Index of the sub-piece among the nodes of its height.
+    pub index: u64,
+    // Path from the sub-piece to the aggregate root, bottom up.
+    pub siblings: Vec<[u8; NODE_SIZE]>,
+}
+
+impl Aggregate {
+    // Lay out sub-pieces, given as CommP and padded size, in an aggregate of
+    // `piece_size` bytes. Returns None when a size isn't a valid piece size
+    // or the sub-pieces don't fit.
+    pub fn new(piece_size: u64, pieces: &[([u8; NODE_SIZE], u64)]) -> Option<Self> {
+        if !is_piece_size(piece_size) {
+            return None;
+        }
+        let top = height(piece_size);
+        let mut levels = vec![BTreeMap::new(); top + 1];
+        let mut placed = Vec::with_capacity(pieces.len());
+        let mut offset = 0u64;
+        for (commp, size) in pieces {
+            if !is_piece_size(*size) {
+                return None;
+            }
+            offset = (offset + size - 1) / size * size;
+            if offset.checked_add(*size)? > piece_size {
+                return None;
+            }
+            levels[height(*size)].insert(offset / size, *commp);
+            placed.push((offset, *size));
+            offset += size;
+        }
+
+        let zeros = zero_nodes(top);
+        for h in 0..top {
+            let mut parents = levels[h].keys().map(|index| index / 2).collect::<Vec<_>>();
+            parents.dedup();
+            for parent in parents {
+                let node = |index| levels[h].get(&index).copied().unwrap_or(zeros[h]);
+                let hash = hash_nodes(&node(2 * parent), &node(2 * parent + 1));
+                levels[h + 1].insert(parent, hash);
+            }
+        }
+
+        Some(Self {
+            piece_size,
+            zeros,
+            levels,
+            pieces: placed,
+        })
+    }
+
+    pub fn piece_size(&self) -> u64 {
+        self.piece_size
+    }
+
+    // Offset and padded size of every sub-piece, in the order given.
+    pub fn pieces(&self) -> &[(u64, u64)] {
+        &self.pieces
+    }
+
+    fn node(&self, height: usize, index: u64) -> [u8; NODE_SIZE] {
+        self.levels[height]
+            .get(&index)
+            .copied()
+            .unwrap_or(self.zeros[height])
+    }
+
+    pub fn commp(&self) -> [u8; NODE_SIZE] {
+        self.node(self.levels.len() - 1, 0)
+    }
+
+    // Inclusion proof of the i-th sub-piece.
+    pub fn prove(&self, i: usize) -> Option<SubPieceProof> {
+        let (offset, size) = *self.pieces.get(i)?;
+        let mut index = offset / size;
+        let siblings = (height(size)..self.levels.len() - 1)
+            .map(|h| {
+                let sibling = self.node(h, index ^ 1);
+                index >>= 1;
+                sibling
+            })
+            .collect();
+        Some(SubPieceProof {
+            index: offset / size,
+            siblings,
+        })
+    }
+
+    // Binding of the aggregate to an attested data root, for the committee
+    // to sign.
+    pub fn binding(&self, data_root: Fr) -> PieceBinding {
+        PieceBinding {
+            data_root,
+            commp: self.commp(),
+            piece_size: self.piece_size,
+        }
+    }
+}
+
+impl SubPieceProof {
+    // Check that the piece `commp` of `size` bytes sits at `self.index` of
+    // the aggregate `aggregate` of `aggregate_size` bytes, e.g. the piece of
+    // a signed `PieceBinding`.
+    pub fn verify(
+        &self,
+        commp: &[u8; NODE_SIZE],
+        size: u64,
+        aggregate: &[u8; NODE_SIZE],
+        aggregate_size: u64,
+    ) -> bool {
+        if !is_piece_size(size) || !is_piece_size(aggregate_size) || size > aggregate_size {
+            return false;
+        }
+        if self.siblings.len() != height(aggregate_size) - height(size)
+            || self
+                .index
+                .checked_shr(self.siblings.len() as u32)
+                .map_or(false, |high| high != 0)
+        {
+            return false;
+        }
+
+        let mut index = self.index;
+        let mut node = *commp;
+        for sibling in self.siblings.iter() {
+            node = if index & 1 == 0 {
+                hash_nodes(&node, sibling)
+            } else {
+                hash_nodes(sibling, &node)
+            };
+            index >>= 1;
+        }
+        node == *aggregate
+    }
+
+    // Byte offset of the sub-piece in the aggregate.
+    pub fn offset(&self, size: u64) -> u64 {
+        self.index * size
+    }
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+// Binary CIDv1 of a piece commitment.
+pub fn piece_cid(commp: &[u8; NODE_SIZE]) -> Vec<u8> {
+    let mut cid = vec![];
+    write_varint(&mut cid, 1);
+    write_varint(&mut cid, FIL_COMMITMENT_UNSEALED);
+    write_varint(&mut cid, SHA2_256_TRUNC254_PADDED);
+    write_varint(&mut cid, NODE_SIZE as u64);
+    cid.extend_from_slice(commp);
+    cid
+}
+
+// Statement signed by the committee that the attested data root and the
+// piece commitment describe the same payload.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PieceBinding {
+    pub data_root: Fr,
+    pub commp: [u8; NODE_SIZE],
+    pub piece_size: u64,
+}
+
+impl PieceBinding {
+    pub fn new(data_root: Fr, payload: &[u8]) -> Self {
+        Self {
+            data_root,
+            commp: compute(payload),
+            piece_size: padded_piece_size(payload.len()) as u64,
+        }
+    }
+
+    pub fn piece_cid(&self) -> Vec<u8> {
+        piece_cid(&self.commp)
+    }
+
+    // Poseidon digest of the binding; CommP is split into two 128 bit limbs
+    // since it doesn't fit the scalar field.
+    pub fn digest(&self) -> Fr {
+        let lo = u128::from_le_bytes(self.commp[..16].try_into().unwrap());
+        let hi = u128::from_le_bytes(self.commp[16..].try_into().unwrap());
+        poseidon::hash([
+            self.data_root,
+            Fr::from_u128(lo),
+            Fr::from_u128(hi),
+            Fr::from(self.piece_size),
+        ])
+    }
+}
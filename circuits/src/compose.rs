@@ -0,0 +1,209 @@
+// Circuits assembled from sub-circuits. Every part configures its own
+// columns next to a set shared by all of them: an advice column with
+// equality enabled, the main gate and the Poseidon chip. Public inputs go to
+// the instance column of the main gate, through `Shared::expose`.
+// A part exposes a fixed number of public inputs from the offset it is given
+// and declares an upper bound of the rows it assigns. A composite is then
+// just a tuple of parts, and its size follows from the parts instead of from
+// keygen failing with NotEnoughRows.
+//
+// Parts hand cells on to the parts after them through `Wires`, so e.g. the
+// committee verifies signatures in one part and hashes the verified keys
+// into the members root in the next.
+use crate::poseidon::{self, PoseidonConfig};
+use crate::report::report;
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error},
+};
+use maingate::{MainGate, MainGateConfig, MainGateInstructions};
+use std::collections::BTreeMap;
+
+#[derive(Clone, Debug)]
+pub struct Shared<F: FieldExt> {
+    pub value: Column<Advice>,
+    pub main_gate: MainGateConfig,
+    pub poseidon: PoseidonConfig<F>,
+}
+
+impl<F: FieldExt> Shared<F> {
+    // Configure before any part, the main gate brings the instance column
+    // of the composite.
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let main_gate = MainGate::<F>::configure(meta);
+        assert_eq!(
+            meta.num_instance_columns(),
+            1,
+            "shared columns are configured first"
+        );
+        let value = meta.advice_column();
+        meta.enable_equality(value);
+
+        Self {
+            value,
+            main_gate,
+            poseidon: poseidon::configure(meta),
+        }
+    }
+
+    // Constrain `cell` to row `row` of the instance column.
+    pub fn expose(
+        &self,
+        layouter: impl Layouter<F>,
+        cell: AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        MainGate::<F>::new(self.main_gate.clone()).expose_public(layouter, cell, row)
+    }
+}
+
+// Cells parts hand on to the parts after them, by name. A part taking a
+// name no earlier part put fails synthesis, as a composite with its parts
+// in the wrong order should.
+#[derive(Clone, Debug)]
+pub struct Wires<F: FieldExt>(BTreeMap<&'static str, Vec<AssignedCell<F, F>>>);
+
+impl<F: FieldExt> Wires<F> {
+    pub fn new() -> Self {
+        Self(BTreeMap::new())
+    }
+
+    pub fn put(&mut self, name: &'static str, cells: Vec<AssignedCell<F, F>>) {
+        self.0.insert(name, cells);
+    }
+
+    pub fn get(&self, name: &'static str) -> Result<&[AssignedCell<F, F>], Error> {
+        self.0.get(name).map(Vec::as_slice).ok_or(Error::Synthesis)
+    }
+}
+
+impl<F: FieldExt> Default for Wires<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub trait SubCircuit<F: FieldExt>: Clone {
+    type Config: Clone;
+
+    fn without_witnesses(&self) -> Self;
+
+    fn configure(meta: &mut ConstraintSystem<F>, shared: &Shared<F>) -> Self::Config;
+
+    // Number of rows of the instance column the part constrains.
+    fn instances(&self) -> usize;
+
+    // Upper bound of the rows `synthesize` assigns. Parts share columns, so
+    // the rows of a composite are at most the sum over its parts.
+    fn rows(&self) -> usize;
+
+    // Assign the part, exposing its public inputs from row `offset` of the
+    // instance column.
+    fn synthesize(
+        &self,
+        config: &Self::Config,
+        shared: &Shared<F>,
+        layouter: impl Layouter<F>,
+        offset: usize,
+        wires: &mut Wires<F>,
+    ) -> Result<(), Error>;
+}
+
+// Rows of a part synthesized on its own, for parts built on chips whose rows
+// have no closed form, e.g. ECDSA verification in halo2wrong. Only parts
+// that take no wires can be measured.
+pub fn measured_rows<F: FieldExt, S: SubCircuit<F>>(part: &S) -> usize {
+    report(&Composite(part.without_witnesses()))
+        .expect("the part synthesizes on its own")
+        .rows
+}
+
+// Parts are laid out and expose their public inputs in tuple order.
+macro_rules! tuple_sub_circuit {
+    ($($part:ident $index:tt),+) => {
+        impl<F: FieldExt, $($part: SubCircuit<F>),+> SubCircuit<F> for ($($part,)+) {
+            type Config = ($($part::Config,)+);
+
+            fn without_witnesses(&self) -> Self {
+                ($(self.$index.without_witnesses(),)+)
+            }
+
+            fn configure(meta: &mut ConstraintSystem<F>, shared: &Shared<F>) -> Self::Config {
+                ($($part::configure(meta, shared),)+)
+            }
+
+            fn instances(&self) -> usize {
+                0 $(+ self.$index.instances())+
+            }
+
+            fn rows(&self) -> usize {
+                0 $(+ self.$index.rows())+
+            }
+
+            #[allow(unused_assignments)]
+            fn synthesize(
+                &self,
+                config: &Self::Config,
+                shared: &Shared<F>,
+                mut layouter: impl Layouter<F>,
+                mut offset: usize,
+                wires: &mut Wires<F>,
+            ) -> Result<(), Error> {
+                $(
+                    self.$index.synthesize(
+                        &config.$index,
+                        shared,
+                        layouter.namespace(|| format!("part {}", $index)),
+                        offset,
+                        wires,
+                    )?;
+                    offset += self.$index.instances();
+                )+
+                Ok(())
+            }
+        }
+    };
+}
+
+tuple_sub_circuit!(A 0, B 1);
+tuple_sub_circuit!(A 0, B 1, C 2);
+tuple_sub_circuit!(A 0, B 1, C 2, D 3);
+
+#[derive(Clone, Debug)]
+pub struct Composite<S>(pub S);
+
+impl<S> Composite<S> {
+    // Smallest k with room for the declared rows and the rows halo2 reserves
+    // for blinding.
+    pub fn min_k<F: FieldExt>(&self) -> u32
+    where
+        S: SubCircuit<F>,
+    {
+        let mut meta = ConstraintSystem::<F>::default();
+        <Self as Circuit<F>>::configure(&mut meta);
+        let rows = self.0.rows() + meta.minimum_rows();
+        rows.next_power_of_two().trailing_zeros()
+    }
+}
+
+impl<F: FieldExt, S: SubCircuit<F>> Circuit<F> for Composite<S> {
+    type Config = (Shared<F>, S::Config);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self(self.0.without_witnesses())
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let shared = Shared::configure(meta);
+        let config = S::configure(meta, &shared);
+        (shared, config)
+    }
+
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<F>) -> Result<(), Error> {
+        let (shared, config) = config;
+        self.0
+            .synthesize(&config, &shared, layouter, 0, &mut Wires::new())
+    }
+}
@@ -0,0 +1,275 @@
+use crate::secret::Secret;
+use ecc::{AssignedPoint, EccConfig, GeneralEccChip};
+use ff::Field;
+use halo2_proofs::{
+    arithmetic::{CurveAffine, FieldExt},
+    circuit::Layouter,
+    plonk::{ConstraintSystem, Error},
+};
+use halo2curves::group::Curve;
+use integer::{rns::Integer, AssignedInteger, IntegerChip, IntegerConfig, IntegerInstructions};
+use maingate::{
+    big_to_fe, fe_to_big, MainGate, MainGateConfig, RangeChip, RangeConfig, RangeInstructions,
+    RegionCtx,
+};
+use rand::RngCore;
+
+pub const BIT_LEN_LIMB: usize = 68;
+pub const NUMBER_OF_LIMBS: usize = 4;
+
+// Points per multi-scalar multiplication of `EcdsaChip::verify`, the aux
+// values of the ECC chip have to be assigned for this many pairs.
+pub const MUL_PAIRS: usize = 2;
+
+#[derive(Clone, Debug)]
+pub struct EcdsaConfig {
+    main_gate_config: MainGateConfig,
+    range_config: RangeConfig,
+}
+
+impl EcdsaConfig {
+    pub fn new(range_config: RangeConfig, main_gate_config: MainGateConfig) -> Self {
+        Self {
+            range_config,
+            main_gate_config,
+        }
+    }
+
+    pub fn ecc_chip_config(&self) -> EccConfig {
+        EccConfig::new(self.range_config.clone(), self.main_gate_config.clone())
+    }
+
+    pub fn integer_chip_config(&self) -> IntegerConfig {
+        IntegerConfig::new(self.range_config.clone(), self.main_gate_config.clone())
+    }
+
+    pub fn main_gate_config(&self) -> MainGateConfig {
+        self.main_gate_config.clone()
+    }
+
+    pub fn range_config(&self) -> RangeConfig {
+        self.range_config.clone()
+    }
+
+    // Configure a main gate and a range chip wide enough for the base and
+    // scalar field integers of `E`.
+    pub fn configure<E: CurveAffine, N: FieldExt>(meta: &mut ConstraintSystem<N>) -> Self {
+        let main_gate_config = MainGate::<N>::configure(meta);
+        Self::configure_with::<E, N>(meta, main_gate_config)
+    }
+
+    // The same on a main gate the circuit already has, e.g. the shared one of
+    // a composite.
+    pub fn configure_with<E: CurveAffine, N: FieldExt>(
+        meta: &mut ConstraintSystem<N>,
+        main_gate_config: MainGateConfig,
+    ) -> Self {
+        let (rns_base, rns_scalar) = GeneralEccChip::<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>::rns();
+        let mut overflow_bit_lens: Vec<usize> = vec![];
+        overflow_bit_lens.extend(rns_base.overflow_lengths());
+        overflow_bit_lens.extend(rns_scalar.overflow_lengths());
+        let composition_bit_lens = vec![BIT_LEN_LIMB / NUMBER_OF_LIMBS];
+
+        let range_config = RangeChip::<N>::configure(
+            meta,
+            &main_gate_config,
+            composition_bit_lens,
+            overflow_bit_lens,
+        );
+        Self::new(range_config, main_gate_config)
+    }
+
+    pub fn config_range<N: FieldExt>(&self, layouter: &mut impl Layouter<N>) -> Result<(), Error> {
+        let range_chip = RangeChip::<N>::new(self.range_config.clone());
+        range_chip.load_table(layouter)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct EcdsaSig<
+    W: FieldExt,
+    N: FieldExt,
+    const NUMBER_OF_LIMBS: usize,
+    const BIT_LEN_LIMB: usize,
+> {
+    pub r: Integer<W, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>,
+    pub s: Integer<W, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>,
+}
+
+pub struct AssignedEcdsaSig<
+    W: FieldExt,
+    N: FieldExt,
+    const NUMBER_OF_LIMBS: usize,
+    const BIT_LEN_LIMB: usize,
+> {
+    pub r: AssignedInteger<W, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>,
+    pub s: AssignedInteger<W, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>,
+}
+
+pub struct AssignedPublicKey<
+    W: FieldExt,
+    N: FieldExt,
+    const NUMBER_OF_LIMBS: usize,
+    const BIT_LEN_LIMB: usize,
+> {
+    pub point: AssignedPoint<W, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>,
+}
+
+pub struct EcdsaChip<
+    E: CurveAffine,
+    N: FieldExt,
+    const NUMBER_OF_LIMBS: usize,
+    const BIT_LEN_LIMB: usize,
+> {
+    ecc_chip: GeneralEccChip<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>,
+    window_size: usize,
+}
+
+impl<E: CurveAffine, N: FieldExt, const NUMBER_OF_LIMBS: usize, const BIT_LEN_LIMB: usize>
+    EcdsaChip<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>
+{
+    // `window_size` has to match the aux values assigned to the ECC chip,
+    // see `MUL_PAIRS`.
+    pub fn new(
+        ecc_chip: GeneralEccChip<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>,
+        window_size: usize,
+    ) -> Self {
+        Self {
+            ecc_chip,
+            window_size,
+        }
+    }
+
+    pub fn scalar_field_chip(
+        &self,
+    ) -> &IntegerChip<E::ScalarExt, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB> {
+        self.ecc_chip.scalar_field_chip()
+    }
+
+    fn ecc_chip(&self) -> GeneralEccChip<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB> {
+        self.ecc_chip.clone()
+    }
+}
+
+impl<E: CurveAffine, N: FieldExt, const NUMBER_OF_LIMBS: usize, const BIT_LEN_LIMB: usize>
+    EcdsaChip<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>
+{
+    // The generator as a constant, so it's fixed by the verifying key and
+    // circuits checking several signatures can assign it once.
+    pub fn assign_generator(
+        &self,
+        ctx: &mut RegionCtx<'_, N>,
+    ) -> Result<AssignedPoint<E::Base, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>, Error> {
+        self.ecc_chip.assign_constant(ctx, E::generator())
+    }
+
+    pub fn verify(
+        &self,
+        ctx: &mut RegionCtx<'_, N>,
+        sig: &AssignedEcdsaSig<E::Scalar, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>,
+        pk: &AssignedPublicKey<E::Base, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>,
+        msg_hash: &AssignedInteger<E::Scalar, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>,
+    ) -> Result<(), Error> {
+        let generator = self.assign_generator(ctx)?;
+        self.verify_with_generator(ctx, &generator, sig, pk, msg_hash)
+    }
+
+    // Verify with a generator from `assign_generator`.
+    pub fn verify_with_generator(
+        &self,
+        ctx: &mut RegionCtx<'_, N>,
+        generator: &AssignedPoint<E::Base, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>,
+        sig: &AssignedEcdsaSig<E::Scalar, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>,
+        pk: &AssignedPublicKey<E::Base, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>,
+        msg_hash: &AssignedInteger<E::Scalar, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>,
+    ) -> Result<(), Error> {
+        let ecc_chip = self.ecc_chip();
+        let scalar_chip = ecc_chip.scalar_field_chip();
+        let base_chip = ecc_chip.base_field_chip();
+
+        // 1. check 0 < r, s < n
+
+        // since `assert_not_zero` already includes a in-field check, we can just
+        // call `assert_not_zero`
+        scalar_chip.assert_not_zero(ctx, &sig.r)?;
+        scalar_chip.assert_not_zero(ctx, &sig.s)?;
+
+        // 2. w = s^(-1) (mod n)
+        let (s_inv, _) = scalar_chip.invert(ctx, &sig.s)?;
+
+        // 3. u1 = m' * w (mod n)
+        let u1 = scalar_chip.mul(ctx, msg_hash, &s_inv)?;
+
+        // 4. u2 = r * w (mod n)
+        let u2 = scalar_chip.mul(ctx, &sig.r, &s_inv)?;
+
+        // 5. compute Q = u1*G + u2*pk, as one multi-scalar multiplication so
+        // both terms share the doublings
+        let q = ecc_chip.mul_batch_1d_horizontal(
+            ctx,
+            vec![(generator.clone(), u1), (pk.point.clone(), u2)],
+            self.window_size,
+        )?;
+
+        // 6. reduce q_x in E::ScalarExt
+        // assuming E::Base/E::ScalarExt have the same number of limbs
+        let q_x = q.x();
+        let q_x_reduced_in_q = base_chip.reduce(ctx, q_x)?;
+        let q_x_reduced_in_r = scalar_chip.reduce_external(ctx, &q_x_reduced_in_q)?;
+
+        // 7. check if Q.x == r (mod n)
+        scalar_chip.assert_strict_equal(ctx, &q_x_reduced_in_r, &sig.r)?;
+
+        Ok(())
+    }
+}
+
+pub fn mod_n<C: CurveAffine>(x: C::Base) -> C::Scalar {
+    let x_big = fe_to_big(x);
+    big_to_fe(x_big)
+}
+
+// Sign a message hash natively, retrying in the unlikely case the nonce
+// yields r = 0 or s = 0. The key and the nonce only go through the constant
+// time scalar multiplication and field arithmetic of halo2curves.
+pub fn sign<C: CurveAffine>(
+    sk: &Secret<C::ScalarExt>,
+    msg_hash: C::ScalarExt,
+    mut rng: impl RngCore,
+) -> (C::Scalar, C::Scalar) {
+    loop {
+        let k = Secret::new(C::ScalarExt::random(&mut rng));
+        let k_inv = Secret::new(k.expose().invert().unwrap());
+
+        let r_point = (C::generator() * *k.expose())
+            .to_affine()
+            .coordinates()
+            .unwrap();
+        let r = mod_n::<C>(*r_point.x());
+        let s = *k_inv.expose() * (msg_hash + (r * *sk.expose()));
+
+        if !bool::from(r.is_zero()) && !bool::from(s.is_zero()) {
+            return (r, s);
+        }
+    }
+}
+
+pub fn verify_signature<C: CurveAffine>(
+    public_key: C,
+    msg_hash: C::ScalarExt,
+    (r, s): (C::Scalar, C::Scalar),
+) -> bool {
+    let s_inv = s.invert();
+    if bool::from(r.is_zero()) || bool::from(s_inv.is_none()) {
+        return false;
+    }
+    let s_inv = s_inv.unwrap();
+
+    let point = (C::generator() * (msg_hash * s_inv)) + (public_key * (r * s_inv));
+    match Option::<_>::from(point.to_affine().coordinates()) {
+        Some(coordinates) => mod_n::<C>(*coordinates.x()) == r,
+        None => false,
+    }
+}
@@ -0,0 +1,193 @@
+// Proof that a KZG blob commitment and a Poseidon Merkle root commit to the
+// same data.
+//
+// The data is read as the coefficients of a polynomial p. The circuit
+// computes the Poseidon root of the data, derives the challenge
+// z = Poseidon(root, commitment) and exposes y = p(z). Checking the KZG
+// opening of the commitment at (z, y) outside of the circuit then binds the
+// commitment to the root without committing to the data twice.
+use crate::horner::{self, HornerChip, HornerConfig};
+use crate::merkle::{self, MerkleChip};
+use crate::poseidon::{self, PoseidonConfig};
+use halo2_proofs::{
+    arithmetic::{Field, FieldExt},
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance},
+    poly::{
+        commitment::{Blind, Params, ParamsProver},
+        kzg::commitment::ParamsKZG,
+        EvaluationDomain,
+    },
+};
+use halo2curves::bn256::{Bn256, Fr, G1Affine, G2Affine, G1, G2};
+use halo2curves::group::{prime::PrimeCurveAffine, Curve, GroupEncoding};
+use halo2curves::pairing::Engine;
+use std::convert::TryInto;
+
+// Rows of the instance column.
+pub const ROOT: usize = 0;
+pub const COMMITMENT_LO: usize = 1;
+pub const COMMITMENT_HI: usize = 2;
+pub const EVALUATION: usize = 3;
+
+#[derive(Clone, Copy)]
+pub struct EquivalenceCircuit<const N: usize> {
+    pub data: Value<[Fr; N]>,
+}
+
+#[derive(Clone, Debug)]
+pub struct EquivalenceConfig {
+    data: Column<Advice>,
+    instance: Column<Instance>,
+    poseidon: PoseidonConfig<Fr>,
+    horner: HornerConfig,
+}
+
+impl<const N: usize> Circuit<Fr> for EquivalenceCircuit<N> {
+    type Config = EquivalenceConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            data: Value::unknown(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        let data = meta.advice_column();
+        meta.enable_equality(data);
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+
+        let horner = {
+            let coeff = meta.advice_column();
+            let point = meta.advice_column();
+            let acc = meta.advice_column();
+            HornerChip::configure(meta, coeff, point, acc)
+        };
+
+        EquivalenceConfig {
+            data,
+            instance,
+            poseidon: poseidon::configure(meta),
+            horner,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let data = layouter.assign_region(
+            || "load data",
+            |mut region| {
+                (0..N)
+                    .map(|i| {
+                        region.assign_advice(
+                            || format!("data_{}", i),
+                            config.data,
+                            i,
+                            || self.data.map(|data| data[i]),
+                        )
+                    })
+                    .collect::<Result<Vec<_>, Error>>()
+            },
+        )?;
+
+        let commitment = layouter.assign_region(
+            || "load commitment",
+            |mut region| {
+                let lo = region.assign_advice_from_instance(
+                    || "commitment lo",
+                    config.instance,
+                    COMMITMENT_LO,
+                    config.data,
+                    0,
+                )?;
+                let hi = region.assign_advice_from_instance(
+                    || "commitment hi",
+                    config.instance,
+                    COMMITMENT_HI,
+                    config.data,
+                    1,
+                )?;
+                Ok([lo, hi])
+            },
+        )?;
+
+        let merkle_chip = MerkleChip::new(config.poseidon.clone());
+        let root = merkle_chip.root(layouter.namespace(|| "data root"), &data)?;
+
+        let [lo, hi] = commitment;
+        let challenge = poseidon::hash_assigned(
+            &config.poseidon,
+            layouter.namespace(|| "challenge"),
+            [root.clone(), lo, hi],
+        )?;
+
+        let horner_chip = HornerChip::new(config.horner.clone());
+        let evaluation =
+            horner_chip.evaluate(layouter.namespace(|| "evaluate"), &data, &challenge)?;
+
+        layouter.constrain_instance(root.cell(), config.instance, ROOT)?;
+        layouter.constrain_instance(evaluation.cell(), config.instance, EVALUATION)
+    }
+}
+
+// Split the compressed commitment into two 128 bit limbs so it fits the
+// scalar field.
+pub fn commitment_limbs(commitment: &G1Affine) -> [Fr; 2] {
+    let bytes = commitment.to_bytes();
+    let bytes = bytes.as_ref();
+    let lo = u128::from_le_bytes(bytes[..16].try_into().unwrap());
+    let hi = u128::from_le_bytes(bytes[16..].try_into().unwrap());
+    [Fr::from_u128(lo), Fr::from_u128(hi)]
+}
+
+pub fn challenge(root: Fr, commitment: &G1Affine) -> Fr {
+    let [lo, hi] = commitment_limbs(commitment);
+    poseidon::hash([root, lo, hi])
+}
+
+// Commit to the data as polynomial coefficients.
+pub fn commit(params: &ParamsKZG<Bn256>, data: &[Fr]) -> G1Affine {
+    let domain = EvaluationDomain::new(1, params.k());
+    let mut coeffs = data.to_vec();
+    coeffs.resize(params.n() as usize, Fr::zero());
+    let poly = domain.coeff_from_vec(coeffs);
+    params.commit(&poly, Blind::default()).to_affine()
+}
+
+// Open the data polynomial at `point`, returning the commitment to the
+// quotient (p(X) - p(point)) / (X - point).
+pub fn open(params: &ParamsKZG<Bn256>, data: &[Fr], point: Fr) -> G1Affine {
+    let mut quotient = vec![Fr::zero(); data.len().saturating_sub(1)];
+    let mut carry = Fr::zero();
+    for i in (1..data.len()).rev() {
+        carry = data[i] + carry * point;
+        quotient[i - 1] = carry;
+    }
+    commit(params, &quotient)
+}
+
+// Check e(proof, [s - z]_2) == e(C - [y]_1, [1]_2).
+pub fn verify_opening(
+    params: &ParamsKZG<Bn256>,
+    commitment: &G1Affine,
+    point: Fr,
+    evaluation: Fr,
+    proof: &G1Affine,
+) -> bool {
+    let s_minus_z = (G2::from(params.s_g2()) - params.g2() * point).to_affine();
+    let c_minus_y = (G1::from(*commitment) - G1Affine::generator() * evaluation).to_affine();
+    Bn256::pairing(proof, &s_minus_z) == Bn256::pairing(&c_minus_y, &G2Affine::generator())
+}
+
+// Public inputs of `EquivalenceCircuit` for the given data and commitment.
+pub fn instances(data: &[Fr], commitment: &G1Affine) -> Vec<Fr> {
+    let root = merkle::root(data);
+    let [lo, hi] = commitment_limbs(commitment);
+    let evaluation = horner::evaluate(data, challenge(root, commitment));
+    vec![root, lo, hi, evaluation]
+}
@@ -0,0 +1,282 @@
+// Reed-Solomon erasure coding of attested data over the native field. The
+// data elements are the coefficients of a polynomial of degree below their
+// count k, and shard i is its evaluation at i, so any k shards recover the
+// data by interpolation. Attested data is identified by the Poseidon root
+// over its elements, as in `commp::PieceBinding`, and a reconstruction is
+// only accepted when it matches the root.
+//
+// `EncodingCircuit` proves that the root over the shards commits to a valid
+// encoding of the data under the data root, so a consumer in the
+// pessimistic mode accepts the shards without waiting on fraud proofs. It
+// evaluates the data polynomial at every shard index, which costs one row
+// per data element and shard and grows quadratically with the blob.
+use crate::horner::{self, HornerChip, HornerConfig};
+use crate::layout::InstanceLayout;
+use crate::merkle::{self, MerkleChip};
+use crate::poseidon::{self, PoseidonConfig};
+use crate::spec::StatementSpec;
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance},
+};
+use halo2curves::bn256::Fr;
+use std::collections::BTreeMap;
+
+// Rows of the instance column, see `layout`.
+pub const DATA_ROOT: usize = 0;
+pub const SHARDS_ROOT: usize = 1;
+
+pub fn layout() -> InstanceLayout {
+    InstanceLayout::new("erasure-encoding", 1)
+        .field("data_root", 1)
+        .field("shards_root", 1)
+}
+
+pub fn spec() -> StatementSpec {
+    StatementSpec::new(layout())
+        .relation(
+            "data",
+            "data_root is the Merkle root over the data elements",
+            &["load data", "data root"],
+        )
+        .relation(
+            "encoding",
+            "shards_root is the Merkle root over the evaluations of the data \
+             polynomial at every shard index",
+            &["load points", "shard 0", "shards root"],
+        )
+}
+
+// Extend `data` to `shards` evaluations.
+pub fn encode<F: FieldExt>(data: &[F], shards: usize) -> Vec<F> {
+    assert!(shards >= data.len(), "fewer shards than data elements");
+    (0..shards as u64)
+        .map(|i| horner::evaluate(data, F::from(i)))
+        .collect()
+}
+
+// Interpolate the `data_len` coefficients from as many shards, given with
+// their index. Returns None for repeated indexes or too few shards.
+pub fn interpolate<F: FieldExt>(data_len: usize, shards: &[(usize, F)]) -> Option<Vec<F>> {
+    let shards = shards.get(..data_len)?;
+    let points = shards
+        .iter()
+        .map(|(i, _)| F::from(*i as u64))
+        .collect::<Vec<_>>();
+
+    // Z(x) = prod (x - x_m), lowest degree first.
+    let mut vanishing = vec![F::one()];
+    for point in points.iter() {
+        let mut next = vec![F::zero(); vanishing.len() + 1];
+        for (d, coeff) in vanishing.iter().enumerate() {
+            next[d + 1] += coeff;
+            next[d] -= *coeff * point;
+        }
+        vanishing = next;
+    }
+
+    let mut data = vec![F::zero(); data_len];
+    for (j, (point, (_, value))) in points.iter().zip(shards.iter()).enumerate() {
+        let denominator = points
+            .iter()
+            .enumerate()
+            .filter(|(m, _)| *m != j)
+            .fold(F::one(), |acc, (_, other)| acc * (*point - other));
+        let scale = *value * Option::<F>::from(denominator.invert())?;
+
+        // Z(x) / (x - x_j) by synthetic division, highest degree first.
+        let mut carry = F::zero();
+        for d in (0..data_len).rev() {
+            carry = vanishing[d + 1] + carry * point;
+            data[d] += carry * scale;
+        }
+    }
+    Some(data)
+}
+
+// Collects the shards of one attested blob until the data can be recovered.
+#[derive(Clone, Debug)]
+pub struct Reconstruction<F: FieldExt> {
+    data_root: F,
+    data_len: usize,
+    total: usize,
+    shards: BTreeMap<usize, F>,
+}
+
+impl<F: FieldExt> Reconstruction<F> {
+    // `data_len` must be a power of two, like the leaves of the data root.
+    pub fn new(data_root: F, data_len: usize, total: usize) -> Self {
+        assert!(
+            data_len.is_power_of_two(),
+            "data length must be a power of two"
+        );
+        assert!(total >= data_len, "fewer shards than data elements");
+        Self {
+            data_root,
+            data_len,
+            total,
+            shards: BTreeMap::new(),
+        }
+    }
+
+    // Add a sampled or collected shard. Returns false when the index is out
+    // of range or a different shard was added at it before.
+    pub fn add(&mut self, index: usize, shard: F) -> bool {
+        if index >= self.total {
+            return false;
+        }
+        *self.shards.entry(index).or_insert(shard) == shard
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.shards.len() >= self.data_len
+    }
+
+    // Indexes that weren't collected yet, e.g. to repair.
+    pub fn missing(&self) -> Vec<usize> {
+        (0..self.total)
+            .filter(|i| !self.shards.contains_key(i))
+            .collect()
+    }
+
+    // The data, once enough shards are in, every collected shard agrees with
+    // it and it matches the data root.
+    pub fn reconstruct(&self) -> Option<Vec<F>> {
+        let shards = self
+            .shards
+            .iter()
+            .map(|(i, shard)| (*i, *shard))
+            .collect::<Vec<_>>();
+        let data = interpolate(self.data_len, &shards)?;
+        let consistent = shards[self.data_len..]
+            .iter()
+            .all(|(i, shard)| horner::evaluate(&data, F::from(*i as u64)) == *shard);
+        (consistent && merkle::root(&data) == self.data_root).then_some(data)
+    }
+}
+
+// Public inputs of `EncodingCircuit`; `data` and `shards` must both have a
+// power of two length.
+pub fn instances(data: &[Fr], shards: usize) -> Vec<Fr> {
+    layout()
+        .encode(&[
+            ("data_root", &[merkle::root(data)]),
+            ("shards_root", &[merkle::root(&encode(data, shards))]),
+        ])
+        .expect("encoding instances match the layout")
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct EncodingCircuit<const DATA: usize, const SHARDS: usize> {
+    pub data: Value<[Fr; DATA]>,
+}
+
+impl<const DATA: usize, const SHARDS: usize> EncodingCircuit<DATA, SHARDS> {
+    pub fn new(data: [Fr; DATA]) -> Self {
+        Self {
+            data: Value::known(data),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct EncodingConfig {
+    value: Column<Advice>,
+    instance: Column<Instance>,
+    poseidon: PoseidonConfig<Fr>,
+    horner: HornerConfig,
+}
+
+impl<const DATA: usize, const SHARDS: usize> Circuit<Fr> for EncodingCircuit<DATA, SHARDS> {
+    type Config = EncodingConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            data: Value::unknown(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        assert!(
+            DATA.is_power_of_two() && SHARDS.is_power_of_two(),
+            "data and shards must be powers of two"
+        );
+        assert!(SHARDS >= DATA, "fewer shards than data elements");
+
+        let value = meta.advice_column();
+        meta.enable_equality(value);
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+
+        let horner = {
+            let coeff = meta.advice_column();
+            let point = meta.advice_column();
+            let acc = meta.advice_column();
+            HornerChip::configure(meta, coeff, point, acc)
+        };
+
+        EncodingConfig {
+            value,
+            instance,
+            poseidon: poseidon::configure(meta),
+            horner,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let data = layouter.assign_region(
+            || "load data",
+            |mut region| {
+                (0..DATA)
+                    .map(|i| {
+                        region.assign_advice(
+                            || format!("data_{}", i),
+                            config.value,
+                            i,
+                            || self.data.map(|data| data[i]),
+                        )
+                    })
+                    .collect::<Result<Vec<_>, Error>>()
+            },
+        )?;
+
+        // Shard indexes are fixed, so a prover can't move an evaluation.
+        let points = layouter.assign_region(
+            || "load points",
+            |mut region| {
+                (0..SHARDS)
+                    .map(|i| {
+                        region.assign_advice_from_constant(
+                            || format!("point_{}", i),
+                            config.value,
+                            i,
+                            Fr::from(i as u64),
+                        )
+                    })
+                    .collect::<Result<Vec<_>, Error>>()
+            },
+        )?;
+
+        let horner_chip = HornerChip::new(config.horner.clone());
+        let shards = points
+            .iter()
+            .enumerate()
+            .map(|(i, point)| {
+                horner_chip.evaluate(layouter.namespace(|| format!("shard {}", i)), &data, point)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let merkle_chip = MerkleChip::new(config.poseidon.clone());
+        let data_root = merkle_chip.root(layouter.namespace(|| "data root"), &data)?;
+        let shards_root = merkle_chip.root(layouter.namespace(|| "shards root"), &shards)?;
+
+        layouter.constrain_instance(data_root.cell(), config.instance, DATA_ROOT)?;
+        layouter.constrain_instance(shards_root.cell(), config.instance, SHARDS_ROOT)
+    }
+}
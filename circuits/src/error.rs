@@ -0,0 +1,206 @@
+use halo2_proofs::plonk;
+use std::{error, fmt, io};
+
+// Errors returned by the circuit and proving APIs of the crate. Callers can
+// use `is_retryable` to tell transient failures from ones that will keep
+// failing until the circuit or its inputs change.
+//
+// The crate itself doesn't talk to peers or chains, `Network` and `Submit`
+// are for the node and the submitters built on it, so one error type covers
+// the pipeline from collecting signatures to landing the proof.
+#[derive(Debug)]
+pub enum QuarryError {
+    Proof(ProofError),
+    Circuit(CircuitError),
+    Network(NetworkError),
+    Submit(SubmitError),
+}
+
+#[derive(Debug)]
+pub enum ProofError {
+    Keygen(plonk::Error),
+    Prove(plonk::Error),
+    // The proof was read successfully but doesn't verify.
+    Invalid,
+    // The proof couldn't be read, e.g. because it's truncated or garbage.
+    // Like `Invalid` this won't change on a retry.
+    Transcript(io::Error),
+    // The key isn't the one registered for the circuit and version.
+    Unregistered(String),
+}
+
+#[derive(Debug)]
+pub enum CircuitError {
+    // The circuit needs more rows than 2^k.
+    NotEnoughRows { k: u32 },
+    // The witness doesn't satisfy the constraints or doesn't match the
+    // shape the circuit was configured for.
+    InvalidWitness(String),
+    Synthesis(plonk::Error),
+    // Public inputs that don't fit the instance layout of the circuit.
+    InstanceLayout(String),
+    // Parameters no circuit can be built with, e.g. no window sizes to tune.
+    InvalidParameters(String),
+}
+
+#[derive(Debug)]
+pub enum NetworkError {
+    // A peer or endpoint didn't answer, or not in time.
+    Unavailable(String),
+    // A peer answered with something that doesn't decode.
+    Malformed(String),
+}
+
+#[derive(Debug)]
+pub enum SubmitError {
+    // The fee or nonce of the message went stale before it was included,
+    // resubmitting with fresh ones can succeed.
+    Stale(String),
+    // The chain executed the message and rejected it, e.g. a verifier
+    // refusing the proof.
+    Rejected(String),
+}
+
+impl QuarryError {
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            QuarryError::Network(NetworkError::Unavailable(_))
+            | QuarryError::Submit(SubmitError::Stale(_)) => true,
+            QuarryError::Proof(_)
+            | QuarryError::Circuit(_)
+            | QuarryError::Network(_)
+            | QuarryError::Submit(_) => false,
+        }
+    }
+}
+
+impl fmt::Display for QuarryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuarryError::Proof(err) => write!(f, "proof error: {}", err),
+            QuarryError::Circuit(err) => write!(f, "circuit error: {}", err),
+            QuarryError::Network(err) => write!(f, "network error: {}", err),
+            QuarryError::Submit(err) => write!(f, "submit error: {}", err),
+        }
+    }
+}
+
+impl fmt::Display for ProofError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProofError::Keygen(err) => write!(f, "key generation failed: {}", err),
+            ProofError::Prove(err) => write!(f, "proof generation failed: {}", err),
+            ProofError::Invalid => write!(f, "proof is invalid"),
+            ProofError::Transcript(err) => write!(f, "transcript error: {}", err),
+            ProofError::Unregistered(circuit) => write!(f, "{} is not registered", circuit),
+        }
+    }
+}
+
+impl fmt::Display for CircuitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CircuitError::NotEnoughRows { k } => {
+                write!(f, "circuit doesn't fit in 2^{} rows", k)
+            }
+            CircuitError::InvalidWitness(reason) => write!(f, "invalid witness: {}", reason),
+            CircuitError::Synthesis(err) => write!(f, "synthesis failed: {}", err),
+            CircuitError::InstanceLayout(reason) => write!(f, "bad public inputs: {}", reason),
+            CircuitError::InvalidParameters(reason) => write!(f, "invalid parameters: {}", reason),
+        }
+    }
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetworkError::Unavailable(reason) => write!(f, "unavailable: {}", reason),
+            NetworkError::Malformed(reason) => write!(f, "malformed response: {}", reason),
+        }
+    }
+}
+
+impl fmt::Display for SubmitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SubmitError::Stale(reason) => write!(f, "stale message: {}", reason),
+            SubmitError::Rejected(reason) => write!(f, "message rejected: {}", reason),
+        }
+    }
+}
+
+impl error::Error for QuarryError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            QuarryError::Proof(err) => Some(err),
+            QuarryError::Circuit(err) => Some(err),
+            QuarryError::Network(err) => Some(err),
+            QuarryError::Submit(err) => Some(err),
+        }
+    }
+}
+
+impl error::Error for ProofError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ProofError::Keygen(err) | ProofError::Prove(err) => Some(err),
+            ProofError::Invalid | ProofError::Unregistered(_) => None,
+            ProofError::Transcript(err) => Some(err),
+        }
+    }
+}
+
+impl error::Error for CircuitError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            CircuitError::Synthesis(err) => Some(err),
+            CircuitError::NotEnoughRows { .. }
+            | CircuitError::InvalidWitness(_)
+            | CircuitError::InstanceLayout(_)
+            | CircuitError::InvalidParameters(_) => None,
+        }
+    }
+}
+
+impl error::Error for NetworkError {}
+
+impl error::Error for SubmitError {}
+
+impl From<ProofError> for QuarryError {
+    fn from(err: ProofError) -> Self {
+        QuarryError::Proof(err)
+    }
+}
+
+impl From<CircuitError> for QuarryError {
+    fn from(err: CircuitError) -> Self {
+        QuarryError::Circuit(err)
+    }
+}
+
+impl From<NetworkError> for QuarryError {
+    fn from(err: NetworkError) -> Self {
+        QuarryError::Network(err)
+    }
+}
+
+impl From<SubmitError> for QuarryError {
+    fn from(err: SubmitError) -> Self {
+        QuarryError::Submit(err)
+    }
+}
+
+// Errors coming out of the proof system that are really about the circuit
+// are reported as such, everything else is attributed to the proving step
+// `wrap` describes.
+pub(crate) fn from_plonk(err: plonk::Error, wrap: fn(plonk::Error) -> ProofError) -> QuarryError {
+    match err {
+        plonk::Error::NotEnoughRowsAvailable { current_k } => {
+            CircuitError::NotEnoughRows { k: current_k }.into()
+        }
+        plonk::Error::Synthesis => CircuitError::Synthesis(err).into(),
+        plonk::Error::ConstraintSystemFailure => ProofError::Invalid.into(),
+        plonk::Error::Transcript(err) => ProofError::Transcript(err).into(),
+        err => wrap(err).into(),
+    }
+}
@@ -0,0 +1,126 @@
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter},
+    plonk::{Advice, Column, ConstraintSystem, Error, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+// Native evaluation of a polynomial given by its coefficients, lowest
+// degree first.
+pub fn evaluate<F: FieldExt>(coeffs: &[F], point: F) -> F {
+    coeffs
+        .iter()
+        .rev()
+        .fold(F::zero(), |acc, coeff| acc * point + coeff)
+}
+
+#[derive(Clone, Debug)]
+pub struct HornerConfig {
+    coeff: Column<Advice>,
+    point: Column<Advice>,
+    acc: Column<Advice>,
+    q_step: Selector,
+}
+
+#[derive(Clone, Debug)]
+pub struct HornerChip<F: FieldExt> {
+    config: HornerConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> HornerChip<F> {
+    pub fn new(config: HornerConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        coeff: Column<Advice>,
+        point: Column<Advice>,
+        acc: Column<Advice>,
+    ) -> HornerConfig {
+        meta.enable_equality(coeff);
+        meta.enable_equality(point);
+        meta.enable_equality(acc);
+
+        let q_step = meta.selector();
+
+        // acc[i] = acc[i - 1] * point + coeff[i]
+        meta.create_gate("horner step", |meta| {
+            let q_step = meta.query_selector(q_step);
+            let coeff = meta.query_advice(coeff, Rotation::cur());
+            let point = meta.query_advice(point, Rotation::cur());
+            let acc_prev = meta.query_advice(acc, Rotation::prev());
+            let acc = meta.query_advice(acc, Rotation::cur());
+
+            vec![q_step * (acc_prev * point + coeff - acc)]
+        });
+
+        HornerConfig {
+            coeff,
+            point,
+            acc,
+            q_step,
+        }
+    }
+
+    // Evaluate the polynomial with the given coefficients (lowest degree first)
+    // at `point`. Uses one row per coefficient.
+    pub fn evaluate(
+        &self,
+        mut layouter: impl Layouter<F>,
+        coeffs: &[AssignedCell<F, F>],
+        point: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        assert!(!coeffs.is_empty(), "cannot evaluate an empty polynomial");
+        let config = &self.config;
+
+        layouter.assign_region(
+            || "horner evaluation",
+            |mut region| {
+                let mut coeffs = coeffs.iter().rev();
+                // Start the accumulator from the leading coefficient.
+                let mut acc =
+                    coeffs
+                        .next()
+                        .unwrap()
+                        .copy_advice(|| "acc_0", &mut region, config.acc, 0)?;
+
+                for (i, coeff) in coeffs.enumerate() {
+                    let offset = i + 1;
+                    config.q_step.enable(&mut region, offset)?;
+
+                    coeff.copy_advice(
+                        || format!("coeff_{}", offset),
+                        &mut region,
+                        config.coeff,
+                        offset,
+                    )?;
+                    point.copy_advice(
+                        || format!("point_{}", offset),
+                        &mut region,
+                        config.point,
+                        offset,
+                    )?;
+
+                    let value = acc
+                        .value()
+                        .zip(point.value())
+                        .zip(coeff.value())
+                        .map(|((acc, point), coeff)| *acc * point + coeff);
+                    acc = region.assign_advice(
+                        || format!("acc_{}", offset),
+                        config.acc,
+                        offset,
+                        || value,
+                    )?;
+                }
+                Ok(acc)
+            },
+        )
+    }
+}
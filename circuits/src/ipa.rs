@@ -0,0 +1,104 @@
+// Key generation, proving and verification with the inner product argument
+// over Vesta, for deployments that can't rely on a trusted setup. Circuits
+// are over the Vesta scalar field, i.e. the Pallas base field, which is the
+// native field of Mina and Zcash Orchard; any circuit generic over the field
+// works unchanged. Verification is linear in the circuit size, and only the
+// Blake2b transcript is supported since the Keccak256 and Poseidon ones are
+// there for verifiers of BN254 proofs.
+use crate::error::{self, ProofError, QuarryError};
+use halo2_proofs::{
+    plonk::{create_proof, keygen_pk, keygen_vk, verify_proof, Circuit, ProvingKey, VerifyingKey},
+    poly::{
+        commitment::{Params, ParamsProver},
+        ipa::{
+            commitment::{IPACommitmentScheme, ParamsIPA},
+            multiopen::{ProverIPA, VerifierIPA},
+            strategy::SingleStrategy,
+        },
+    },
+    transcript::{
+        Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+    },
+};
+use halo2curves::pasta::{EqAffine, Fp};
+use rand::RngCore;
+use tracing::{debug, info_span};
+
+// Parameters for circuits of up to 2^k rows, derived from a hash to curve so
+// they need no ceremony.
+pub fn setup(k: u32) -> ParamsIPA<EqAffine> {
+    ParamsIPA::new(k)
+}
+
+// Generate the proving key of a circuit. The circuit is expected to be
+// without witnesses.
+pub fn keygen<C: Circuit<Fp>>(
+    params: &ParamsIPA<EqAffine>,
+    circuit: &C,
+) -> Result<ProvingKey<EqAffine>, QuarryError> {
+    let _span = info_span!("keygen", k = params.k(), backend = "ipa").entered();
+
+    let vk = info_span!("keygen_vk")
+        .in_scope(|| keygen_vk(params, circuit))
+        .map_err(|err| error::from_plonk(err, ProofError::Keygen))?;
+    info_span!("keygen_pk")
+        .in_scope(|| keygen_pk(params, vk, circuit))
+        .map_err(|err| error::from_plonk(err, ProofError::Keygen))
+}
+
+// Prove a single circuit with a single instance column.
+pub fn prove<C: Circuit<Fp>>(
+    params: &ParamsIPA<EqAffine>,
+    pk: &ProvingKey<EqAffine>,
+    circuit: C,
+    instances: &[Fp],
+    rng: impl RngCore,
+) -> Result<Vec<u8>, QuarryError> {
+    let _span = info_span!(
+        "prove",
+        k = params.k(),
+        instances = instances.len(),
+        backend = "ipa"
+    )
+    .entered();
+
+    let mut transcript = Blake2bWrite::<_, EqAffine, Challenge255<_>>::init(vec![]);
+    create_proof::<IPACommitmentScheme<_>, ProverIPA<_>, _, _, _, _>(
+        params,
+        pk,
+        &[circuit],
+        &[&[instances]],
+        rng,
+        &mut transcript,
+    )
+    .map_err(|err| error::from_plonk(err, ProofError::Prove))?;
+    let proof = transcript.finalize();
+    debug!(size = proof.len(), "created proof");
+    Ok(proof)
+}
+
+pub fn verify(
+    params: &ParamsIPA<EqAffine>,
+    vk: &VerifyingKey<EqAffine>,
+    instances: &[Fp],
+    proof: &[u8],
+) -> Result<(), QuarryError> {
+    let _span = info_span!(
+        "verify",
+        k = params.k(),
+        size = proof.len(),
+        backend = "ipa"
+    )
+    .entered();
+
+    let strategy = SingleStrategy::new(params);
+    let mut transcript = Blake2bRead::<_, EqAffine, Challenge255<_>>::init(proof);
+    verify_proof::<IPACommitmentScheme<_>, VerifierIPA<_>, _, _, _>(
+        params,
+        vk,
+        strategy,
+        &[&[instances]],
+        &mut transcript,
+    )
+    .map_err(|err| error::from_plonk(err, |_| ProofError::Invalid))
+}
@@ -0,0 +1,118 @@
+// Layouts of the instance column. A layout names every public input of one
+// version of a circuit and the rows it occupies, and is the one encoder and
+// decoder the prover, the native verifier and the contract and actor
+// generators go through, so none of them can keep reading an input from a
+// row it moved away from. Adding, removing or reordering inputs makes a new
+// version; `registry::CircuitEntry::versions` is what peers agree on.
+use crate::error::{CircuitError, QuarryError};
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InstanceField {
+    pub name: &'static str,
+    pub row: usize,
+    pub len: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InstanceLayout {
+    pub circuit: &'static str,
+    pub version: u32,
+    fields: Vec<InstanceField>,
+}
+
+impl InstanceLayout {
+    pub fn new(circuit: &'static str, version: u32) -> Self {
+        Self {
+            circuit,
+            version,
+            fields: vec![],
+        }
+    }
+
+    // Append a field of `len` rows after the previous ones.
+    pub fn field(mut self, name: &'static str, len: usize) -> Self {
+        assert!(
+            self.get(name).is_none(),
+            "duplicate instance field {}",
+            name
+        );
+        let row = self.len();
+        self.fields.push(InstanceField { name, row, len });
+        self
+    }
+
+    pub fn fields(&self) -> &[InstanceField] {
+        &self.fields
+    }
+
+    pub fn get(&self, name: &str) -> Option<&InstanceField> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+
+    // Rows of the instance column the layout occupies.
+    pub fn len(&self) -> usize {
+        self.fields.last().map_or(0, |field| field.row + field.len)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Lay out a value for every field, in any order.
+    pub fn encode<F: Copy>(&self, values: &[(&str, &[F])]) -> Result<Vec<F>, QuarryError> {
+        if let Some((name, _)) = values.iter().find(|(name, _)| self.get(name).is_none()) {
+            return Err(self.error(format!("no field {}", name)));
+        }
+        if values.len() != self.fields.len() {
+            return Err(self.error(format!(
+                "{} values for {} fields",
+                values.len(),
+                self.fields.len()
+            )));
+        }
+
+        let mut instances = Vec::with_capacity(self.len());
+        for field in self.fields.iter() {
+            let (_, value) = values
+                .iter()
+                .find(|(name, _)| *name == field.name)
+                .ok_or_else(|| self.error(format!("missing {}", field.name)))?;
+            if value.len() != field.len {
+                return Err(self.error(format!(
+                    "{} has {} rows, got {}",
+                    field.name,
+                    field.len,
+                    value.len()
+                )));
+            }
+            instances.extend_from_slice(value);
+        }
+        Ok(instances)
+    }
+
+    pub fn decode<'a, F>(
+        &self,
+        instances: &'a [F],
+    ) -> Result<BTreeMap<&'static str, &'a [F]>, QuarryError> {
+        if instances.len() != self.len() {
+            return Err(self.error(format!("{} rows, expected {}", instances.len(), self.len())));
+        }
+        Ok(self
+            .fields
+            .iter()
+            .map(|field| (field.name, &instances[field.row..field.row + field.len]))
+            .collect())
+    }
+
+    fn error(&self, reason: String) -> QuarryError {
+        CircuitError::InstanceLayout(format!("{}: {}", self, reason)).into()
+    }
+}
+
+impl fmt::Display for InstanceLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} v{}", self.circuit, self.version)
+    }
+}
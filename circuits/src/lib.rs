@@ -0,0 +1,47 @@
+pub mod backend;
+#[cfg(feature = "blake2b")]
+pub mod blake2b;
+pub mod bytes;
+pub mod committee;
+pub mod commp;
+pub mod compose;
+pub mod ecdsa;
+#[cfg(feature = "kzg")]
+pub mod equivalence;
+pub mod erasure;
+pub mod error;
+pub mod horner;
+#[cfg(feature = "ipa")]
+pub mod ipa;
+pub mod layout;
+pub mod liveness;
+pub mod merkle;
+pub mod mmr;
+pub mod oracle;
+pub mod payload;
+#[cfg(feature = "sha256")]
+pub mod podsi;
+pub mod policy;
+pub mod poseidon;
+#[cfg(feature = "kzg")]
+pub mod proof;
+pub mod randomness;
+pub mod registry;
+pub mod report;
+pub mod rollup;
+// Not public while ECDSA is the only scheme, nothing outside the crate
+// should build on the trait before a second one shapes it.
+#[allow(dead_code)]
+mod scheme;
+pub mod secret;
+pub mod semaphore;
+#[cfg(feature = "sha256")]
+pub mod sha256;
+pub mod spec;
+pub mod swap;
+pub mod transcript;
+#[cfg(feature = "kzg")]
+pub mod tune;
+pub mod vote;
+
+pub use error::QuarryError;
@@ -0,0 +1,133 @@
+// Participation tracking built on the signer bitmap exposed by the committee
+// circuit. The tracker keeps the bitmaps of the last `window` attestations
+// and scores every seat by the share of them it signed, scores are in basis
+// points so reports hash the same everywhere.
+use crate::committee;
+use crate::ecdsa;
+use crate::secret::Secret;
+use halo2_proofs::arithmetic::{CurveAffine, FieldExt};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use std::collections::VecDeque;
+
+pub const SCORE_SCALE: u32 = 10_000;
+
+#[derive(Clone, Debug)]
+pub struct LivenessTracker {
+    seats: usize,
+    window: usize,
+    bitmaps: VecDeque<Vec<bool>>,
+}
+
+impl LivenessTracker {
+    pub fn new(seats: usize, window: usize) -> Self {
+        assert!(window > 0, "liveness window can't be empty");
+        Self {
+            seats,
+            window,
+            bitmaps: VecDeque::with_capacity(window),
+        }
+    }
+
+    // Record the participation of one attestation, `active` is indexed by
+    // seat. The oldest attestation falls out once the window is full.
+    pub fn record(&mut self, active: &[bool]) {
+        assert_eq!(active.len(), self.seats, "bitmap doesn't match committee");
+        if self.bitmaps.len() == self.window {
+            self.bitmaps.pop_front();
+        }
+        self.bitmaps.push_back(active.to_vec());
+    }
+
+    // Record the `SIGNERS_BITMAP` public input of a committee proof.
+    pub fn record_bitmap<N: FieldExt, const N_MAX: usize>(&mut self, bitmap: N) {
+        let active = committee::decode_bitmap::<N, N_MAX>(bitmap);
+        self.record(&active[..self.seats]);
+    }
+
+    pub fn attestations(&self) -> usize {
+        self.bitmaps.len()
+    }
+
+    pub fn scores(&self) -> Vec<u32> {
+        let total = self.bitmaps.len() as u64;
+        (0..self.seats)
+            .map(|seat| {
+                if total == 0 {
+                    return 0;
+                }
+                let signed = self.bitmaps.iter().filter(|active| active[seat]).count() as u64;
+                (signed * SCORE_SCALE as u64 / total) as u32
+            })
+            .collect()
+    }
+
+    pub fn report(&self, epoch: u64) -> LivenessReport {
+        LivenessReport {
+            epoch,
+            attestations: self.attestations() as u64,
+            scores: self.scores(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LivenessReport {
+    pub epoch: u64,
+    pub attestations: u64,
+    pub scores: Vec<u32>,
+}
+
+impl LivenessReport {
+    // Seats scoring below `threshold` basis points, these are the candidates
+    // for a removal proposal.
+    pub fn below(&self, threshold: u32) -> Vec<usize> {
+        self.scores
+            .iter()
+            .enumerate()
+            .filter(|(_, &score)| score < threshold)
+            .map(|(seat, _)| seat)
+            .collect()
+    }
+
+    pub fn digest(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(self.epoch.to_be_bytes());
+        hasher.update(self.attestations.to_be_bytes());
+        hasher.update((self.scores.len() as u64).to_be_bytes());
+        for score in self.scores.iter() {
+            hasher.update(score.to_be_bytes());
+        }
+        hasher.finalize().into()
+    }
+
+    pub fn sign<C: CurveAffine>(
+        self,
+        sk: &Secret<C::ScalarExt>,
+        rng: impl RngCore,
+    ) -> SignedLivenessReport<C> {
+        let signature = ecdsa::sign::<C>(sk, report_hash::<C>(&self), rng);
+        SignedLivenessReport {
+            report: self,
+            signature,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SignedLivenessReport<C: CurveAffine> {
+    pub report: LivenessReport,
+    pub signature: (C::Scalar, C::Scalar),
+}
+
+impl<C: CurveAffine> SignedLivenessReport<C> {
+    pub fn verify(&self, public_key: C) -> bool {
+        ecdsa::verify_signature(public_key, report_hash::<C>(&self.report), self.signature)
+    }
+}
+
+fn report_hash<C: CurveAffine>(report: &LivenessReport) -> C::ScalarExt {
+    let mut bytes = [0u8; 64];
+    bytes[..32].copy_from_slice(&report.digest());
+    C::ScalarExt::from_bytes_wide(&bytes)
+}
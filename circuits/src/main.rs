@@ -1,3 +1,348 @@
+// Deterministic test vectors for other implementations of the protocol,
+// and constraint reports for audits.
+//
+//   quarry-circuits vectors generate > vectors.json
+//   quarry-circuits constraints committee > committee.json
+//
+// Field elements are printed as big-endian 0x-prefixed hex and byte strings
+// as plain hex. Everything is derived from a fixed seed so the output only
+// changes when an encoding, a digest or a circuit does.
+use halo2_proofs::{
+    arithmetic::{CurveAffine, Field},
+    circuit::Value,
+    poly::kzg::commitment::ParamsKZG,
+};
+use halo2curves::bn256::{Bn256, Fr};
+use halo2curves::group::Curve;
+use halo2curves::secp256k1::Secp256k1Affine;
+use quarry_circuits::{
+    committee, commp, ecdsa,
+    layout::InstanceLayout,
+    liveness::LivenessTracker,
+    mmr::{self, InclusionCircuit, Mmr},
+    policy::QuorumPolicy,
+    poseidon, proof,
+    randomness::{self, seed_commitment, CommitReveal, RevealCircuit},
+    report::{self, ConstraintReport},
+    secret::Secret,
+    semaphore,
+};
+use rand::{rngs::StdRng, SeedableRng};
+use std::env;
+use std::process;
+
+const SEED: u64 = 0x71_75_61_72_72_79;
+
+fn fr(value: &Fr) -> String {
+    format!("\"{:?}\"", value)
+}
+
+fn bytes(value: &[u8]) -> String {
+    let hex = value
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+    format!("\"{}\"", hex)
+}
+
+fn list(values: impl IntoIterator<Item = String>) -> String {
+    format!("[{}]", values.into_iter().collect::<Vec<_>>().join(", "))
+}
+
+fn object(fields: &[(&str, String)]) -> String {
+    let fields = fields
+        .iter()
+        .map(|(name, value)| format!("\"{}\": {}", name, value))
+        .collect::<Vec<_>>();
+    format!("{{{}}}", fields.join(", "))
+}
+
+fn poseidon_vectors() -> String {
+    let (a, b) = (Fr::from(1), Fr::from(2));
+    object(&[
+        ("input", list([fr(&a), fr(&b)])),
+        ("quarry", fr(&poseidon::hash([a, b]))),
+        ("circomlib", fr(&semaphore::hash2(a, b))),
+        (
+            "semaphore_identity_commitment",
+            fr(&semaphore::identity_commitment(a, b)),
+        ),
+    ])
+}
+
+fn commp_vectors() -> String {
+    let payload = (0..=255u8).collect::<Vec<_>>();
+    let binding = commp::PieceBinding::new(Fr::from(42), &payload);
+    object(&[
+        ("payload", bytes(&payload)),
+        ("commp", bytes(&binding.commp)),
+        ("piece_cid", bytes(&binding.piece_cid())),
+        ("piece_size", binding.piece_size.to_string()),
+        ("data_root", fr(&binding.data_root)),
+        ("binding_digest", fr(&binding.digest())),
+    ])
+}
+
+fn committee_vectors(rng: &mut StdRng) -> String {
+    type E = Secp256k1Affine;
+    const N_MAX: usize = 4;
+
+    let secrets = (0..3)
+        .map(|_| Secret::new(<E as CurveAffine>::ScalarExt::random(&mut *rng)))
+        .collect::<Vec<_>>();
+    let members = secrets
+        .iter()
+        .map(|sk| (E::generator() * sk.expose()).to_affine())
+        .collect::<Vec<_>>();
+    let msg_hash = <E as CurveAffine>::ScalarExt::from(7);
+    let active = [true, false, true];
+    let signatures = secrets
+        .iter()
+        .zip(active)
+        .map(|(sk, active)| active.then(|| ecdsa::sign::<E>(sk, msg_hash, &mut *rng)))
+        .collect::<Vec<_>>();
+    let policy = QuorumPolicy::k_of_n(2, 0..3);
+    // The first attestation of a chain builds on a zero digest.
+    let prev_attestation = Fr::zero();
+    let instances = committee::instances::<E, Fr, N_MAX>(
+        &members,
+        msg_hash,
+        prev_attestation,
+        &active,
+        &policy,
+        1,
+    );
+
+    object(&[
+        (
+            "members",
+            list(members.iter().map(|member| {
+                let coordinates = member.coordinates().unwrap();
+                list([
+                    format!("\"{:?}\"", coordinates.x()),
+                    format!("\"{:?}\"", coordinates.y()),
+                ])
+            })),
+        ),
+        ("msg_hash", format!("\"{:?}\"", msg_hash)),
+        (
+            "signatures",
+            list(signatures.iter().map(|signature| match signature {
+                Some((r, s)) => list([format!("\"{:?}\"", r), format!("\"{:?}\"", s)]),
+                None => "null".to_string(),
+            })),
+        ),
+        ("policy_commitment", fr(&policy.commitment::<Fr>())),
+        ("epoch", "1".to_string()),
+        ("prev_attestation", fr(&prev_attestation)),
+        ("instances", list(instances.iter().map(fr))),
+    ])
+}
+
+fn liveness_vectors() -> String {
+    let mut tracker = LivenessTracker::new(3, 4);
+    for active in [
+        [true, true, false],
+        [true, false, false],
+        [true, true, true],
+    ] {
+        tracker.record(&active);
+    }
+    let report = tracker.report(9);
+    object(&[
+        (
+            "scores",
+            list(report.scores.iter().map(|score| score.to_string())),
+        ),
+        ("digest", bytes(&report.digest())),
+    ])
+}
+
+fn mmr_vectors() -> String {
+    let mut mmr = Mmr::new();
+    for leaf in 1..=5 {
+        mmr.push(Fr::from(leaf));
+    }
+    let proof = mmr.prove(2);
+    object(&[
+        ("leaves", list((1..=5).map(|leaf| fr(&Fr::from(leaf))))),
+        ("root", fr(&mmr.root().unwrap())),
+        ("proof_index", proof.index.to_string()),
+        ("proof_siblings", list(proof.siblings.iter().map(fr))),
+        ("proof_peaks", list(proof.peaks.iter().map(fr))),
+    ])
+}
+
+// A full proof for the smallest circuit, so verifiers can check their
+// transcript and encoding against ours.
+fn randomness_vectors(rng: &mut StdRng) -> String {
+    const N: usize = 4;
+    const K: u32 = 10;
+
+    let reveals = (0..N)
+        .map(|_| (Fr::random(&mut *rng), Fr::random(&mut *rng)))
+        .collect::<Vec<_>>();
+    let mut round = CommitReveal::new(N);
+    for (member, (seed, salt)) in reveals.iter().enumerate() {
+        round.commit(member, seed_commitment(*seed, *salt));
+    }
+    for (member, (seed, salt)) in reveals.iter().enumerate() {
+        round.reveal(member, *seed, *salt);
+    }
+    let circuit = round.circuit::<N>().unwrap();
+    let instances = round.instances().unwrap();
+
+    let params = ParamsKZG::<Bn256>::setup(K, &mut *rng);
+    let pk = proof::keygen(&params, &circuit).expect("keygen should not fail");
+    let proof = proof::prove(&params, &pk, circuit, &instances, &mut *rng)
+        .expect("proof generation should not fail");
+
+    object(&[
+        ("k", K.to_string()),
+        (
+            "reveals",
+            list(
+                reveals
+                    .iter()
+                    .map(|(seed, salt)| list([fr(seed), fr(salt)])),
+            ),
+        ),
+        ("instances", list(instances.iter().map(fr))),
+        ("proof", bytes(&proof)),
+    ])
+}
+
+// Instance layouts at the sizes used above, for the contract and actor
+// generators.
+fn layout_vectors() -> String {
+    let layouts: [InstanceLayout; 3] = [committee::layout(4), randomness::layout(4), mmr::layout()];
+    list(layouts.iter().map(|layout| {
+        object(&[
+            ("circuit", format!("\"{}\"", layout.circuit)),
+            ("version", layout.version.to_string()),
+            (
+                "fields",
+                list(layout.fields().iter().map(|field| {
+                    object(&[
+                        ("name", format!("\"{}\"", field.name)),
+                        ("row", field.row.to_string()),
+                        ("len", field.len.to_string()),
+                    ])
+                })),
+            ),
+        ])
+    }))
+}
+
+fn generate() -> String {
+    let mut rng = StdRng::seed_from_u64(SEED);
+    object(&[
+        ("seed", SEED.to_string()),
+        ("poseidon", poseidon_vectors()),
+        ("commp", commp_vectors()),
+        ("committee", committee_vectors(&mut rng)),
+        ("liveness", liveness_vectors()),
+        ("mmr", mmr_vectors()),
+        ("randomness", randomness_vectors(&mut rng)),
+        ("layouts", layout_vectors()),
+    ])
+}
+
+fn string(value: &str) -> String {
+    format!("{:?}", value)
+}
+
+fn report_json(report: &ConstraintReport) -> String {
+    object(&[
+        ("advice_columns", report.advice_columns.to_string()),
+        ("fixed_columns", report.fixed_columns.to_string()),
+        ("instance_columns", report.instance_columns.to_string()),
+        ("selectors", report.selectors.to_string()),
+        (
+            "gates",
+            list(report.gates.iter().map(|gate| {
+                object(&[(
+                    "constraint_degrees",
+                    list(gate.constraints.iter().map(|degree| degree.to_string())),
+                )])
+            })),
+        ),
+        ("lookups", report.lookups.to_string()),
+        ("copied_columns", report.copied_columns.to_string()),
+        ("copies", report.copies.to_string()),
+        (
+            "instance_rows",
+            list(report.instance_rows.iter().map(|row| row.to_string())),
+        ),
+        (
+            "regions",
+            list(report.regions.iter().map(|region| {
+                object(&[
+                    ("name", string(&region.name)),
+                    ("first_row", region.first_row.to_string()),
+                    ("rows", region.rows.to_string()),
+                    ("cells", region.cells.to_string()),
+                    ("selectors", region.selectors.to_string()),
+                    ("copies", region.copies.to_string()),
+                ])
+            })),
+        ),
+        ("rows", report.rows.to_string()),
+        ("min_k", report.min_k.to_string()),
+    ])
+}
+
+// The circuits at the sizes of the vectors above. Only their shape matters,
+// the reports are made without witnesses.
+fn constraints(circuit: &str) -> Option<String> {
+    let report = match circuit {
+        "committee" => {
+            type E = Secp256k1Affine;
+            let circuit = committee::CommitteeCircuit::<E, Fr, 4> {
+                members: vec![Value::unknown(); 4],
+                signatures: vec![Value::unknown(); 4],
+                active: vec![Value::unknown(); 4],
+                policy: QuorumPolicy::k_of_n(2, 0..3),
+                msg_hash: Value::unknown(),
+                prev_attestation: Value::unknown(),
+                epoch: 1,
+                aux_generator: E::generator(),
+                window_size: 2,
+            };
+            report::report::<_, Fr>(&circuit)
+        }
+        "randomness" => report::report(&RevealCircuit::<4> {
+            seeds: Value::unknown(),
+            salts: Value::unknown(),
+            commitments: Value::unknown(),
+            revealed: Value::unknown(),
+        }),
+        "mmr" => {
+            let mut mmr = Mmr::new();
+            for leaf in 1..=5 {
+                mmr.push(Fr::from(leaf));
+            }
+            report::report(&InclusionCircuit::new(&mmr.prove(2), Fr::from(3)))
+        }
+        _ => return None,
+    };
+    Some(report_json(&report.expect("synthesis should not fail")))
+}
+
+fn usage() -> ! {
+    eprintln!("usage: quarry-circuits vectors generate");
+    eprintln!("       quarry-circuits constraints committee|randomness|mmr");
+    process::exit(2);
+}
+
 fn main() {
-    println!("Hello, world!");
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        ["vectors", "generate"] => println!("{}", generate()),
+        ["constraints", circuit] => match constraints(circuit) {
+            Some(report) => println!("{}", report),
+            None => usage(),
+        },
+        _ => usage(),
+    }
 }
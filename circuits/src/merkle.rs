@@ -0,0 +1,266 @@
+use crate::poseidon::{self, PoseidonConfig};
+use crate::swap::{SwapChip, SwapConfig};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::Error,
+};
+
+// Native Poseidon Merkle root over a power of two number of leaves.
+pub fn root<F: FieldExt>(leaves: &[F]) -> F {
+    assert!(
+        leaves.len().is_power_of_two(),
+        "number of leaves must be a power of two"
+    );
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| poseidon::hash([pair[0], pair[1]]))
+            .collect();
+    }
+    level[0]
+}
+
+// Append-only Merkle tree of fixed depth. Leaves that weren't pushed yet are
+// `zero`, so the root matches `root` over the leaves padded with `zero`, e.g.
+// the committee registry padded with the leaf of the padding key. Every push
+// only rehashes the path of the new leaf.
+#[derive(Clone, Debug)]
+pub struct IncrementalTree<F: FieldExt> {
+    // zeros[h] is the root of an empty subtree of height h.
+    zeros: Vec<F>,
+    // levels[h][j] is the node over leaves [j * 2^h, (j + 1) * 2^h), for the
+    // nodes with at least one pushed leaf.
+    levels: Vec<Vec<F>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MerkleProof<F: FieldExt> {
+    pub index: usize,
+    // Path from the leaf to the root, bottom up.
+    pub siblings: Vec<F>,
+}
+
+impl<F: FieldExt> IncrementalTree<F> {
+    pub fn new(depth: usize, zero: F) -> Self {
+        let mut zeros = vec![zero];
+        for height in 0..depth {
+            zeros.push(poseidon::hash([zeros[height], zeros[height]]));
+        }
+        Self {
+            zeros,
+            levels: vec![vec![]; depth + 1],
+        }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.zeros.len() - 1
+    }
+
+    pub fn len(&self) -> usize {
+        self.levels[0].len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        1 << self.depth()
+    }
+
+    // Node over leaves [index * 2^height, (index + 1) * 2^height), the
+    // missing ones taken as `zero`.
+    pub fn node(&self, height: usize, index: usize) -> F {
+        self.levels[height]
+            .get(index)
+            .copied()
+            .unwrap_or(self.zeros[height])
+    }
+
+    // Append a leaf and return its index, or None when the tree is full.
+    pub fn push(&mut self, leaf: F) -> Option<usize> {
+        let index = self.len();
+        if index == self.capacity() {
+            return None;
+        }
+        self.levels[0].push(leaf);
+        for height in 1..=self.depth() {
+            let parent = index >> height;
+            let node = poseidon::hash([
+                self.node(height - 1, 2 * parent),
+                self.node(height - 1, 2 * parent + 1),
+            ]);
+            match self.levels[height].get_mut(parent) {
+                Some(slot) => *slot = node,
+                None => self.levels[height].push(node),
+            }
+        }
+        Some(index)
+    }
+
+    pub fn root(&self) -> F {
+        self.node(self.depth(), 0)
+    }
+
+    pub fn prove(&self, index: usize) -> MerkleProof<F> {
+        assert!(index < self.len(), "leaf out of range");
+        MerkleProof {
+            index,
+            siblings: (0..self.depth())
+                .map(|height| self.node(height, (index >> height) ^ 1))
+                .collect(),
+        }
+    }
+}
+
+impl<F: FieldExt> MerkleProof<F> {
+    pub fn verify(&self, leaf: F, root: F) -> bool {
+        let in_range = self
+            .index
+            .checked_shr(self.siblings.len() as u32)
+            .map_or(true, |rest| rest == 0);
+        in_range && path_root(leaf, self.index, &self.siblings) == root
+    }
+
+    // Index as 8 little-endian bytes followed by the siblings, bottom up.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = (self.index as u64).to_le_bytes().to_vec();
+        for sibling in self.siblings.iter() {
+            bytes.extend_from_slice(sibling.to_repr().as_ref());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let width = F::Repr::default().as_ref().len();
+        if bytes.len() < 8 || (bytes.len() - 8) % width != 0 {
+            return None;
+        }
+        let index = u64::from_le_bytes(bytes[..8].try_into().unwrap()) as usize;
+        let siblings = bytes[8..]
+            .chunks(width)
+            .map(|chunk| {
+                let mut repr = F::Repr::default();
+                repr.as_mut().copy_from_slice(chunk);
+                Option::from(F::from_repr(repr))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(Self { index, siblings })
+    }
+}
+
+// Root of the tree holding `leaf` at `index` with the given path.
+pub fn path_root<F: FieldExt>(leaf: F, index: usize, siblings: &[F]) -> F {
+    siblings
+        .iter()
+        .enumerate()
+        .fold(leaf, |node, (height, &sibling)| {
+            if (index >> height) & 1 == 1 {
+                poseidon::hash([sibling, node])
+            } else {
+                poseidon::hash([node, sibling])
+            }
+        })
+}
+
+// Witness of a path for `assign_path_root`.
+#[derive(Clone, Debug)]
+pub struct MerklePath<F: FieldExt> {
+    pub index: Value<usize>,
+    pub siblings: Vec<Value<F>>,
+}
+
+impl<F: FieldExt> MerklePath<F> {
+    pub fn new(proof: &MerkleProof<F>) -> Self {
+        Self {
+            index: Value::known(proof.index),
+            siblings: proof.siblings.iter().copied().map(Value::known).collect(),
+        }
+    }
+
+    pub fn without_witnesses(&self) -> Self {
+        Self {
+            index: Value::unknown(),
+            siblings: vec![Value::unknown(); self.siblings.len()],
+        }
+    }
+}
+
+// In-circuit `path_root`. The direction bits come from the witnessed index,
+// only the depth of the path is fixed.
+pub fn assign_path_root<F: FieldExt>(
+    poseidon: &PoseidonConfig<F>,
+    swap: &SwapConfig,
+    mut layouter: impl Layouter<F>,
+    leaf: &AssignedCell<F, F>,
+    path: &MerklePath<F>,
+) -> Result<AssignedCell<F, F>, Error> {
+    let swap_chip = SwapChip::new(swap.clone());
+    let mut node = leaf.clone();
+    for (level, sibling) in path.siblings.iter().enumerate() {
+        let (left, right) = swap_chip.swap(
+            layouter.namespace(|| format!("swap {}", level)),
+            &node,
+            *sibling,
+            path.index.map(|index| (index >> level) & 1 == 1),
+        )?;
+        node = poseidon::hash_assigned(
+            poseidon,
+            layouter.namespace(|| format!("node {}", level)),
+            [left, right],
+        )?;
+    }
+    Ok(node)
+}
+
+#[derive(Clone, Debug)]
+pub struct MerkleChip<F: FieldExt> {
+    config: PoseidonConfig<F>,
+}
+
+impl<F: FieldExt> MerkleChip<F> {
+    pub fn new(config: PoseidonConfig<F>) -> Self {
+        Self { config }
+    }
+
+    pub fn hash_pair(
+        &self,
+        layouter: impl Layouter<F>,
+        left: AssignedCell<F, F>,
+        right: AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        poseidon::hash_assigned(&self.config, layouter, [left, right])
+    }
+
+    // Compute the root of already assigned leaves, hashing level by level so
+    // each node ends up in its own namespace.
+    pub fn root(
+        &self,
+        mut layouter: impl Layouter<F>,
+        leaves: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        assert!(
+            leaves.len().is_power_of_two(),
+            "number of leaves must be a power of two"
+        );
+        let mut level = leaves.to_vec();
+        let mut depth = 0;
+        while level.len() > 1 {
+            level = level
+                .chunks(2)
+                .enumerate()
+                .map(|(i, pair)| {
+                    self.hash_pair(
+                        layouter.namespace(|| format!("node {}-{}", depth, i)),
+                        pair[0].clone(),
+                        pair[1].clone(),
+                    )
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            depth += 1;
+        }
+        Ok(level.remove(0))
+    }
+}
@@ -0,0 +1,594 @@
+// Merkle Mountain Range over emitted attestations. Appending never changes
+// existing nodes, so an inclusion proof against the root at size H keeps
+// proving that the attestation existed at H no matter how far the range
+// grows afterwards.
+//
+// The range is a list of perfect Poseidon trees (the peaks), largest first.
+// The root binds the size as well: root = Poseidon(size, bag) where bag is
+// the Poseidon chain over the peaks from left to right.
+use crate::compose::{Shared, SubCircuit, Wires};
+use crate::layout::InstanceLayout;
+use crate::merkle::{self, IncrementalTree};
+use crate::poseidon::{self, PoseidonConfig, HASH_ROWS};
+use crate::spec::StatementSpec;
+use crate::swap::{SwapChip, SwapConfig};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Expression, Instance, Selector},
+    poly::Rotation,
+};
+use halo2curves::bn256::Fr;
+
+// Nodes are kept in an incremental tree of height `MAX_HEIGHT`. Every peak
+// is a complete node of it, so none depends on the leaves not pushed yet.
+#[derive(Clone, Debug)]
+pub struct Mmr<F: FieldExt> {
+    tree: IncrementalTree<F>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MmrProof<F: FieldExt> {
+    pub size: usize,
+    pub index: usize,
+    // Path from the leaf to its peak, bottom up.
+    pub siblings: Vec<F>,
+    // All peaks, the one holding the leaf included.
+    pub peaks: Vec<F>,
+}
+
+// Height of every peak of a range with `size` leaves, largest first.
+pub fn peak_heights(size: usize) -> Vec<usize> {
+    (0..usize::BITS as usize)
+        .rev()
+        .filter(|height| size & (1 << height) != 0)
+        .collect()
+}
+
+// Which peak holds leaf `index`, and the index of the leaf within it.
+pub fn locate(size: usize, index: usize) -> (usize, usize) {
+    assert!(index < size, "leaf out of range");
+    let mut offset = 0;
+    for (peak, height) in peak_heights(size).into_iter().enumerate() {
+        if index < offset + (1 << height) {
+            return (peak, index - offset);
+        }
+        offset += 1 << height;
+    }
+    unreachable!()
+}
+
+pub fn bag<F: FieldExt>(size: usize, peaks: &[F]) -> F {
+    let bag = peaks[1..]
+        .iter()
+        .fold(peaks[0], |acc, &peak| poseidon::hash([acc, peak]));
+    poseidon::hash([F::from(size as u64), bag])
+}
+
+impl<F: FieldExt> Mmr<F> {
+    pub fn new() -> Self {
+        Self {
+            tree: IncrementalTree::new(MAX_HEIGHT, F::zero()),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.tree.len()
+    }
+
+    // Append a leaf and return its index.
+    pub fn push(&mut self, leaf: F) -> usize {
+        self.tree
+            .push(leaf)
+            .expect("range holds at most 2^MAX_HEIGHT leaves")
+    }
+
+    pub fn peaks(&self) -> Vec<F> {
+        let mut offset = 0;
+        peak_heights(self.size())
+            .into_iter()
+            .map(|height| {
+                let peak = self.tree.node(height, offset >> height);
+                offset += 1 << height;
+                peak
+            })
+            .collect()
+    }
+
+    pub fn root(&self) -> Option<F> {
+        (self.size() > 0).then(|| bag(self.size(), &self.peaks()))
+    }
+
+    pub fn prove(&self, index: usize) -> MmrProof<F> {
+        let (peak, _) = locate(self.size(), index);
+        let height = peak_heights(self.size())[peak];
+        MmrProof {
+            size: self.size(),
+            index,
+            siblings: self.tree.prove(index).siblings[..height].to_vec(),
+            peaks: self.peaks(),
+        }
+    }
+}
+
+impl<F: FieldExt> Default for Mmr<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: FieldExt> MmrProof<F> {
+    pub fn verify(&self, leaf: F, root: F) -> bool {
+        if self.index >= self.size {
+            return false;
+        }
+        let heights = peak_heights(self.size);
+        let (peak, index) = locate(self.size, self.index);
+        if self.peaks.len() != heights.len() || self.siblings.len() != heights[peak] {
+            return false;
+        }
+
+        merkle::path_root(leaf, index, &self.siblings) == self.peaks[peak]
+            && bag(self.size, &self.peaks) == root
+    }
+}
+
+// Largest range the production circuit proves inclusion in, 2^MAX_HEIGHT - 1
+// leaves.
+pub const MAX_HEIGHT: usize = 32;
+
+// Witness of an inclusion path. Only the height bounding the range shapes
+// the circuit; the size of the range, the peak holding the leaf and the
+// position within it are witnesses, so one verifying key serves every range
+// below 2^height leaves. Peaks are indexed by height, with zeros for the
+// heights the range has no peak at, and the path to the peak is padded with
+// zero siblings up to the highest peak.
+#[derive(Clone, Debug)]
+pub struct MmrPath<F: FieldExt> {
+    pub height: usize,
+    pub size: Value<usize>,
+    pub peak_height: Value<usize>,
+    pub index: Value<usize>,
+    pub siblings: Vec<Value<F>>,
+    pub peaks: Vec<Value<F>>,
+}
+
+impl<F: FieldExt> MmrPath<F> {
+    pub fn new(height: usize, proof: &MmrProof<F>) -> Self {
+        assert!(height > 0 && height < usize::BITS as usize);
+        assert!(proof.size < 1 << height, "range too large for the height");
+        let heights = peak_heights(proof.size);
+        let (peak, index) = locate(proof.size, proof.index);
+
+        let mut peaks = vec![F::zero(); height];
+        for (peak, height) in proof.peaks.iter().zip(heights.iter()) {
+            peaks[*height] = *peak;
+        }
+        let mut siblings = proof.siblings.clone();
+        siblings.resize(height - 1, F::zero());
+
+        Self {
+            height,
+            size: Value::known(proof.size),
+            peak_height: Value::known(heights[peak]),
+            index: Value::known(index),
+            siblings: siblings.into_iter().map(Value::known).collect(),
+            peaks: peaks.into_iter().map(Value::known).collect(),
+        }
+    }
+
+    pub fn without_witnesses(&self) -> Self {
+        Self {
+            height: self.height,
+            size: Value::unknown(),
+            peak_height: Value::unknown(),
+            index: Value::unknown(),
+            siblings: vec![Value::unknown(); self.height - 1],
+            peaks: vec![Value::unknown(); self.height],
+        }
+    }
+}
+
+// Bits of the size, most significant first, and which height holds the
+// leaf. Row 0 starts the accumulators at zero and row r covers height
+// `height - r`:
+//   acc = 2 * acc_prev + bit, so the last acc is the size
+//   onehot marks the peak of the leaf, which has to be one of the peaks,
+//   and count sums it up to check there is exactly one
+//   started is set from the first peak on, where the bag starts
+#[derive(Clone, Debug)]
+pub struct ShapeConfig {
+    // bit, acc, onehot, count, started
+    columns: [Column<Advice>; 5],
+    q_shape: Selector,
+}
+
+pub fn configure_shape<F: FieldExt>(meta: &mut ConstraintSystem<F>) -> ShapeConfig {
+    let columns = [(); 5].map(|_| meta.advice_column());
+    for column in columns.iter() {
+        meta.enable_equality(*column);
+    }
+    let q_shape = meta.selector();
+
+    meta.create_gate("mmr shape", |meta| {
+        let q = meta.query_selector(q_shape);
+        let [bit, acc, onehot, count, started] =
+            columns.map(|column| meta.query_advice(column, Rotation::cur()));
+        let [_, acc_prev, _, count_prev, started_prev] =
+            columns.map(|column| meta.query_advice(column, Rotation::prev()));
+        let one = Expression::Constant(F::one());
+        let two = Expression::Constant(F::from(2));
+
+        vec![
+            q.clone() * bit.clone() * (one.clone() - bit.clone()),
+            q.clone() * (acc - two * acc_prev - bit.clone()),
+            q.clone() * onehot.clone() * (one.clone() - onehot.clone()),
+            q.clone() * onehot.clone() * (one - bit.clone()),
+            q.clone() * (count - count_prev - onehot),
+            q * (started - started_prev.clone() - bit.clone() + started_prev * bit),
+        ]
+    });
+
+    ShapeConfig { columns, q_shape }
+}
+
+// Assigned shape, indexed by height.
+struct Shape<F: FieldExt> {
+    size: AssignedCell<F, F>,
+    bits: Vec<AssignedCell<F, F>>,
+    onehot: Vec<AssignedCell<F, F>>,
+    // Whether a larger peak came before.
+    started: Vec<AssignedCell<F, F>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct MmrConfig<F: FieldExt> {
+    value: Column<Advice>,
+    poseidon: PoseidonConfig<F>,
+    swap: SwapConfig,
+    shape: ShapeConfig,
+}
+
+#[derive(Clone, Debug)]
+pub struct MmrChip<F: FieldExt> {
+    config: MmrConfig<F>,
+}
+
+impl<F: FieldExt> MmrChip<F> {
+    pub fn new(config: MmrConfig<F>) -> Self {
+        Self { config }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> MmrConfig<F> {
+        let value = meta.advice_column();
+        meta.enable_equality(value);
+
+        MmrConfig {
+            value,
+            poseidon: poseidon::configure(meta),
+            swap: SwapChip::configure(meta),
+            shape: configure_shape(meta),
+        }
+    }
+
+    fn assign_shape(
+        &self,
+        mut layouter: impl Layouter<F>,
+        path: &MmrPath<F>,
+    ) -> Result<Shape<F>, Error> {
+        let height = path.height;
+        let [bit_column, acc_column, onehot_column, count_column, started_column] =
+            self.config.shape.columns;
+        let as_field = |value: bool| if value { F::one() } else { F::zero() };
+
+        layouter.assign_region(
+            || "shape",
+            |mut region| {
+                let mut acc =
+                    region.assign_advice_from_constant(|| "acc", acc_column, 0, F::zero())?;
+                let mut count =
+                    region.assign_advice_from_constant(|| "count", count_column, 0, F::zero())?;
+                let mut started = region.assign_advice_from_constant(
+                    || "started",
+                    started_column,
+                    0,
+                    F::zero(),
+                )?;
+
+                let mut bits = vec![];
+                let mut onehot = vec![];
+                let mut started_before = vec![];
+                for h in (0..height).rev() {
+                    let row = height - h;
+                    self.config.shape.q_shape.enable(&mut region, row)?;
+                    started_before.push(started.clone());
+                    bits.push(region.assign_advice(
+                        || format!("bit {}", h),
+                        bit_column,
+                        row,
+                        || path.size.map(|size| as_field((size >> h) & 1 == 1)),
+                    )?);
+                    acc = region.assign_advice(
+                        || format!("acc {}", h),
+                        acc_column,
+                        row,
+                        || path.size.map(|size| F::from((size >> h) as u64)),
+                    )?;
+                    onehot.push(region.assign_advice(
+                        || format!("onehot {}", h),
+                        onehot_column,
+                        row,
+                        || path.peak_height.map(|peak| as_field(peak == h)),
+                    )?);
+                    count = region.assign_advice(
+                        || format!("count {}", h),
+                        count_column,
+                        row,
+                        || path.peak_height.map(|peak| as_field(peak >= h)),
+                    )?;
+                    started = region.assign_advice(
+                        || format!("started {}", h),
+                        started_column,
+                        row,
+                        || path.size.map(|size| as_field(size >> h != 0)),
+                    )?;
+                }
+                region.constrain_constant(count.cell(), F::one())?;
+
+                // Assigned top down, indexed by height from here on.
+                for column in [&mut bits, &mut onehot, &mut started_before] {
+                    column.reverse();
+                }
+                Ok(Shape {
+                    size: acc,
+                    bits,
+                    onehot,
+                    started: started_before,
+                })
+            },
+        )
+    }
+
+    // Compute the root of the range from the path of `leaf` to its peak and
+    // the other peaks.
+    pub fn root(
+        &self,
+        mut layouter: impl Layouter<F>,
+        leaf: &AssignedCell<F, F>,
+        path: &MmrPath<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let height = path.height;
+        assert_eq!(path.siblings.len(), height - 1);
+        assert_eq!(path.peaks.len(), height);
+        let swap_chip = SwapChip::new(self.config.swap.clone());
+        let shape = self.assign_shape(layouter.namespace(|| "shape"), path)?;
+
+        // Every node on the path up to the highest peak, the one at the
+        // height of the leaf's peak has to be that peak.
+        let mut nodes = vec![leaf.clone()];
+        {
+            let mut layouter = layouter.namespace(|| "peak");
+            for (level, sibling) in path.siblings.iter().enumerate() {
+                let (left, right) = swap_chip.swap(
+                    layouter.namespace(|| format!("swap {}", level)),
+                    &nodes[level],
+                    *sibling,
+                    path.index.map(|index| (index >> level) & 1 == 1),
+                )?;
+                nodes.push(poseidon::hash_assigned(
+                    &self.config.poseidon,
+                    layouter.namespace(|| format!("node {}", level)),
+                    [left, right],
+                )?);
+            }
+        }
+
+        let peaks = layouter.assign_region(
+            || "load peaks",
+            |mut region| {
+                path.peaks
+                    .iter()
+                    .enumerate()
+                    .map(|(h, value)| {
+                        region.assign_advice(
+                            || format!("peak {}", h),
+                            self.config.value,
+                            h,
+                            || *value,
+                        )
+                    })
+                    .collect::<Result<Vec<_>, Error>>()
+            },
+        )?;
+        for (h, (peak, node)) in peaks.iter().zip(nodes.iter()).enumerate() {
+            let checked = swap_chip.select(
+                layouter.namespace(|| format!("check peak {}", h)),
+                &shape.onehot[h],
+                node,
+                peak,
+            )?;
+            layouter.assign_region(
+                || format!("check peak {}", h),
+                |mut region| region.constrain_equal(checked.cell(), peak.cell()),
+            )?;
+        }
+
+        // The chain over the peaks the range has, largest first. No larger
+        // peak can come before the top one, so it needs no hash.
+        let mut bag = peaks[height - 1].clone();
+        for h in (0..height - 1).rev() {
+            let hash = poseidon::hash_assigned(
+                &self.config.poseidon,
+                layouter.namespace(|| format!("bag {}", h)),
+                [bag.clone(), peaks[h].clone()],
+            )?;
+            let next = swap_chip.select(
+                layouter.namespace(|| format!("bag {}", h)),
+                &shape.started[h],
+                &hash,
+                &peaks[h],
+            )?;
+            bag = swap_chip.select(
+                layouter.namespace(|| format!("bag {}", h)),
+                &shape.bits[h],
+                &next,
+                &bag,
+            )?;
+        }
+        poseidon::hash_assigned(
+            &self.config.poseidon,
+            layouter.namespace(|| "root"),
+            [shape.size, bag],
+        )
+    }
+}
+
+// Rows of the instance column.
+pub const ROOT: usize = 0;
+pub const LEAF: usize = 1;
+
+pub fn layout() -> InstanceLayout {
+    InstanceLayout::new("mmr-inclusion", 1)
+        .field("root", 1)
+        .field("leaf", 1)
+}
+
+pub fn spec() -> StatementSpec {
+    StatementSpec::new(layout())
+        .relation(
+            "peak",
+            "leaf is on the path to one of the peaks",
+            &[
+                "load leaf",
+                "mmr root/shape",
+                "mmr root/peak",
+                "mmr root/check peak 0",
+            ],
+        )
+        .relation(
+            "root",
+            "root bags the peaks and commits to the size of the range",
+            &["mmr root/load peaks", "mmr root/bag 0", "mmr root/root"],
+        )
+}
+
+// Proof that a leaf is part of the range with a public root.
+#[derive(Clone, Debug)]
+pub struct InclusionCircuit<F: FieldExt = Fr> {
+    pub leaf: Value<F>,
+    pub path: MmrPath<F>,
+}
+
+impl<F: FieldExt> InclusionCircuit<F> {
+    pub fn new(proof: &MmrProof<F>, leaf: F) -> Self {
+        Self::with_height(MAX_HEIGHT, proof, leaf)
+    }
+
+    // For deployments that bound the range lower than `MAX_HEIGHT`.
+    pub fn with_height(height: usize, proof: &MmrProof<F>, leaf: F) -> Self {
+        Self {
+            leaf: Value::known(leaf),
+            path: MmrPath::new(height, proof),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct InclusionConfig<F: FieldExt> {
+    mmr: MmrConfig<F>,
+    instance: Column<Instance>,
+}
+
+impl<F: FieldExt> Circuit<F> for InclusionCircuit<F> {
+    type Config = InclusionConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            leaf: Value::unknown(),
+            path: self.path.without_witnesses(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+
+        InclusionConfig {
+            mmr: MmrChip::configure(meta),
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let (leaf, root) = self.assign(&config.mmr, layouter.namespace(|| "inclusion"))?;
+        layouter.constrain_instance(leaf.cell(), config.instance, LEAF)?;
+        layouter.constrain_instance(root.cell(), config.instance, ROOT)
+    }
+}
+
+impl<F: FieldExt> InclusionCircuit<F> {
+    // The leaf and the root it is included in.
+    fn assign(
+        &self,
+        config: &MmrConfig<F>,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>), Error> {
+        let leaf = layouter.assign_region(
+            || "load leaf",
+            |mut region| region.assign_advice(|| "leaf", config.value, 0, || self.leaf),
+        )?;
+
+        let chip = MmrChip::new(config.clone());
+        let root = chip.root(layouter.namespace(|| "mmr root"), &leaf, &self.path)?;
+        Ok((leaf, root))
+    }
+}
+
+impl<F: FieldExt> SubCircuit<F> for InclusionCircuit<F> {
+    type Config = MmrConfig<F>;
+
+    fn without_witnesses(&self) -> Self {
+        Circuit::without_witnesses(self)
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>, shared: &Shared<F>) -> Self::Config {
+        MmrConfig {
+            value: shared.value,
+            poseidon: shared.poseidon.clone(),
+            swap: SwapChip::configure(meta),
+            shape: configure_shape(meta),
+        }
+    }
+
+    fn instances(&self) -> usize {
+        layout().len()
+    }
+
+    fn rows(&self) -> usize {
+        let height = self.path.height;
+        // the path, the bag and the root
+        let hashes = 2 * (height - 1) + 1;
+        // path swaps, peak checks and two selects per bagged peak
+        let selects = (height - 1) + height + 2 * (height - 1);
+        // leaf, shape and peaks
+        1 + (height + 1) + height + selects + hashes * HASH_ROWS
+    }
+
+    fn synthesize(
+        &self,
+        config: &Self::Config,
+        shared: &Shared<F>,
+        mut layouter: impl Layouter<F>,
+        offset: usize,
+        _: &mut Wires<F>,
+    ) -> Result<(), Error> {
+        let (leaf, root) = self.assign(config, layouter.namespace(|| "inclusion"))?;
+        shared.expose(layouter.namespace(|| "leaf"), leaf, offset + LEAF)?;
+        shared.expose(layouter.namespace(|| "root"), root, offset + ROOT)
+    }
+}
@@ -0,0 +1,415 @@
+// Oracle attestations: every member of the committee signs the value it
+// observed for the epoch and the circuit exposes the median of the signed
+// values, so a contract gets a feed value backed by a majority of the
+// committee without trusting whoever collected the signatures.
+//
+// The committee has an odd number of members, which makes the median the
+// one value with at most half of the others below and at most half above
+// it. Values have `VALUE_BITS` bits, so every comparison is a range check
+// of a difference. Members sign the observation itself rather than a
+// digest, the message being epoch * 2^VALUE_BITS + value. A value spans
+// exactly one limb of the scalar, so the circuit pins the message limb by
+// limb: the value, then the epoch, then zeros. Comparing the native
+// composition instead would let the message differ by a multiple of the
+// native modulus. Any other message with a valid signature would be a
+// forgery.
+//
+// `ObservationBuilder` is the payload builder of a member. Its payload is
+// the value, and its message hash the `observation` of it rather than the
+// payload digest.
+use crate::committee::{member_leaf, padding_key};
+use crate::ecdsa::{
+    self, AssignedEcdsaSig, AssignedPublicKey, EcdsaChip, EcdsaConfig, BIT_LEN_LIMB, MUL_PAIRS,
+    NUMBER_OF_LIMBS,
+};
+use crate::error::CircuitError;
+use crate::layout::InstanceLayout;
+use crate::merkle::{IncrementalTree, MerkleChip};
+use crate::payload::PayloadBuilder;
+use crate::poseidon::{self, PoseidonConfig};
+use crate::secret::Secret;
+use crate::spec::StatementSpec;
+use ecc::GeneralEccChip;
+use halo2_proofs::{
+    arithmetic::{CurveAffine, FieldExt},
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    plonk::{Circuit, ConstraintSystem, Error},
+};
+use integer::{IntegerInstructions, Range};
+use maingate::{
+    fe_to_big, AssignedValue, MainGate, MainGateInstructions, RangeChip, RangeInstructions,
+    RegionCtx, Term,
+};
+use rand::RngCore;
+
+// Limbs of the range chip configured by `EcdsaConfig`. Values span a whole
+// number of limbs, so they need no overflow table, and one limb of a scalar.
+const LIMB_BITS: usize = BIT_LEN_LIMB / NUMBER_OF_LIMBS;
+pub const VALUE_BITS: usize = BIT_LEN_LIMB;
+
+// Rows of the instance column, see `layout`.
+pub const MEMBERS_ROOT: usize = 0;
+pub const EPOCH: usize = 1;
+pub const MEDIAN: usize = 2;
+
+pub fn layout() -> InstanceLayout {
+    InstanceLayout::new("oracle", 1)
+        .field("members_root", 1)
+        .field("epoch", 1)
+        .field("median", 1)
+}
+
+pub fn spec() -> StatementSpec {
+    StatementSpec::new(layout())
+        .relation(
+            "observations",
+            "every member signed epoch * 2^VALUE_BITS + value for its value",
+            &["verify observations"],
+        )
+        .relation(
+            "median",
+            "at most half of the values are below median and at most half above",
+            &["verify observations"],
+        )
+        .relation(
+            "members",
+            "members_root is the Merkle root over the member keys",
+            &["member 0", "members root"],
+        )
+}
+
+// Message a member signs for observing `value` in `epoch`.
+pub fn observation<E: CurveAffine>(epoch: u64, value: u64) -> E::ScalarExt {
+    E::ScalarExt::from(epoch) * E::ScalarExt::from_u128(1 << VALUE_BITS) + E::ScalarExt::from(value)
+}
+
+pub fn sign_observation<E: CurveAffine>(
+    sk: &Secret<E::ScalarExt>,
+    epoch: u64,
+    value: u64,
+    rng: impl RngCore,
+) -> (E::Scalar, E::Scalar) {
+    ecdsa::sign::<E>(sk, observation::<E>(epoch, value), rng)
+}
+
+// The observation carried by a payload of `ObservationBuilder`.
+pub fn payload_observation<E: CurveAffine>(epoch: u64, payload: &[u8]) -> Option<E::ScalarExt> {
+    let value = u64::from_be_bytes(payload.try_into().ok()?);
+    Some(observation::<E>(epoch, value))
+}
+
+// Payloads of a member: the value it last observed, as 8 big-endian bytes.
+// An observation is attested to once, so an epoch without a new one has no
+// payload instead of repeating the last value.
+#[derive(Clone, Debug, Default)]
+pub struct ObservationBuilder {
+    value: Option<u64>,
+}
+
+impl ObservationBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Replaces an observation not attested to yet.
+    pub fn observe(&mut self, value: u64) {
+        self.value = Some(value);
+    }
+
+    pub fn value(&self) -> Option<u64> {
+        self.value
+    }
+}
+
+impl PayloadBuilder for ObservationBuilder {
+    fn kind(&self) -> &'static str {
+        "oracle"
+    }
+
+    fn build(&mut self, _epoch: u64) -> Option<Vec<u8>> {
+        Some(self.value.take()?.to_be_bytes().to_vec())
+    }
+
+    fn msg_hash<C: CurveAffine>(&self, epoch: u64, payload: &[u8]) -> C::ScalarExt {
+        payload_observation::<C>(epoch, payload).expect("oracle payloads are a value")
+    }
+}
+
+pub fn median(values: &[u64]) -> u64 {
+    let mut values = values.to_vec();
+    values.sort_unstable();
+    values[values.len() / 2]
+}
+
+// Members are committed to like in the committee circuit, padded with the
+// padding key to a power of two.
+pub fn members_root<E: CurveAffine, N: FieldExt>(members: &[E]) -> N {
+    let mut registry = IncrementalTree::new(
+        members.len().next_power_of_two().trailing_zeros() as usize,
+        member_leaf::<E, N>(&padding_key::<E>()),
+    );
+    for member in members {
+        registry.push(member_leaf::<E, N>(member));
+    }
+    registry.root()
+}
+
+pub fn instances<E: CurveAffine, N: FieldExt>(members: &[E], epoch: u64, values: &[u64]) -> Vec<N> {
+    layout()
+        .encode(&[
+            ("members_root", &[members_root::<E, N>(members)]),
+            ("epoch", &[N::from(epoch)]),
+            ("median", &[N::from(median(values))]),
+        ])
+        .expect("oracle instances match the layout")
+}
+
+#[derive(Clone, Debug)]
+pub struct OracleCircuit<E: CurveAffine, const MEMBERS: usize> {
+    pub members: Vec<Value<E>>,
+    pub values: Vec<Value<u64>>,
+    // Signatures on the observation of every member.
+    pub signatures: Vec<Value<(E::Scalar, E::Scalar)>>,
+    pub epoch: u64,
+    pub aux_generator: E,
+    pub window_size: usize,
+}
+
+impl<E: CurveAffine, const MEMBERS: usize> OracleCircuit<E, MEMBERS> {
+    pub fn new(
+        members: &[E],
+        values: &[u64],
+        signatures: &[(E::Scalar, E::Scalar)],
+        epoch: u64,
+        aux_generator: E,
+        window_size: usize,
+    ) -> Result<Self, CircuitError> {
+        if members.len() != MEMBERS || values.len() != MEMBERS || signatures.len() != MEMBERS {
+            return Err(CircuitError::InvalidWitness(format!(
+                "{} members, {} values and {} signatures for {} members, every member has to \
+                 report",
+                members.len(),
+                values.len(),
+                signatures.len(),
+                MEMBERS
+            )));
+        }
+        Ok(Self {
+            members: members.iter().copied().map(Value::known).collect(),
+            values: values.iter().copied().map(Value::known).collect(),
+            signatures: signatures.iter().copied().map(Value::known).collect(),
+            epoch,
+            aux_generator,
+            window_size,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct OracleConfig<N: FieldExt> {
+    ecdsa: EcdsaConfig,
+    poseidon: PoseidonConfig<N>,
+}
+
+impl<E: CurveAffine, N: FieldExt, const MEMBERS: usize> Circuit<N> for OracleCircuit<E, MEMBERS> {
+    type Config = OracleConfig<N>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            members: vec![Value::unknown(); MEMBERS],
+            values: vec![Value::unknown(); MEMBERS],
+            signatures: vec![Value::unknown(); MEMBERS],
+            epoch: self.epoch,
+            aux_generator: self.aux_generator,
+            window_size: self.window_size,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<N>) -> Self::Config {
+        assert!(
+            MEMBERS % 2 == 1,
+            "the median needs an odd number of members"
+        );
+        OracleConfig {
+            ecdsa: EcdsaConfig::configure::<E, N>(meta),
+            poseidon: poseidon::configure(meta),
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<N>,
+    ) -> Result<(), Error> {
+        let mut ecc_chip = GeneralEccChip::<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>::new(
+            config.ecdsa.ecc_chip_config(),
+        );
+        let main_gate = MainGate::<N>::new(config.ecdsa.main_gate_config());
+        let range_chip = RangeChip::<N>::new(config.ecdsa.range_config());
+
+        layouter.assign_region(
+            || "assign aux values",
+            |region| {
+                let ctx = &mut RegionCtx::new(region, 0);
+                ecc_chip.assign_aux_generator(ctx, Value::known(self.aux_generator))?;
+                ecc_chip.assign_aux(ctx, self.window_size, MUL_PAIRS)?;
+                Ok(())
+            },
+        )?;
+
+        let ecdsa_chip = EcdsaChip::new(ecc_chip.clone(), self.window_size);
+        let scalar_chip = ecc_chip.scalar_field_chip();
+
+        let values = self
+            .values
+            .iter()
+            .fold(Value::known(vec![]), |values, value| {
+                values.zip(*value).map(|(mut values, value)| {
+                    values.push(value);
+                    values
+                })
+            });
+
+        let (members, epoch, median) = layouter.assign_region(
+            || "verify observations",
+            |region| {
+                let ctx = &mut RegionCtx::new(region, 0);
+
+                let generator = ecdsa_chip.assign_generator(ctx)?;
+                let epoch = main_gate.assign_value(ctx, Value::known(N::from(self.epoch)))?;
+                let median = range_chip.assign(
+                    ctx,
+                    values.as_ref().map(|values| N::from(median(values))),
+                    LIMB_BITS,
+                    VALUE_BITS,
+                )?;
+
+                let mut members = Vec::with_capacity(MEMBERS);
+                let mut below = Vec::with_capacity(MEMBERS);
+                let mut above = Vec::with_capacity(MEMBERS);
+                for i in 0..MEMBERS {
+                    let member = ecc_chip.assign_point(ctx, self.members[i])?;
+                    let value = range_chip.assign(
+                        ctx,
+                        self.values[i].map(N::from),
+                        LIMB_BITS,
+                        VALUE_BITS,
+                    )?;
+
+                    let msg = self.values[i].map(|value| observation::<E>(self.epoch, value));
+                    let msg = scalar_chip.assign_integer(
+                        ctx,
+                        ecc_chip.new_unassigned_scalar(msg),
+                        Range::Remainder,
+                    )?;
+                    main_gate.assert_equal(ctx, &msg.limb(0), &value)?;
+                    main_gate.assert_equal(ctx, &msg.limb(1), &epoch)?;
+                    for limb in 2..NUMBER_OF_LIMBS {
+                        main_gate.assert_zero(ctx, &msg.limb(limb))?;
+                    }
+
+                    let r = self.signatures[i].map(|signature| signature.0);
+                    let s = self.signatures[i].map(|signature| signature.1);
+                    let r = scalar_chip.assign_integer(
+                        ctx,
+                        ecc_chip.new_unassigned_scalar(r),
+                        Range::Remainder,
+                    )?;
+                    let s = scalar_chip.assign_integer(
+                        ctx,
+                        ecc_chip.new_unassigned_scalar(s),
+                        Range::Remainder,
+                    )?;
+                    ecdsa_chip.verify_with_generator(
+                        ctx,
+                        &generator,
+                        &AssignedEcdsaSig { r, s },
+                        &AssignedPublicKey {
+                            point: member.clone(),
+                        },
+                        &msg,
+                    )?;
+
+                    below.push(less_than(&main_gate, &range_chip, ctx, &value, &median)?);
+                    above.push(less_than(&main_gate, &range_chip, ctx, &median, &value)?);
+                    members.push(member);
+                }
+
+                let half = N::from(((MEMBERS - 1) / 2) as u64);
+                for flags in [&below, &above] {
+                    let terms = flags
+                        .iter()
+                        .map(|flag| Term::Assigned(flag, N::one()))
+                        .collect::<Vec<_>>();
+                    let count = main_gate.compose(ctx, &terms, N::zero())?;
+                    let slack = main_gate.neg_with_constant(ctx, &count, half)?;
+                    let checked =
+                        range_chip.assign(ctx, slack.value().copied(), LIMB_BITS, LIMB_BITS)?;
+                    main_gate.assert_equal(ctx, &checked, &slack)?;
+                }
+
+                Ok((members, epoch, median))
+            },
+        )?;
+
+        let merkle_chip = MerkleChip::new(config.poseidon.clone());
+        let mut leaves = members
+            .iter()
+            .enumerate()
+            .map(|(i, member)| {
+                merkle_chip.hash_pair(
+                    layouter.namespace(|| format!("member {}", i)),
+                    member.x().native().clone(),
+                    member.y().native().clone(),
+                )
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        if !MEMBERS.is_power_of_two() {
+            let padding = layouter.assign_region(
+                || "padding leaves",
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+                    main_gate.assign_constant(ctx, member_leaf::<E, N>(&padding_key::<E>()))
+                },
+            )?;
+            leaves.resize(MEMBERS.next_power_of_two(), padding);
+        }
+        let root = merkle_chip.root(layouter.namespace(|| "members root"), &leaves)?;
+
+        main_gate.expose_public(layouter.namespace(|| "members root"), root, MEMBERS_ROOT)?;
+        main_gate.expose_public(layouter.namespace(|| "epoch"), epoch, EPOCH)?;
+        main_gate.expose_public(layouter.namespace(|| "median"), median, MEDIAN)?;
+
+        config.ecdsa.config_range(&mut layouter)?;
+
+        Ok(())
+    }
+}
+
+// Assign whether a < b for values of `VALUE_BITS` bits: the difference
+// b - a - 1 if it holds and a - b otherwise has to fit `VALUE_BITS` bits.
+fn less_than<N: FieldExt>(
+    main_gate: &MainGate<N>,
+    range_chip: &RangeChip<N>,
+    ctx: &mut RegionCtx<'_, N>,
+    a: &AssignedValue<N>,
+    b: &AssignedValue<N>,
+) -> Result<AssignedValue<N>, Error> {
+    let lt = a.value().zip(b.value()).map(|(a, b)| {
+        if fe_to_big(*a) < fe_to_big(*b) {
+            N::one()
+        } else {
+            N::zero()
+        }
+    });
+    let lt = main_gate.assign_bit(ctx, lt)?;
+
+    let below = main_gate.sub(ctx, b, a)?;
+    let below = main_gate.sub_with_constant(ctx, &below, N::one())?;
+    let not_below = main_gate.sub(ctx, a, b)?;
+    let diff = main_gate.select(ctx, &below, &not_below, &lt)?;
+    let checked = range_chip.assign(ctx, diff.value().copied(), LIMB_BITS, VALUE_BITS)?;
+    main_gate.assert_equal(ctx, &checked, &diff)?;
+    Ok(lt)
+}
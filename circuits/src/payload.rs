@@ -0,0 +1,104 @@
+// What the committee signs every epoch. A builder produces the payload of an
+// application, e.g. a chain head, a price feed or a rollup state root, and
+// the committee signs its digest as `msg_hash`, so applications plug in their
+// own statement without touching the committee circuit. The digest covers
+// the kind of the builder and the epoch, so payloads of different builders
+// or epochs never share a signature. A builder whose payload a circuit
+// checks next to the committee can sign something cheaper to rebuild there
+// instead, see `PayloadBuilder::msg_hash`.
+use halo2_proofs::arithmetic::{CurveAffine, FieldExt};
+use sha2::{Digest, Sha256};
+
+pub trait PayloadBuilder {
+    // Short name of the payload format.
+    fn kind(&self) -> &'static str;
+
+    // Payload for `epoch`, or None when there is nothing to attest to yet.
+    fn build(&mut self, epoch: u64) -> Option<Vec<u8>>;
+
+    // Message hash the committee signs for `payload`, the SHA-256 digest by
+    // default.
+    fn msg_hash<C: CurveAffine>(&self, epoch: u64, payload: &[u8]) -> C::ScalarExt {
+        msg_hash::<C>(&digest(self.kind(), epoch, payload))
+    }
+}
+
+pub fn digest(kind: &str, epoch: u64, payload: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update((kind.len() as u64).to_be_bytes());
+    hasher.update(kind.as_bytes());
+    hasher.update(epoch.to_be_bytes());
+    hasher.update(payload);
+    hasher.finalize().into()
+}
+
+// The digest as the message hash signed by the committee.
+pub fn msg_hash<C: CurveAffine>(digest: &[u8; 32]) -> C::ScalarExt {
+    let mut bytes = [0u8; 64];
+    bytes[..32].copy_from_slice(digest);
+    C::ScalarExt::from_bytes_wide(&bytes)
+}
+
+// Build the payload of `epoch` and return it with its message hash.
+pub fn build<C: CurveAffine>(
+    builder: &mut impl PayloadBuilder,
+    epoch: u64,
+) -> Option<(Vec<u8>, C::ScalarExt)> {
+    let payload = builder.build(epoch)?;
+    let msg_hash = builder.msg_hash::<C>(epoch, &payload);
+    Some((payload, msg_hash))
+}
+
+// A Filecoin tipset, by the CIDs of its block headers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tipset {
+    pub height: u64,
+    pub blocks: Vec<Vec<u8>>,
+}
+
+// The default builder: the committee attests to the heaviest tipset it saw,
+// approximated by the highest one.
+#[derive(Clone, Debug, Default)]
+pub struct TipsetHeaderBuilder {
+    head: Option<Tipset>,
+}
+
+impl TipsetHeaderBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Tipsets below the current head are ignored.
+    pub fn update(&mut self, tipset: Tipset) {
+        if self
+            .head
+            .as_ref()
+            .map_or(true, |head| tipset.height >= head.height)
+        {
+            self.head = Some(tipset);
+        }
+    }
+
+    pub fn head(&self) -> Option<&Tipset> {
+        self.head.as_ref()
+    }
+}
+
+impl PayloadBuilder for TipsetHeaderBuilder {
+    fn kind(&self) -> &'static str {
+        "tipset"
+    }
+
+    // Height, then every block CID prefixed with its length, in the order
+    // of the tipset key.
+    fn build(&mut self, _epoch: u64) -> Option<Vec<u8>> {
+        let head = self.head.as_ref()?;
+        let mut payload = head.height.to_be_bytes().to_vec();
+        payload.extend_from_slice(&(head.blocks.len() as u32).to_be_bytes());
+        for cid in head.blocks.iter() {
+            payload.extend_from_slice(&(cid.len() as u32).to_be_bytes());
+            payload.extend_from_slice(cid);
+        }
+        Some(payload)
+    }
+}
@@ -0,0 +1,258 @@
+// PoDSI sub-piece inclusion in a circuit: the piece commitment of a
+// sub-piece is the node at `index` of the SHA-254 tree of an aggregate,
+// `LEVELS` above it. This is `commp::SubPieceProof::verify` with the path as
+// a witness, so an aggregation circuit can check deal inclusion next to the
+// attestation of the data.
+//
+// A node is 32 bytes and its parent the SHA-256 digest of the two children
+// with the top two bits cleared, which takes two compressions: one over the
+// children and one over the padding block, which is the same for every
+// node. The direction bits make up the public index, which bounds it by
+// 2^LEVELS. The circuit only fixes the number of levels between the two
+// pieces; their sizes come with the deal, and the verifier checks that they
+// are LEVELS heights apart as the native verifier does.
+use crate::bytes::ByteOp;
+use crate::commp::SubPieceProof;
+use crate::error::CircuitError;
+use crate::layout::InstanceLayout;
+use crate::sha256::{self, Sha256Chip, Sha256Config, Word};
+use crate::spec::StatementSpec;
+use crate::swap::{SwapChip, SwapConfig};
+use ff::Field;
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Expression, Instance, Selector},
+    poly::Rotation,
+};
+use halo2curves::bn256::Fr;
+
+const NODE_SIZE: usize = 32;
+
+// Rows of the instance column, see `layout`.
+pub const PIECE: usize = 0;
+pub const AGGREGATE: usize = PIECE + NODE_SIZE;
+pub const INDEX: usize = AGGREGATE + NODE_SIZE;
+
+pub fn layout() -> InstanceLayout {
+    InstanceLayout::new("podsi-sub-piece", 1)
+        .field("piece", NODE_SIZE)
+        .field("aggregate", NODE_SIZE)
+        .field("index", 1)
+}
+
+pub fn spec() -> StatementSpec {
+    StatementSpec::new(layout())
+        .relation(
+            "index",
+            "index is the number with the direction bits of the path",
+            &["direction bits"],
+        )
+        .relation(
+            "path",
+            "aggregate is the SHA-254 root over piece at index and the siblings \
+             of the path",
+            &["level 0/swap", "level 0/parent", "level 0/truncate"],
+        )
+}
+
+// Second block of every node hash: the padding after 64 bytes of message.
+fn padding_block() -> [u32; 16] {
+    let mut block = [0; 16];
+    block[0] = 0x8000_0000;
+    block[15] = 512;
+    block
+}
+
+pub fn instances(piece: &[u8; NODE_SIZE], aggregate: &[u8; NODE_SIZE], index: u64) -> Vec<Fr> {
+    let piece = piece.map(|byte| Fr::from(byte as u64));
+    let aggregate = aggregate.map(|byte| Fr::from(byte as u64));
+    layout()
+        .encode(&[
+            ("piece", &piece),
+            ("aggregate", &aggregate),
+            ("index", &[Fr::from(index)]),
+        ])
+        .expect("sub-piece instances match the layout")
+}
+
+#[derive(Clone, Debug)]
+pub struct SubPieceCircuit<const LEVELS: usize> {
+    pub piece: Value<[u8; NODE_SIZE]>,
+    pub index: Value<u64>,
+    // Path from the sub-piece to the aggregate root, bottom up.
+    pub siblings: Vec<Value<[u8; NODE_SIZE]>>,
+}
+
+impl<const LEVELS: usize> SubPieceCircuit<LEVELS> {
+    pub fn new(proof: &SubPieceProof, piece: [u8; NODE_SIZE]) -> Result<Self, CircuitError> {
+        if proof.siblings.len() != LEVELS {
+            return Err(CircuitError::InvalidWitness(format!(
+                "path of {} levels in a circuit of {}",
+                proof.siblings.len(),
+                LEVELS
+            )));
+        }
+        Ok(Self {
+            piece: Value::known(piece),
+            index: Value::known(proof.index),
+            siblings: proof.siblings.iter().copied().map(Value::known).collect(),
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SubPieceConfig {
+    sha256: Sha256Config,
+    swap: SwapConfig,
+    // bit, acc
+    bits: [Column<Advice>; 2],
+    q_bits: Selector,
+    instance: Column<Instance>,
+}
+
+impl<const LEVELS: usize> Circuit<Fr> for SubPieceCircuit<LEVELS> {
+    type Config = SubPieceConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            piece: Value::unknown(),
+            index: Value::unknown(),
+            siblings: vec![Value::unknown(); LEVELS],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        let constant = meta.fixed_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        let bits = [(); 2].map(|_| meta.advice_column());
+        for column in bits.iter() {
+            meta.enable_equality(*column);
+        }
+        let q_bits = meta.selector();
+
+        // acc is the index with the bits from the current row up, so the last
+        // acc, fixed to zero, leaves no room above 2^LEVELS. The swaps each
+        // bit goes into make it boolean.
+        meta.create_gate("direction bits", |meta| {
+            let q_bits = meta.query_selector(q_bits);
+            let bit = meta.query_advice(bits[0], Rotation::cur());
+            let acc = meta.query_advice(bits[1], Rotation::cur());
+            let next = meta.query_advice(bits[1], Rotation::next());
+            vec![q_bits * (acc - bit - next * Expression::Constant(Fr::from(2)))]
+        });
+
+        SubPieceConfig {
+            sha256: Sha256Chip::configure(meta, constant),
+            swap: SwapChip::configure(meta),
+            bits,
+            q_bits,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let chip = Sha256Chip::new(config.sha256.clone());
+        chip.load(&mut layouter)?;
+        let swap_chip = SwapChip::new(config.swap.clone());
+
+        let (bits, index) = layouter.assign_region(
+            || "direction bits",
+            |mut region| {
+                let mut bits = vec![];
+                let mut acc = None;
+                for level in (0..LEVELS).rev() {
+                    config.q_bits.enable(&mut region, level)?;
+                    bits.push(region.assign_advice(
+                        || "bit",
+                        config.bits[0],
+                        level,
+                        || self.index.map(|index| Fr::from((index >> level) & 1)),
+                    )?);
+                    acc = Some(region.assign_advice(
+                        || "acc",
+                        config.bits[1],
+                        level,
+                        || {
+                            self.index
+                                .map(|index| Fr::from((index & ((1 << LEVELS) - 1)) >> level))
+                        },
+                    )?);
+                }
+                let top = region.assign_advice_from_constant(
+                    || "top",
+                    config.bits[1],
+                    LEVELS,
+                    Fr::zero(),
+                )?;
+                bits.reverse();
+                Ok((bits, acc.unwrap_or(top)))
+            },
+        )?;
+
+        let piece = chip.assign_bytes(
+            layouter.namespace(|| "piece"),
+            &(0..NODE_SIZE)
+                .map(|i| self.piece.map(|piece| piece[i]))
+                .collect::<Vec<_>>(),
+        )?;
+        let iv = chip.constant_words(layouter.namespace(|| "iv"), &sha256::IV)?;
+        let padding = chip.constant_words(layouter.namespace(|| "padding"), &padding_block())?;
+        let mask = chip
+            .byte_chip()
+            .constant(layouter.namespace(|| "mask"), 0x3f)?;
+
+        let mut node = piece.clone();
+        for (level, (sibling, bit)) in self.siblings.iter().zip(bits.iter()).enumerate() {
+            let mut layouter = layouter.namespace(|| format!("level {}", level));
+            let sibling = chip.assign_bytes(
+                layouter.namespace(|| "sibling"),
+                &(0..NODE_SIZE)
+                    .map(|i| sibling.map(|sibling| sibling[i]))
+                    .collect::<Vec<_>>(),
+            )?;
+
+            // Left is the sibling when the bit is set, the node otherwise.
+            let mut left = vec![];
+            let mut right = vec![];
+            for (i, (node, sibling)) in node.iter().zip(sibling.iter()).enumerate() {
+                let mut layouter = layouter.namespace(|| format!("swap {}", i));
+                left.push(swap_chip.select(layouter.namespace(|| "left"), bit, sibling, node)?);
+                right.push(swap_chip.select(layouter.namespace(|| "right"), bit, node, sibling)?);
+            }
+
+            let block = left
+                .chunks(4)
+                .chain(right.chunks(4))
+                .map(Word::from_be_bytes)
+                .collect::<Vec<_>>();
+            let mut layouter = layouter.namespace(|| "parent");
+            let state = chip.compress(layouter.namespace(|| "children"), &iv, &block)?;
+            let digest = chip.compress(layouter.namespace(|| "padding"), &state, &padding)?;
+            node = digest.iter().flat_map(Word::to_be_bytes).collect();
+
+            // SHA-254 clears the top two bits of the digest, those of its
+            // last byte.
+            let last = node.pop().expect("a node is 32 bytes");
+            node.push(chip.byte_chip().apply(
+                layouter.namespace(|| "truncate"),
+                ByteOp::And,
+                &last,
+                &mask,
+            )?);
+        }
+
+        for (i, byte) in piece.iter().enumerate() {
+            layouter.constrain_instance(byte.cell(), config.instance, PIECE + i)?;
+        }
+        for (i, byte) in node.iter().enumerate() {
+            layouter.constrain_instance(byte.cell(), config.instance, AGGREGATE + i)?;
+        }
+        layouter.constrain_instance(index.cell(), config.instance, INDEX)
+    }
+}
@@ -0,0 +1,208 @@
+// Quorum policies over the seats of a committee. A policy is a tree whose
+// leaves are seats and whose nodes require the weights of their satisfied
+// children to reach a threshold, which covers k-of-n (unit weights),
+// stake-weighted thresholds and nested organisations alike.
+//
+// The shape of the tree is fixed by the circuit, while thresholds and
+// weights are witnesses bound by the policy commitment in the public inputs,
+// so one key serves every policy of the same shape.
+use crate::poseidon::{self, PoseidonConfig};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, Value},
+    plonk::Error,
+};
+use maingate::{AssignedCondition, AssignedValue, MainGate, MainGateInstructions, RegionCtx};
+
+// Weight sums and thresholds must stay below 2^WEIGHT_BITS.
+pub const WEIGHT_BITS: usize = 64;
+
+const SEAT_TAG: u64 = 1;
+const THRESHOLD_TAG: u64 = 2;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum QuorumPolicy {
+    Seat(usize),
+    Threshold {
+        threshold: u64,
+        members: Vec<(u64, QuorumPolicy)>,
+    },
+}
+
+impl QuorumPolicy {
+    pub fn k_of_n(k: u64, seats: impl IntoIterator<Item = usize>) -> Self {
+        Self::threshold(k, seats.into_iter().map(|seat| (1, Self::Seat(seat))))
+    }
+
+    // `weights` is indexed by seat.
+    pub fn weighted(threshold: u64, weights: &[u64]) -> Self {
+        Self::threshold(
+            threshold,
+            weights
+                .iter()
+                .enumerate()
+                .map(|(seat, &weight)| (weight, Self::Seat(seat))),
+        )
+    }
+
+    // All nested policies count with the same weight, e.g. 2-of-3
+    // organisations each with an internal 3-of-5.
+    pub fn nested(k: u64, policies: impl IntoIterator<Item = QuorumPolicy>) -> Self {
+        Self::threshold(k, policies.into_iter().map(|policy| (1, policy)))
+    }
+
+    pub fn threshold(
+        threshold: u64,
+        members: impl IntoIterator<Item = (u64, QuorumPolicy)>,
+    ) -> Self {
+        let members = members.into_iter().collect::<Vec<_>>();
+        members
+            .iter()
+            .try_fold(0u64, |total, (weight, _)| total.checked_add(*weight))
+            .expect("total weight overflows");
+        Self::Threshold { threshold, members }
+    }
+
+    pub fn max_seat(&self) -> Option<usize> {
+        match self {
+            Self::Seat(seat) => Some(*seat),
+            Self::Threshold { members, .. } => members
+                .iter()
+                .filter_map(|(_, member)| member.max_seat())
+                .max(),
+        }
+    }
+
+    // `active` is indexed by seat.
+    pub fn satisfied(&self, active: &[bool]) -> bool {
+        match self {
+            Self::Seat(seat) => active[*seat],
+            Self::Threshold { threshold, members } => {
+                let weight: u64 = members
+                    .iter()
+                    .filter(|(_, member)| member.satisfied(active))
+                    .map(|(weight, _)| weight)
+                    .sum();
+                weight >= *threshold
+            }
+        }
+    }
+
+    // Flat encoding of the policy, in the order `assign` lays it out.
+    pub fn encoding<N: FieldExt>(&self) -> Vec<N> {
+        let mut encoding = vec![];
+        self.encode(&mut encoding);
+        encoding
+    }
+
+    fn encode<N: FieldExt>(&self, encoding: &mut Vec<N>) {
+        match self {
+            Self::Seat(seat) => {
+                encoding.push(N::from(SEAT_TAG));
+                encoding.push(N::from(*seat as u64));
+            }
+            Self::Threshold { threshold, members } => {
+                encoding.push(N::from(THRESHOLD_TAG));
+                encoding.push(N::from(*threshold));
+                encoding.push(N::from(members.len() as u64));
+                for (weight, member) in members.iter() {
+                    encoding.push(N::from(*weight));
+                    member.encode(encoding);
+                }
+            }
+        }
+    }
+
+    // Poseidon chain over the encoding, matching `commit_assigned`.
+    pub fn commitment<N: FieldExt>(&self) -> N {
+        let encoding = self.encoding::<N>();
+        encoding[1..]
+            .iter()
+            .fold(encoding[0], |acc, &value| poseidon::hash([acc, value]))
+    }
+
+    // Upper bound of the main gate rows `assign` takes, a row per operation
+    // and at most two per bit of the threshold comparison.
+    pub fn rows(&self) -> usize {
+        match self {
+            Self::Seat(_) => 2,
+            Self::Threshold { members, .. } => {
+                let members = members
+                    .iter()
+                    .map(|(_, member)| 3 + member.rows())
+                    .sum::<usize>();
+                4 + members + 2 + 2 * (WEIGHT_BITS + 1)
+            }
+        }
+    }
+
+    // Evaluate the policy over the assigned `active` bits. Returns whether the
+    // policy is satisfied together with the assigned encoding to commit to.
+    pub fn assign<N: FieldExt>(
+        &self,
+        main_gate: &MainGate<N>,
+        ctx: &mut RegionCtx<'_, N>,
+        active: &[AssignedCondition<N>],
+    ) -> Result<(AssignedCondition<N>, Vec<AssignedValue<N>>), Error> {
+        let mut encoding = vec![];
+        let satisfied = self.assign_node(main_gate, ctx, active, &mut encoding)?;
+        Ok((satisfied, encoding))
+    }
+
+    fn assign_node<N: FieldExt>(
+        &self,
+        main_gate: &MainGate<N>,
+        ctx: &mut RegionCtx<'_, N>,
+        active: &[AssignedCondition<N>],
+        encoding: &mut Vec<AssignedValue<N>>,
+    ) -> Result<AssignedCondition<N>, Error> {
+        match self {
+            Self::Seat(seat) => {
+                encoding.push(main_gate.assign_constant(ctx, N::from(SEAT_TAG))?);
+                encoding.push(main_gate.assign_constant(ctx, N::from(*seat as u64))?);
+                Ok(active[*seat].clone())
+            }
+            Self::Threshold { threshold, members } => {
+                let threshold = main_gate.assign_value(ctx, Value::known(N::from(*threshold)))?;
+                encoding.push(main_gate.assign_constant(ctx, N::from(THRESHOLD_TAG))?);
+                encoding.push(threshold.clone());
+                encoding.push(main_gate.assign_constant(ctx, N::from(members.len() as u64))?);
+
+                let mut weight = main_gate.assign_constant(ctx, N::zero())?;
+                for (member_weight, member) in members.iter() {
+                    let member_weight =
+                        main_gate.assign_value(ctx, Value::known(N::from(*member_weight)))?;
+                    encoding.push(member_weight.clone());
+                    let satisfied = member.assign_node(main_gate, ctx, active, encoding)?;
+                    let counted = main_gate.mul(ctx, &member_weight, &satisfied)?;
+                    weight = main_gate.add(ctx, &weight, &counted)?;
+                }
+
+                // weight >= threshold iff weight - threshold + 2^WEIGHT_BITS
+                // has its top bit set.
+                let shifted = main_gate.sub(ctx, &weight, &threshold)?;
+                let shifted =
+                    main_gate.add_constant(ctx, &shifted, N::from_u128(1 << WEIGHT_BITS))?;
+                let bits = main_gate.to_bits(ctx, &shifted, WEIGHT_BITS + 1)?;
+                Ok(bits[WEIGHT_BITS].clone())
+            }
+        }
+    }
+}
+
+pub fn commit_assigned<N: FieldExt>(
+    config: &PoseidonConfig<N>,
+    mut layouter: impl Layouter<N>,
+    encoding: &[AssignedValue<N>],
+) -> Result<AssignedValue<N>, Error> {
+    encoding[1..]
+        .iter()
+        .enumerate()
+        .try_fold(encoding[0].clone(), |acc, (i, value)| {
+            poseidon::hash_assigned(
+                config,
+                layouter.namespace(|| format!("policy {}", i)),
+                [acc, value.clone()],
+            )
+        })
+}
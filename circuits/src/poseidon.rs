@@ -0,0 +1,84 @@
+use halo2_gadgets::poseidon::{
+    primitives::{self as poseidon, ConstantLength, Spec},
+    Hash, Pow5Chip, Pow5Config,
+};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter},
+    plonk::{ConstraintSystem, Error},
+};
+use std::convert::TryInto;
+
+// Width and rate of the sponge used for every in-circuit hash in the crate.
+pub const WIDTH: usize = 3;
+pub const RATE: usize = 2;
+
+pub type PoseidonConfig<F> = Pow5Config<F, WIDTH, RATE>;
+
+// Upper bound of the rows of one `hash_assigned` of up to RATE elements: a
+// row per round plus loading the state and the message.
+pub const HASH_ROWS: usize = 8 + 56 + 4;
+
+#[derive(Debug, Clone, Copy)]
+pub struct PoseidonSpec<const WIDTH: usize, const RATE: usize>;
+
+impl<F: FieldExt, const WIDTH: usize, const RATE: usize> Spec<F, WIDTH, RATE>
+    for PoseidonSpec<WIDTH, RATE>
+{
+    fn full_rounds() -> usize {
+        8
+    }
+
+    fn partial_rounds() -> usize {
+        56
+    }
+
+    fn sbox(val: F) -> F {
+        val.pow_vartime(&[5])
+    }
+
+    fn secure_mds() -> usize {
+        0
+    }
+}
+
+// Allocate the columns for a Pow5 chip. The first round constant column
+// doubles as the constant column of the circuit.
+pub fn configure<F: FieldExt>(meta: &mut ConstraintSystem<F>) -> PoseidonConfig<F> {
+    let state = (0..WIDTH).map(|_| meta.advice_column()).collect::<Vec<_>>();
+    let partial_sbox = meta.advice_column();
+
+    let rc_a = (0..WIDTH).map(|_| meta.fixed_column()).collect::<Vec<_>>();
+    let rc_b = (0..WIDTH).map(|_| meta.fixed_column()).collect::<Vec<_>>();
+
+    meta.enable_constant(rc_b[0]);
+
+    Pow5Chip::configure::<PoseidonSpec<WIDTH, RATE>>(
+        meta,
+        state.try_into().unwrap(),
+        partial_sbox,
+        rc_a.try_into().unwrap(),
+        rc_b.try_into().unwrap(),
+    )
+}
+
+// Native hash of a fixed length message, matching `hash_assigned`.
+pub fn hash<F: FieldExt, const L: usize>(message: [F; L]) -> F {
+    poseidon::Hash::<_, PoseidonSpec<WIDTH, RATE>, ConstantLength<L>, WIDTH, RATE>::init()
+        .hash(message)
+}
+
+// Hash already assigned cells. The chip copies them into its own state
+// columns so they can come from any column with equality enabled.
+pub fn hash_assigned<F: FieldExt, const L: usize>(
+    config: &PoseidonConfig<F>,
+    mut layouter: impl Layouter<F>,
+    message: [AssignedCell<F, F>; L],
+) -> Result<AssignedCell<F, F>, Error> {
+    let chip = Pow5Chip::construct(config.clone());
+    let hasher = Hash::<_, _, PoseidonSpec<WIDTH, RATE>, ConstantLength<L>, WIDTH, RATE>::init(
+        chip,
+        layouter.namespace(|| "init"),
+    )?;
+    hasher.hash(layouter.namespace(|| "hash"), message)
+}
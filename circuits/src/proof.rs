@@ -0,0 +1,182 @@
+use crate::error::{self, ProofError, QuarryError};
+use crate::layout::InstanceLayout;
+use crate::registry::CircuitRegistry;
+use crate::transcript::{
+    Blake2bTranscript, Keccak256Transcript, PoseidonTranscript, TranscriptKind, TranscriptScheme,
+};
+use halo2_proofs::{
+    plonk::{create_proof, keygen_pk, keygen_vk, verify_proof, Circuit, ProvingKey, VerifyingKey},
+    poly::{
+        commitment::Params,
+        kzg::{
+            commitment::{KZGCommitmentScheme, ParamsKZG},
+            multiopen::{ProverGWC, VerifierGWC},
+            strategy::SingleStrategy,
+        },
+    },
+    transcript::{TranscriptReadBuffer, TranscriptWriterBuffer},
+};
+use halo2curves::bn256::{Bn256, Fr, G1Affine};
+use rand::RngCore;
+use tracing::{debug, info_span};
+
+// Generate the proving key of a circuit. The circuit is expected to be
+// without witnesses.
+pub fn keygen<C: Circuit<Fr>>(
+    params: &ParamsKZG<Bn256>,
+    circuit: &C,
+) -> Result<ProvingKey<G1Affine>, QuarryError> {
+    let _span = info_span!("keygen", k = params.k()).entered();
+
+    let vk = info_span!("keygen_vk")
+        .in_scope(|| keygen_vk(params, circuit))
+        .map_err(|err| error::from_plonk(err, ProofError::Keygen))?;
+    info_span!("keygen_pk")
+        .in_scope(|| keygen_pk(params, vk, circuit))
+        .map_err(|err| error::from_plonk(err, ProofError::Keygen))
+}
+
+// Generate the proving key of a circuit in `registry`, so a build that
+// changed the circuit fails here instead of making proofs nobody accepts.
+pub fn keygen_registered<C: Circuit<Fr>>(
+    params: &ParamsKZG<Bn256>,
+    circuit: &C,
+    registry: &CircuitRegistry,
+    layout: &InstanceLayout,
+) -> Result<ProvingKey<G1Affine>, QuarryError> {
+    let pk = keygen(params, circuit)?;
+    registry.check(layout, params.k(), pk.get_vk())?;
+    Ok(pk)
+}
+
+// Prove a single circuit with a single instance column.
+pub fn prove<C: Circuit<Fr>>(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuit: C,
+    instances: &[Fr],
+    rng: impl RngCore,
+) -> Result<Vec<u8>, QuarryError> {
+    prove_with(params, pk, circuit, instances, rng, TranscriptKind::Blake2b)
+}
+
+// Prove with a choice of transcript, which the verifier has to match.
+pub fn prove_with<C: Circuit<Fr>>(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuit: C,
+    instances: &[Fr],
+    rng: impl RngCore,
+    transcript: TranscriptKind,
+) -> Result<Vec<u8>, QuarryError> {
+    let _span = info_span!(
+        "prove",
+        k = params.k(),
+        instances = instances.len(),
+        transcript = ?transcript
+    )
+    .entered();
+
+    let proof = match transcript {
+        TranscriptKind::Blake2b => {
+            prove_with_transcript::<C, Blake2bTranscript>(params, pk, circuit, instances, rng)
+        }
+        TranscriptKind::Keccak256 => {
+            prove_with_transcript::<C, Keccak256Transcript>(params, pk, circuit, instances, rng)
+        }
+        TranscriptKind::Poseidon => {
+            prove_with_transcript::<C, PoseidonTranscript>(params, pk, circuit, instances, rng)
+        }
+    }?;
+    debug!(size = proof.len(), "created proof");
+    Ok(proof)
+}
+
+// Prove with any transcript, including ones this crate doesn't ship.
+pub fn prove_with_transcript<C: Circuit<Fr>, T: TranscriptScheme<G1Affine>>(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuit: C,
+    instances: &[Fr],
+    rng: impl RngCore,
+) -> Result<Vec<u8>, QuarryError> {
+    let mut transcript: T::Writer<Vec<u8>> = TranscriptWriterBuffer::init(vec![]);
+    create_proof::<KZGCommitmentScheme<_>, ProverGWC<_>, _, _, _, _>(
+        params,
+        pk,
+        &[circuit],
+        &[&[instances]],
+        rng,
+        &mut transcript,
+    )
+    .map_err(|err| error::from_plonk(err, ProofError::Prove))?;
+    Ok(transcript.finalize())
+}
+
+pub fn verify(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    instances: &[Fr],
+    proof: &[u8],
+) -> Result<(), QuarryError> {
+    verify_with(params, vk, instances, proof, TranscriptKind::Blake2b)
+}
+
+// Verify against a key of `registry` only.
+pub fn verify_registered(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    registry: &CircuitRegistry,
+    layout: &InstanceLayout,
+    instances: &[Fr],
+    proof: &[u8],
+) -> Result<(), QuarryError> {
+    registry.check(layout, params.k(), vk)?;
+    verify(params, vk, instances, proof)
+}
+
+pub fn verify_with(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    instances: &[Fr],
+    proof: &[u8],
+    transcript: TranscriptKind,
+) -> Result<(), QuarryError> {
+    let _span = info_span!(
+        "verify",
+        k = params.k(),
+        size = proof.len(),
+        transcript = ?transcript
+    )
+    .entered();
+
+    match transcript {
+        TranscriptKind::Blake2b => {
+            verify_with_transcript::<Blake2bTranscript>(params, vk, instances, proof)
+        }
+        TranscriptKind::Keccak256 => {
+            verify_with_transcript::<Keccak256Transcript>(params, vk, instances, proof)
+        }
+        TranscriptKind::Poseidon => {
+            verify_with_transcript::<PoseidonTranscript>(params, vk, instances, proof)
+        }
+    }
+}
+
+pub fn verify_with_transcript<T: TranscriptScheme<G1Affine>>(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    instances: &[Fr],
+    proof: &[u8],
+) -> Result<(), QuarryError> {
+    let strategy = SingleStrategy::new(params);
+    let mut transcript: T::Reader<&[u8]> = TranscriptReadBuffer::init(proof);
+    verify_proof::<_, VerifierGWC<_>, _, _, _>(
+        params,
+        vk,
+        strategy,
+        &[&[instances]],
+        &mut transcript,
+    )
+    .map_err(|err| error::from_plonk(err, |_| ProofError::Invalid))
+}
@@ -0,0 +1,375 @@
+// Commit-reveal epoch randomness, used when the beacon can't be reached.
+//
+// Every member first publishes Poseidon(seed, salt) and reveals seed and salt
+// once all commitments are in. The epoch randomness is the Poseidon chain
+// over the revealed seeds in member order. The circuit proves that the
+// published randomness is derived from seeds matching the commitments, so
+// the chain only has to check one proof instead of every reveal.
+//
+// A round can't wait on every member: once the deadline of a phase passes
+// the round is closed, and members that didn't commit or reveal in time are
+// excluded from the randomness. The public bitmap of revealed members tells
+// the chain who to treat as non-signers for the epoch. Members can still
+// bias the outcome by one bit each by choosing whether to reveal after
+// seeing the other reveals, which the exclusion makes visible but doesn't
+// prevent.
+use crate::layout::InstanceLayout;
+use crate::poseidon::{self, PoseidonConfig};
+use crate::secret::Secret;
+use crate::spec::StatementSpec;
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    plonk::{Circuit, ConstraintSystem, Error},
+};
+use halo2curves::bn256::Fr;
+use maingate::{MainGate, MainGateConfig, MainGateInstructions, RegionCtx};
+
+// The first `N` rows of the instance column hold the seed commitments, then
+// one bit per member for whether it revealed, then the randomness.
+pub const COMMITMENTS: usize = 0;
+pub const fn revealed_row(n: usize) -> usize {
+    COMMITMENTS + n
+}
+pub const fn randomness_row(n: usize) -> usize {
+    revealed_row(n) + n
+}
+
+pub fn layout(n: usize) -> InstanceLayout {
+    InstanceLayout::new("randomness", 2)
+        .field("commitments", n)
+        .field("revealed", n)
+        .field("randomness", 1)
+}
+
+pub fn spec(n: usize) -> StatementSpec {
+    let spec = StatementSpec::new(layout(n))
+        .relation(
+            "commitments",
+            "the commitment of every revealed member hashes its seed and salt",
+            &["load reveals", "commitment 0", "exclude"],
+        )
+        .relation(
+            "revealed",
+            "revealed is a bitmap with at least one member set",
+            &["load reveals", "exclude"],
+        );
+    // A single seed is the randomness itself.
+    if n > 1 {
+        spec.relation(
+            "randomness",
+            "randomness chains the seeds of the revealed members in member order",
+            &["combine 1", "chain 1"],
+        )
+    } else {
+        spec
+    }
+}
+
+pub fn seed_commitment(seed: Fr, salt: Fr) -> Fr {
+    poseidon::hash([seed, salt])
+}
+
+pub fn combine(seeds: &[Fr]) -> Fr {
+    seeds[1..]
+        .iter()
+        .fold(seeds[0], |acc, &seed| poseidon::hash([acc, seed]))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    Commit,
+    Reveal,
+    Closed,
+}
+
+// Native state of one commit-reveal round. Reveals are kept as secrets
+// since a member's own seed is held here before everyone revealed.
+#[derive(Clone, Debug)]
+pub struct CommitReveal {
+    phase: Phase,
+    commitments: Vec<Option<Fr>>,
+    reveals: Vec<Option<Secret<(Fr, Fr)>>>,
+}
+
+impl CommitReveal {
+    pub fn new(members: usize) -> Self {
+        assert!(members > 0, "commit-reveal needs at least one member");
+        Self {
+            phase: Phase::Commit,
+            commitments: vec![None; members],
+            reveals: vec![None; members],
+        }
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    // Commitments can't be replaced once made. The reveals open as soon as
+    // every member committed.
+    pub fn commit(&mut self, member: usize, commitment: Fr) -> bool {
+        if self.phase != Phase::Commit {
+            return false;
+        }
+        match self.commitments.get_mut(member) {
+            Some(slot @ None) => {
+                *slot = Some(commitment);
+                if self.commitments.iter().all(Option::is_some) {
+                    self.phase = Phase::Reveal;
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // Reveals are only accepted during the reveal phase and when they open
+    // the member's commitment.
+    pub fn reveal(&mut self, member: usize, seed: Fr, salt: Fr) -> bool {
+        if self.phase != Phase::Reveal {
+            return false;
+        }
+        match (self.commitments.get(member), self.reveals.get_mut(member)) {
+            (Some(Some(commitment)), Some(slot @ None))
+                if *commitment == seed_commitment(seed, salt) =>
+            {
+                *slot = Some(Secret::new((seed, salt)));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // Called when the deadline of the current phase passed: the commit
+    // phase moves on to the reveals without the members that didn't commit,
+    // the reveal phase closes the round without the members that didn't
+    // reveal.
+    pub fn close(&mut self) {
+        self.phase = match self.phase {
+            Phase::Commit => Phase::Reveal,
+            Phase::Reveal | Phase::Closed => Phase::Closed,
+        };
+    }
+
+    // Members that haven't revealed yet.
+    pub fn missing(&self) -> Vec<usize> {
+        self.reveals
+            .iter()
+            .enumerate()
+            .filter(|(_, reveal)| reveal.is_none())
+            .map(|(member, _)| member)
+            .collect()
+    }
+
+    // Members left out of the randomness, once the round is closed.
+    pub fn excluded(&self) -> Vec<usize> {
+        match self.phase {
+            Phase::Closed => self.missing(),
+            _ => vec![],
+        }
+    }
+
+    fn revealed(&self) -> Vec<bool> {
+        self.reveals.iter().map(Option::is_some).collect()
+    }
+
+    // Available once everyone revealed or the round closed with at least
+    // one reveal.
+    pub fn randomness(&self) -> Option<Fr> {
+        if self.phase != Phase::Closed && !self.missing().is_empty() {
+            return None;
+        }
+        let seeds = self
+            .reveals
+            .iter()
+            .flatten()
+            .map(|reveal| reveal.expose().0)
+            .collect::<Vec<_>>();
+        (!seeds.is_empty()).then(|| combine(&seeds))
+    }
+
+    // Excluded members are witnessed with a zero seed and salt, which the
+    // circuit ignores.
+    pub fn circuit<const N: usize>(&self) -> Option<RevealCircuit<N>> {
+        assert_eq!(self.reveals.len(), N);
+        self.randomness()?;
+        let reveals = self
+            .reveals
+            .iter()
+            .map(|reveal| {
+                reveal
+                    .as_ref()
+                    .map_or((Fr::zero(), Fr::zero()), |reveal| *reveal.expose())
+            })
+            .collect::<Vec<_>>();
+        Some(RevealCircuit {
+            seeds: Value::known(reveals.iter().map(|(seed, _)| *seed).collect()),
+            salts: Value::known(reveals.iter().map(|(_, salt)| *salt).collect()),
+            commitments: Value::known(self.commitments()),
+            revealed: Value::known(self.revealed()),
+        })
+    }
+
+    // Members that never committed count with a zero commitment.
+    fn commitments(&self) -> Vec<Fr> {
+        self.commitments
+            .iter()
+            .map(|commitment| commitment.unwrap_or_else(Fr::zero))
+            .collect()
+    }
+
+    pub fn instances(&self) -> Option<Vec<Fr>> {
+        let revealed = self
+            .revealed()
+            .into_iter()
+            .map(|revealed| Fr::from(revealed as u64))
+            .collect::<Vec<_>>();
+        let instances = layout(self.commitments.len())
+            .encode(&[
+                ("commitments", &self.commitments()),
+                ("revealed", &revealed),
+                ("randomness", &[self.randomness()?]),
+            ])
+            .expect("randomness instances match their layout");
+        Some(instances)
+    }
+}
+
+#[derive(Clone)]
+pub struct RevealCircuit<const N: usize> {
+    pub seeds: Value<Vec<Fr>>,
+    pub salts: Value<Vec<Fr>>,
+    pub commitments: Value<Vec<Fr>>,
+    pub revealed: Value<Vec<bool>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct RevealConfig {
+    main_gate: MainGateConfig,
+    poseidon: PoseidonConfig<Fr>,
+}
+
+impl<const N: usize> Circuit<Fr> for RevealCircuit<N> {
+    type Config = RevealConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            seeds: Value::unknown(),
+            salts: Value::unknown(),
+            commitments: Value::unknown(),
+            revealed: Value::unknown(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        RevealConfig {
+            main_gate: MainGate::<Fr>::configure(meta),
+            poseidon: poseidon::configure(meta),
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let main_gate = MainGate::<Fr>::new(config.main_gate.clone());
+
+        let reveals = layouter.assign_region(
+            || "load reveals",
+            |region| {
+                let ctx = &mut RegionCtx::new(region, 0);
+                (0..N)
+                    .map(|i| {
+                        let seed =
+                            main_gate.assign_value(ctx, self.seeds.as_ref().map(|s| s[i]))?;
+                        let salt =
+                            main_gate.assign_value(ctx, self.salts.as_ref().map(|s| s[i]))?;
+                        let commitment =
+                            main_gate.assign_value(ctx, self.commitments.as_ref().map(|c| c[i]))?;
+                        let revealed = main_gate.assign_bit(
+                            ctx,
+                            self.revealed.as_ref().map(|r| Fr::from(r[i] as u64)),
+                        )?;
+                        Ok((seed, salt, commitment, revealed))
+                    })
+                    .collect::<Result<Vec<_>, Error>>()
+            },
+        )?;
+
+        let hashes = reveals
+            .iter()
+            .enumerate()
+            .map(|(i, (seed, salt, _, _))| {
+                poseidon::hash_assigned(
+                    &config.poseidon,
+                    layouter.namespace(|| format!("commitment {}", i)),
+                    [seed.clone(), salt.clone()],
+                )
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        // Only the commitments of revealed members have to open, and the
+        // chain starts at the first revealed seed.
+        let (mut randomness, mut started) = layouter.assign_region(
+            || "exclude",
+            |region| {
+                let ctx = &mut RegionCtx::new(region, 0);
+                for ((_, _, commitment, revealed), hash) in reveals.iter().zip(hashes.iter()) {
+                    let checked = main_gate.select(ctx, hash, commitment, revealed)?;
+                    main_gate.assert_equal(ctx, &checked, commitment)?;
+                }
+                let (seed, _, _, revealed) = &reveals[0];
+                let zero = main_gate.assign_constant(ctx, Fr::zero())?;
+                let randomness = main_gate.select(ctx, seed, &zero, revealed)?;
+                Ok((randomness, revealed.clone()))
+            },
+        )?;
+
+        for (i, (seed, _, _, revealed)) in reveals.iter().enumerate().skip(1) {
+            let hash = poseidon::hash_assigned(
+                &config.poseidon,
+                layouter.namespace(|| format!("combine {}", i)),
+                [randomness.clone(), seed.clone()],
+            )?;
+            (randomness, started) = layouter.assign_region(
+                || format!("chain {}", i),
+                |region| {
+                    let ctx = &mut RegionCtx::new(region, 0);
+                    let next = main_gate.select(ctx, &hash, seed, &started)?;
+                    let randomness = main_gate.select(ctx, &next, &randomness, revealed)?;
+                    // started or revealed, both being bits
+                    let both = main_gate.mul(ctx, &started, revealed)?;
+                    let either = main_gate.add(ctx, &started, revealed)?;
+                    let started = main_gate.sub(ctx, &either, &both)?;
+                    Ok((randomness, started))
+                },
+            )?;
+        }
+
+        // Nothing to combine if nobody revealed.
+        layouter.assign_region(
+            || "any revealed",
+            |region| main_gate.assert_one(&mut RegionCtx::new(region, 0), &started),
+        )?;
+
+        for (i, (_, _, commitment, revealed)) in reveals.iter().enumerate() {
+            main_gate.expose_public(
+                layouter.namespace(|| format!("commitment {}", i)),
+                commitment.clone(),
+                COMMITMENTS + i,
+            )?;
+            main_gate.expose_public(
+                layouter.namespace(|| format!("revealed {}", i)),
+                revealed.clone(),
+                revealed_row(N) + i,
+            )?;
+        }
+        main_gate.expose_public(
+            layouter.namespace(|| "randomness"),
+            randomness,
+            randomness_row(N),
+        )
+    }
+}
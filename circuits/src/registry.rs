@@ -0,0 +1,128 @@
+// Registry of the circuits a party proves or accepts. Every circuit is
+// identified by a name and pinned by the hash of its verifying key, so two
+// parties agreeing on an ID and a version know they agree on the exact
+// circuit, and a rebuilt key that doesn't match is caught before any proof
+// is checked against it.
+use crate::error::ProofError;
+use crate::layout::InstanceLayout;
+use halo2_proofs::plonk::VerifyingKey;
+use halo2curves::bn256::G1Affine;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+pub type Fingerprint = [u8; 32];
+
+// Hash of everything the verifier depends on: the domain, the constraint
+// system and the fixed and permutation commitments.
+pub fn vk_fingerprint(vk: &VerifyingKey<G1Affine>) -> Fingerprint {
+    Sha256::digest(format!("{:?}", vk.pinned()).as_bytes()).into()
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CircuitEntry {
+    // Size of the parameters the keys were generated with.
+    pub k: u32,
+    pub fingerprint: Fingerprint,
+    // Statement versions the circuit supports, in increasing order.
+    pub versions: Vec<u32>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CircuitRegistry {
+    entries: BTreeMap<String, CircuitEntry>,
+}
+
+impl CircuitRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Register a circuit, replacing and returning any previous entry with the
+    // same ID.
+    pub fn register(
+        &mut self,
+        id: impl Into<String>,
+        k: u32,
+        vk: &VerifyingKey<G1Affine>,
+        versions: &[u32],
+    ) -> Option<CircuitEntry> {
+        self.insert(id, k, vk_fingerprint(vk), versions)
+    }
+
+    // Register a circuit by fingerprint, e.g. entries received from a peer.
+    pub fn insert(
+        &mut self,
+        id: impl Into<String>,
+        k: u32,
+        fingerprint: Fingerprint,
+        versions: &[u32],
+    ) -> Option<CircuitEntry> {
+        let mut versions = versions.to_vec();
+        versions.sort_unstable();
+        versions.dedup();
+        self.entries.insert(
+            id.into(),
+            CircuitEntry {
+                k,
+                fingerprint,
+                versions,
+            },
+        )
+    }
+
+    pub fn get(&self, id: &str) -> Option<&CircuitEntry> {
+        self.entries.get(id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &CircuitEntry)> {
+        self.entries.iter().map(|(id, entry)| (id.as_str(), entry))
+    }
+
+    // Whether a proof for `id` at `version` made with the key `fingerprint`
+    // is acceptable.
+    pub fn accepts(&self, id: &str, version: u32, fingerprint: &Fingerprint) -> bool {
+        self.entries.get(id).map_or(false, |entry| {
+            entry.fingerprint == *fingerprint && entry.versions.binary_search(&version).is_ok()
+        })
+    }
+
+    // Circuits both registries accept with the same key, each with the
+    // highest version they have in common.
+    pub fn negotiate(&self, other: &CircuitRegistry) -> Vec<(String, u32)> {
+        self.entries
+            .iter()
+            .filter_map(|(id, entry)| {
+                let theirs = other.entries.get(id)?;
+                if theirs.fingerprint != entry.fingerprint {
+                    return None;
+                }
+                entry
+                    .versions
+                    .iter()
+                    .rev()
+                    .find(|version| theirs.versions.binary_search(version).is_ok())
+                    .map(|version| (id.clone(), *version))
+            })
+            .collect()
+    }
+
+    // Fails unless `vk` at `k` is the key registered for the circuit and
+    // version of `layout`.
+    pub fn check(
+        &self,
+        layout: &InstanceLayout,
+        k: u32,
+        vk: &VerifyingKey<G1Affine>,
+    ) -> Result<(), ProofError> {
+        let registered = self.get(layout.circuit).map_or(false, |entry| entry.k == k)
+            && self.accepts(layout.circuit, layout.version, &vk_fingerprint(vk));
+        if registered {
+            Ok(())
+        } else {
+            Err(ProofError::Unregistered(format!(
+                "{} version {} at k = {}",
+                layout.circuit, layout.version, k
+            )))
+        }
+    }
+}
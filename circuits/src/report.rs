@@ -0,0 +1,266 @@
+// Summary of what a circuit constrains, for audits: its columns, every gate
+// with the degree of each constraint, the lookups, the number of copy
+// constraints, the columns and instance rows they bind, and the regions
+// synthesis assigns, with their full namespace, the rows they occupy and
+// what constrains their cells.
+// It's produced by configuring the circuit and running its floor planner
+// without witnesses against an assignment that only records, so it matches
+// what keygen sees. halo2 keeps the names of gates and constraints to
+// itself, so gates are listed in the order they were created.
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::Value,
+    plonk::{
+        Advice, Any, Assigned, Assignment, Challenge, Circuit, Column, ConstraintSystem, Error,
+        Fixed, FloorPlanner, Instance, Selector,
+    },
+};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GateReport {
+    // Degree of every constraint of the gate.
+    pub constraints: Vec<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RegionReport {
+    // Namespaces and region name, joined with '/'.
+    pub name: String,
+    pub first_row: usize,
+    pub rows: usize,
+    // Cells assigned and selectors enabled.
+    pub cells: usize,
+    pub selectors: usize,
+    // Copy constraints with a cell of the region, whichever region made them.
+    pub copies: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConstraintReport {
+    pub advice_columns: usize,
+    pub fixed_columns: usize,
+    pub instance_columns: usize,
+    pub selectors: usize,
+    pub gates: Vec<GateReport>,
+    pub lookups: usize,
+    // Columns with a cell bound by a copy constraint.
+    pub copied_columns: usize,
+    pub copies: usize,
+    // Rows of instance columns bound to a cell by a copy constraint.
+    pub instance_rows: BTreeSet<usize>,
+    pub regions: Vec<RegionReport>,
+    // Rows assigned, and the smallest k that leaves room for blinding.
+    pub rows: usize,
+    pub min_k: u32,
+}
+
+pub fn report<C: Circuit<F>, F: Field>(circuit: &C) -> Result<ConstraintReport, Error> {
+    let mut meta = ConstraintSystem::<F>::default();
+    let config = C::configure(&mut meta);
+
+    let mut recorder = Recorder::default();
+    C::FloorPlanner::synthesize(
+        &mut recorder,
+        &circuit.without_witnesses(),
+        config,
+        meta.constants().clone(),
+    )?;
+
+    let rows = recorder.rows;
+    Ok(ConstraintReport {
+        advice_columns: meta.num_advice_columns(),
+        fixed_columns: meta.num_fixed_columns(),
+        instance_columns: meta.num_instance_columns(),
+        selectors: meta.num_selectors(),
+        gates: meta
+            .gates()
+            .iter()
+            .map(|gate| GateReport {
+                constraints: gate
+                    .polynomials()
+                    .iter()
+                    .map(|polynomial| polynomial.degree())
+                    .collect(),
+            })
+            .collect(),
+        lookups: meta.lookups().len(),
+        copied_columns: recorder.copied_columns.len(),
+        copies: recorder.copies,
+        instance_rows: recorder.instance_rows,
+        regions: recorder.regions,
+        rows,
+        min_k: (rows + meta.minimum_rows())
+            .next_power_of_two()
+            .trailing_zeros(),
+    })
+}
+
+#[derive(Default)]
+struct Recorder {
+    namespaces: Vec<String>,
+    region: Option<RegionReport>,
+    regions: Vec<RegionReport>,
+    copies: usize,
+    copied_columns: HashSet<Column<Any>>,
+    instance_rows: BTreeSet<usize>,
+    rows: usize,
+    // Index in `regions` of the region assigning each cell.
+    owners: HashMap<(Column<Any>, usize), usize>,
+}
+
+impl Recorder {
+    fn touch(&mut self, row: usize) {
+        self.rows = self.rows.max(row + 1);
+        if let Some(region) = self.region.as_mut() {
+            let (first, end) = if region.cells == 0 {
+                (row, row + 1)
+            } else {
+                (
+                    region.first_row.min(row),
+                    (region.first_row + region.rows).max(row + 1),
+                )
+            };
+            region.first_row = first;
+            region.rows = end - first;
+            region.cells += 1;
+        }
+    }
+
+    fn own(&mut self, column: Column<Any>, row: usize) {
+        self.touch(row);
+        if self.region.is_some() {
+            self.owners.insert((column, row), self.regions.len());
+        }
+    }
+
+    fn region_mut(&mut self, index: usize) -> Option<&mut RegionReport> {
+        if index == self.regions.len() {
+            self.region.as_mut()
+        } else {
+            self.regions.get_mut(index)
+        }
+    }
+}
+
+impl<F: Field> Assignment<F> for Recorder {
+    fn enter_region<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        let mut name = self.namespaces.clone();
+        name.push(name_fn().into());
+        self.region = Some(RegionReport {
+            name: name.join("/"),
+            first_row: 0,
+            rows: 0,
+            cells: 0,
+            selectors: 0,
+            copies: 0,
+        });
+    }
+
+    fn exit_region(&mut self) {
+        self.regions.extend(self.region.take());
+    }
+
+    fn enable_selector<A, AR>(&mut self, _: A, _: &Selector, row: usize) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.touch(row);
+        if let Some(region) = self.region.as_mut() {
+            region.selectors += 1;
+        }
+        Ok(())
+    }
+
+    fn query_instance(&self, _: Column<Instance>, _: usize) -> Result<Value<F>, Error> {
+        Ok(Value::unknown())
+    }
+
+    fn assign_advice<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        column: Column<Advice>,
+        row: usize,
+        _: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.own(column.into(), row);
+        Ok(())
+    }
+
+    fn assign_fixed<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        column: Column<Fixed>,
+        row: usize,
+        _: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.own(column.into(), row);
+        Ok(())
+    }
+
+    fn copy(
+        &mut self,
+        left: Column<Any>,
+        left_row: usize,
+        right: Column<Any>,
+        right_row: usize,
+    ) -> Result<(), Error> {
+        let mut owners = BTreeSet::new();
+        for (column, row) in [(left, left_row), (right, right_row)] {
+            if *column.column_type() == Any::Instance {
+                self.instance_rows.insert(row);
+            }
+            owners.extend(self.owners.get(&(column, row)).copied());
+            self.copied_columns.insert(column);
+        }
+        for owner in owners {
+            if let Some(region) = self.region_mut(owner) {
+                region.copies += 1;
+            }
+        }
+        self.copies += 1;
+        Ok(())
+    }
+
+    fn fill_from_row(
+        &mut self,
+        _: Column<Fixed>,
+        _: usize,
+        _: Value<Assigned<F>>,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn get_challenge(&self, _: Challenge) -> Value<F> {
+        Value::unknown()
+    }
+
+    fn push_namespace<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.namespaces.push(name_fn().into());
+    }
+
+    fn pop_namespace(&mut self, _: Option<String>) {
+        self.namespaces.pop();
+    }
+}
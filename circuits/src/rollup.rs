@@ -0,0 +1,253 @@
+// Rollup mode: the committee attests to state transitions of a sovereign
+// rollup, each one moving the state root from `prev_state_root` to
+// `new_state_root` by applying the batch with `batch_commitment`. The
+// transition is bound by its Poseidon digest, which is both the payload and
+// the message hash the committee signs, and `TransitionCircuit` exposes the
+// three roots next to the digest so a verifier gets them without computing
+// Poseidon itself. Composed after the committee parts, `signed` wires the
+// digest to the message the committee verified the signatures on, so one
+// proof shows the committee attested to these roots.
+//
+// Attestations chain when every transition starts from the state root the
+// previous one ended at; `RollupBuilder` only attests to chaining
+// transitions and the contract accepting them enforces the same. Epochs
+// are bound by the attestation of the committee rather than the digest.
+use crate::compose::{Shared, SubCircuit, Wires};
+use crate::layout::InstanceLayout;
+use crate::payload::{self, PayloadBuilder};
+use crate::poseidon::{self, HASH_ROWS};
+use crate::spec::StatementSpec;
+use ff::PrimeField;
+use halo2_proofs::{
+    arithmetic::CurveAffine,
+    circuit::{Layouter, Value},
+    plonk::{ConstraintSystem, Error},
+};
+use halo2curves::bn256::Fr;
+use std::collections::VecDeque;
+
+// Rows of the instance column, see `layout`.
+pub const PREV_STATE_ROOT: usize = 0;
+pub const NEW_STATE_ROOT: usize = 1;
+pub const BATCH_COMMITMENT: usize = 2;
+pub const DIGEST: usize = 3;
+
+pub fn layout() -> InstanceLayout {
+    InstanceLayout::new("rollup-transition", 1)
+        .field("prev_state_root", 1)
+        .field("new_state_root", 1)
+        .field("batch_commitment", 1)
+        .field("digest", 1)
+}
+
+pub fn spec() -> StatementSpec {
+    StatementSpec::new(layout()).relation(
+        "digest",
+        "digest is the Poseidon hash of the two state roots and the batch commitment",
+        &["transition digest"],
+    )
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Transition {
+    pub prev_state_root: Fr,
+    pub new_state_root: Fr,
+    pub batch_commitment: Fr,
+}
+
+impl Transition {
+    pub fn digest(&self) -> Fr {
+        poseidon::hash([
+            self.prev_state_root,
+            self.new_state_root,
+            self.batch_commitment,
+        ])
+    }
+
+    pub fn instances(&self) -> Vec<Fr> {
+        layout()
+            .encode(&[
+                ("prev_state_root", &[self.prev_state_root]),
+                ("new_state_root", &[self.new_state_root]),
+                ("batch_commitment", &[self.batch_commitment]),
+                ("digest", &[self.digest()]),
+            ])
+            .expect("transition instances match the layout")
+    }
+}
+
+// Whether every transition starts where the previous one ended.
+pub fn is_chained(transitions: &[Transition]) -> bool {
+    transitions
+        .windows(2)
+        .all(|pair| pair[1].prev_state_root == pair[0].new_state_root)
+}
+
+// Payloads of a rollup: the digest of the next transition, in order. Batches
+// are queued with the state root they lead to, and each one is attested to
+// as a transition from the root the previous one ended at. The state root
+// only moves on `confirm`, once the attestation of the pending transition
+// was accepted, so a transition the committee failed to attest to is built
+// again for the next epoch.
+#[derive(Clone, Debug)]
+pub struct RollupBuilder {
+    state_root: Fr,
+    pending: VecDeque<(Fr, Fr)>,
+}
+
+impl RollupBuilder {
+    pub fn new(genesis_state_root: Fr) -> Self {
+        Self {
+            state_root: genesis_state_root,
+            pending: VecDeque::new(),
+        }
+    }
+
+    // State root the last confirmed transition ended at.
+    pub fn state_root(&self) -> Fr {
+        self.state_root
+    }
+
+    pub fn push_batch(&mut self, batch_commitment: Fr, new_state_root: Fr) {
+        self.pending.push_back((batch_commitment, new_state_root));
+    }
+
+    // The transition attested to next, from the current state root.
+    pub fn pending_transition(&self) -> Option<Transition> {
+        let (batch_commitment, new_state_root) = *self.pending.front()?;
+        Some(Transition {
+            prev_state_root: self.state_root,
+            new_state_root,
+            batch_commitment,
+        })
+    }
+
+    // Record that the attestation of the pending transition was accepted,
+    // moving the state root forward.
+    pub fn confirm(&mut self) -> Option<Transition> {
+        let transition = self.pending_transition()?;
+        self.pending.pop_front();
+        self.state_root = transition.new_state_root;
+        Some(transition)
+    }
+}
+
+impl PayloadBuilder for RollupBuilder {
+    fn kind(&self) -> &'static str {
+        "rollup"
+    }
+
+    fn build(&mut self, _epoch: u64) -> Option<Vec<u8>> {
+        let transition = self.pending_transition()?;
+        Some(transition.digest().to_repr().as_ref().to_vec())
+    }
+
+    // The digest itself, which is below the scalar modulus of the committee
+    // curve, so the committee circuit reduces it back to the digest.
+    fn msg_hash<C: CurveAffine>(&self, _epoch: u64, payload: &[u8]) -> C::ScalarExt {
+        payload::msg_hash::<C>(payload.try_into().expect("rollup payloads are a digest"))
+    }
+}
+
+// Exposes a transition and its digest. All of its inputs are public, so it
+// only saves the verifier the hash; compose it with parts proving something
+// about the batch, e.g. its inclusion in an MMR of batches.
+#[derive(Clone, Debug)]
+pub struct TransitionCircuit {
+    pub transition: Value<Transition>,
+    // Whether the digest is wired to the "msg hash" of the committee parts
+    // before it.
+    pub signed: bool,
+}
+
+impl TransitionCircuit {
+    pub fn new(transition: Transition) -> Self {
+        Self {
+            transition: Value::known(transition),
+            signed: false,
+        }
+    }
+
+    pub fn signed(transition: Transition) -> Self {
+        Self {
+            signed: true,
+            ..Self::new(transition)
+        }
+    }
+}
+
+impl SubCircuit<Fr> for TransitionCircuit {
+    type Config = ();
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            transition: Value::unknown(),
+            signed: self.signed,
+        }
+    }
+
+    fn configure(_: &mut ConstraintSystem<Fr>, _: &Shared<Fr>) -> Self::Config {}
+
+    fn instances(&self) -> usize {
+        layout().len()
+    }
+
+    // The roots, and a digest of three elements needs two permutations.
+    fn rows(&self) -> usize {
+        3 + 2 * HASH_ROWS
+    }
+
+    fn synthesize(
+        &self,
+        _: &Self::Config,
+        shared: &Shared<Fr>,
+        mut layouter: impl Layouter<Fr>,
+        offset: usize,
+        wires: &mut Wires<Fr>,
+    ) -> Result<(), Error> {
+        let fields = [
+            self.transition.map(|transition| transition.prev_state_root),
+            self.transition.map(|transition| transition.new_state_root),
+            self.transition
+                .map(|transition| transition.batch_commitment),
+        ];
+        let [prev, new, batch] = layouter.assign_region(
+            || "load transition",
+            |mut region| {
+                let mut assign =
+                    |row: usize| region.assign_advice(|| "root", shared.value, row, || fields[row]);
+                Ok([assign(0)?, assign(1)?, assign(2)?])
+            },
+        )?;
+
+        let digest = poseidon::hash_assigned(
+            &shared.poseidon,
+            layouter.namespace(|| "transition digest"),
+            [prev.clone(), new.clone(), batch.clone()],
+        )?;
+        if self.signed {
+            let msg_hash = wires.get("msg hash")?;
+            layouter.assign_region(
+                || "signed digest",
+                |mut region| region.constrain_equal(digest.cell(), msg_hash[0].cell()),
+            )?;
+        }
+
+        shared.expose(
+            layouter.namespace(|| "prev state root"),
+            prev,
+            offset + PREV_STATE_ROOT,
+        )?;
+        shared.expose(
+            layouter.namespace(|| "new state root"),
+            new,
+            offset + NEW_STATE_ROOT,
+        )?;
+        shared.expose(
+            layouter.namespace(|| "batch commitment"),
+            batch,
+            offset + BATCH_COMMITMENT,
+        )?;
+        shared.expose(layouter.namespace(|| "digest"), digest, offset + DIGEST)
+    }
+}
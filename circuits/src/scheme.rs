@@ -0,0 +1,231 @@
+// Signature schemes a committee can attest with. A scheme bundles native
+// signing and verification with the circuit proving a quorum of signatures
+// and the public inputs of that circuit, so the rest of the flow, from
+// collecting signatures to proving and checking instances, only deals with
+// `CommitteeScheme` and networks pick a scheme by its `NAME`. ECDSA over
+// secp256k1 is the only one implemented. BLS and MuSig2 are not: a BLS
+// aggregate needs a pairing check over BLS12-381 in the circuit, and MuSig2
+// a Schnorr verification chip, neither of which the crate has. Until one of
+// them exists the module stays private to the crate.
+use crate::committee::{self, CommitteeCircuit};
+use crate::ecdsa;
+use crate::error::CircuitError;
+use crate::layout::InstanceLayout;
+use crate::policy::QuorumPolicy;
+use crate::secret::Secret;
+use halo2_proofs::{
+    arithmetic::{CurveAffine, FieldExt},
+    plonk::Circuit,
+};
+use halo2curves::group::Curve;
+use rand::RngCore;
+
+// What a committee attests to, apart from who signed. Attestation digests
+// are native, whatever the scheme signs.
+#[derive(Clone, Debug)]
+pub struct Statement<M, N> {
+    pub policy: QuorumPolicy,
+    pub msg_hash: M,
+    pub prev_attestation: N,
+    pub epoch: u64,
+}
+
+pub trait CommitteeScheme<N: FieldExt> {
+    const NAME: &'static str;
+
+    type PublicKey: Clone;
+    type SecretKey: Copy + Default;
+    type Signature: Clone;
+    type Message: Copy;
+    type Circuit: Circuit<N>;
+
+    fn public_key(&self, sk: &Secret<Self::SecretKey>) -> Self::PublicKey;
+
+    fn sign(
+        &self,
+        sk: &Secret<Self::SecretKey>,
+        msg_hash: Self::Message,
+        rng: impl RngCore,
+    ) -> Self::Signature;
+
+    fn verify(
+        &self,
+        key: &Self::PublicKey,
+        msg_hash: Self::Message,
+        signature: &Self::Signature,
+    ) -> bool;
+
+    // Circuit proving the signatures collected for `statement`, indexed by
+    // seat.
+    fn circuit(
+        &self,
+        members: &[Self::PublicKey],
+        signatures: &[Option<Self::Signature>],
+        statement: &Statement<Self::Message, N>,
+        rng: impl RngCore,
+    ) -> Result<Self::Circuit, CircuitError>;
+
+    fn layout(&self) -> InstanceLayout;
+
+    fn instances(
+        &self,
+        members: &[Self::PublicKey],
+        active: &[bool],
+        statement: &Statement<Self::Message, N>,
+    ) -> Vec<N>;
+}
+
+// One ECDSA signature per seat, verified in `CommitteeCircuit`.
+#[derive(Clone, Copy, Debug)]
+pub struct Ecdsa<E: CurveAffine, const N_MAX: usize> {
+    pub aux_generator: E,
+    pub window_size: usize,
+}
+
+impl<E: CurveAffine, N: FieldExt, const N_MAX: usize> CommitteeScheme<N> for Ecdsa<E, N_MAX> {
+    const NAME: &'static str = "ecdsa";
+
+    type PublicKey = E;
+    type SecretKey = E::ScalarExt;
+    type Signature = (E::Scalar, E::Scalar);
+    type Message = E::Scalar;
+    type Circuit = CommitteeCircuit<E, N, N_MAX>;
+
+    fn public_key(&self, sk: &Secret<E::ScalarExt>) -> E {
+        (E::generator() * sk.expose()).to_affine()
+    }
+
+    fn sign(
+        &self,
+        sk: &Secret<E::ScalarExt>,
+        msg_hash: E::Scalar,
+        rng: impl RngCore,
+    ) -> Self::Signature {
+        ecdsa::sign::<E>(sk, msg_hash, rng)
+    }
+
+    fn verify(&self, key: &E, msg_hash: E::Scalar, signature: &Self::Signature) -> bool {
+        ecdsa::verify_signature(*key, msg_hash, *signature)
+    }
+
+    fn circuit(
+        &self,
+        members: &[E],
+        signatures: &[Option<Self::Signature>],
+        statement: &Statement<E::Scalar, N>,
+        rng: impl RngCore,
+    ) -> Result<Self::Circuit, CircuitError> {
+        CommitteeCircuit::new(
+            members,
+            signatures,
+            statement.policy.clone(),
+            statement.msg_hash,
+            statement.prev_attestation,
+            statement.epoch,
+            self.aux_generator,
+            self.window_size,
+            rng,
+        )
+    }
+
+    fn layout(&self) -> InstanceLayout {
+        committee::layout(N_MAX)
+    }
+
+    fn instances(
+        &self,
+        members: &[E],
+        active: &[bool],
+        statement: &Statement<E::Scalar, N>,
+    ) -> Vec<N> {
+        committee::instances::<E, N, N_MAX>(
+            members,
+            statement.msg_hash,
+            statement.prev_attestation,
+            active,
+            &statement.policy,
+            statement.epoch,
+        )
+    }
+}
+
+// The ECDSA scheme through the trait only. It lives here rather than in
+// tests/ since the module is private.
+#[cfg(test)]
+mod tests {
+    use super::{CommitteeScheme, Ecdsa, Statement};
+    use crate::{committee, policy::QuorumPolicy, secret::Secret};
+    use ff::Field;
+    use halo2_proofs::arithmetic::CurveAffine;
+    use halo2curves::bn256::Fr;
+    use halo2curves::secp256k1::{Fq, Secp256k1Affine};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn attest<S: CommitteeScheme<Fr>>(
+        scheme: &S,
+        secrets: &[Secret<S::SecretKey>],
+        active: &[bool],
+        statement: &Statement<S::Message, Fr>,
+        rng: &mut StdRng,
+    ) -> (Vec<S::PublicKey>, Vec<Option<S::Signature>>) {
+        let members = secrets
+            .iter()
+            .map(|sk| scheme.public_key(sk))
+            .collect::<Vec<_>>();
+        let signatures = secrets
+            .iter()
+            .zip(active)
+            .map(|(sk, active)| active.then(|| scheme.sign(sk, statement.msg_hash, &mut *rng)))
+            .collect::<Vec<_>>();
+        (members, signatures)
+    }
+
+    #[test]
+    fn ecdsa_scheme_signs_and_lays_out_instances() {
+        type E = Secp256k1Affine;
+        let mut rng = StdRng::seed_from_u64(707);
+        let scheme = Ecdsa::<E, 4> {
+            aux_generator: E::generator(),
+            window_size: 2,
+        };
+        let secrets = (0..3)
+            .map(|_| Secret::new(Fq::random(&mut rng)))
+            .collect::<Vec<_>>();
+        let active = [true, false, true];
+        let statement = Statement {
+            policy: QuorumPolicy::k_of_n(2, 0..3),
+            msg_hash: Fq::from(7),
+            prev_attestation: Fr::zero(),
+            epoch: 1,
+        };
+        let (members, signatures) = attest(&scheme, &secrets, &active, &statement, &mut rng);
+
+        for (member, signature) in members.iter().zip(signatures.iter()) {
+            if let Some(signature) = signature {
+                assert!(CommitteeScheme::<Fr>::verify(
+                    &scheme,
+                    member,
+                    statement.msg_hash,
+                    signature
+                ));
+                assert!(!CommitteeScheme::<Fr>::verify(
+                    &scheme,
+                    member,
+                    Fq::from(8),
+                    signature
+                ));
+            }
+        }
+
+        let instances = CommitteeScheme::<Fr>::instances(&scheme, &members, &active, &statement);
+        assert_eq!(
+            instances.len(),
+            CommitteeScheme::<Fr>::layout(&scheme).len()
+        );
+        assert_eq!(
+            instances[committee::SIGNERS_BITMAP],
+            committee::encode_bitmap::<Fr>(&active)
+        );
+        assert_eq!(<Ecdsa<E, 4> as CommitteeScheme<Fr>>::NAME, "ecdsa");
+    }
+}
@@ -0,0 +1,47 @@
+// Secret witness values: signing keys and nonces, and commit-reveal seeds
+// and salts before their reveal. A `Secret` is overwritten with its default
+// value when dropped, through a volatile write the compiler can't drop as a
+// dead store, and prints as `Secret(..)` so it can't end up in logs or
+// traces. Only `Copy` values are accepted since the wipe replaces the value
+// in place without running its destructor, which for a heap allocated value
+// would leave the allocation itself untouched.
+//
+// Witnesses handed to the prover are copied into halo2's own buffers, which
+// this doesn't reach; callers should drop circuits holding secrets as soon
+// as the proof is made.
+use std::fmt;
+use std::ptr;
+use std::sync::atomic::{compiler_fence, Ordering};
+
+pub struct Secret<T: Copy + Default>(T);
+
+impl<T: Copy + Default> Secret<T> {
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Copy + Default> Clone for Secret<T> {
+    fn clone(&self) -> Self {
+        Self(self.0)
+    }
+}
+
+impl<T: Copy + Default> Drop for Secret<T> {
+    fn drop(&mut self) {
+        // Safe since the pointer comes from a reference and T is Copy, so
+        // nothing is leaked by not dropping the old value.
+        unsafe { ptr::write_volatile(&mut self.0, T::default()) };
+        compiler_fence(Ordering::SeqCst);
+    }
+}
+
+impl<T: Copy + Default> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Secret(..)")
+    }
+}
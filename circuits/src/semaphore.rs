@@ -0,0 +1,304 @@
+// Group membership and nullifiers in the format used by Semaphore, so an
+// existing Semaphore group can be registered as a committee without members
+// re-issuing their identities.
+//
+// Semaphore hashes with circomlib's Poseidon, which differs from the sponge in
+// `poseidon`: the capacity element is the first word of the state and starts
+// at zero, the output is the first word after a single permutation, and the
+// number of partial rounds depends on the width. The permutation is taken
+// from the Pow5 chip directly so the state can be laid out the circomlib way.
+// The round constants and the MDS are the ones halo2_gadgets derives from the
+// Grain LFSR, which circomlib generates the same way; its MDS is the first
+// Cauchy matrix out of the stream, hence `secure_mds` of zero.
+// For reference circomlib's poseidon([1, 2]) is
+// 0x115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a.
+//
+//   secret              = poseidon([identity_nullifier, identity_trapdoor])
+//   identity_commitment = poseidon([secret])
+//   nullifier_hash      = poseidon([external_nullifier, identity_nullifier])
+//
+// and the group is a binary Merkle tree of identity commitments.
+use crate::swap::{SwapChip, SwapConfig};
+use halo2_gadgets::poseidon::{
+    primitives::Spec, PoseidonInstructions, Pow5Chip, Pow5Config, StateWord,
+};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error},
+};
+use std::convert::TryInto;
+
+// Partial rounds used by circomlib, indexed by width - 2.
+const PARTIAL_ROUNDS: [usize; 8] = [56, 57, 56, 60, 60, 63, 64, 63];
+
+#[derive(Debug, Clone, Copy)]
+pub struct SemaphoreSpec<const WIDTH: usize, const RATE: usize>;
+
+impl<F: FieldExt, const WIDTH: usize, const RATE: usize> Spec<F, WIDTH, RATE>
+    for SemaphoreSpec<WIDTH, RATE>
+{
+    fn full_rounds() -> usize {
+        8
+    }
+
+    fn partial_rounds() -> usize {
+        PARTIAL_ROUNDS[WIDTH - 2]
+    }
+
+    fn sbox(val: F) -> F {
+        val.pow_vartime(&[5])
+    }
+
+    fn secure_mds() -> usize {
+        0
+    }
+}
+
+// Native circomlib Poseidon of `RATE` inputs.
+pub fn hash<F: FieldExt, const WIDTH: usize, const RATE: usize>(message: [F; RATE]) -> F {
+    let (round_constants, mds, _) =
+        <SemaphoreSpec<WIDTH, RATE> as Spec<F, WIDTH, RATE>>::constants();
+    let half_full_rounds = <SemaphoreSpec<WIDTH, RATE> as Spec<F, WIDTH, RATE>>::full_rounds() / 2;
+    let partial_rounds = <SemaphoreSpec<WIDTH, RATE> as Spec<F, WIDTH, RATE>>::partial_rounds();
+
+    let mut state = [F::zero(); WIDTH];
+    state[1..].copy_from_slice(&message);
+    for (round, constants) in round_constants.iter().enumerate() {
+        for (word, constant) in state.iter_mut().zip(constants.iter()) {
+            *word += constant;
+        }
+        let partial = round >= half_full_rounds && round < half_full_rounds + partial_rounds;
+        for word in state.iter_mut().take(if partial { 1 } else { WIDTH }) {
+            *word = word.pow_vartime(&[5]);
+        }
+        let mut mixed = [F::zero(); WIDTH];
+        for (mixed, row) in mixed.iter_mut().zip(mds.iter()) {
+            *mixed = row
+                .iter()
+                .zip(state.iter())
+                .fold(F::zero(), |acc, (m, word)| acc + *m * word);
+        }
+        state = mixed;
+    }
+    state[0]
+}
+
+pub fn hash1<F: FieldExt>(value: F) -> F {
+    hash::<F, 2, 1>([value])
+}
+
+pub fn hash2<F: FieldExt>(left: F, right: F) -> F {
+    hash::<F, 3, 2>([left, right])
+}
+
+pub fn identity_commitment<F: FieldExt>(identity_nullifier: F, identity_trapdoor: F) -> F {
+    hash1(hash2(identity_nullifier, identity_trapdoor))
+}
+
+pub fn nullifier_hash<F: FieldExt>(external_nullifier: F, identity_nullifier: F) -> F {
+    hash2(external_nullifier, identity_nullifier)
+}
+
+// Root of a group given its identity commitments, the number of leaves must
+// be a power of two.
+pub fn group_root<F: FieldExt>(commitments: &[F]) -> F {
+    assert!(
+        commitments.len().is_power_of_two(),
+        "number of leaves must be a power of two"
+    );
+    let mut level = commitments.to_vec();
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| hash2(pair[0], pair[1]))
+            .collect();
+    }
+    level[0]
+}
+
+// Siblings and path indices of a leaf, in the order the Semaphore circuit
+// takes them: bit `i` is set when the node at level `i` is a right child.
+pub fn group_path<F: FieldExt>(commitments: &[F], mut index: usize) -> (Vec<F>, Vec<bool>) {
+    let mut level = commitments.to_vec();
+    let mut siblings = vec![];
+    let mut indices = vec![];
+    while level.len() > 1 {
+        siblings.push(level[index ^ 1]);
+        indices.push(index & 1 == 1);
+        level = level
+            .chunks(2)
+            .map(|pair| hash2(pair[0], pair[1]))
+            .collect();
+        index >>= 1;
+    }
+    (siblings, indices)
+}
+
+#[derive(Clone, Debug)]
+pub struct SemaphoreConfig<F: FieldExt> {
+    hash1: Pow5Config<F, 2, 1>,
+    hash2: Pow5Config<F, 3, 2>,
+    value: Column<Advice>,
+    swap: SwapConfig,
+}
+
+// Cells of a membership proof, for the caller to expose.
+#[derive(Clone, Debug)]
+pub struct AssignedMembership<F: FieldExt> {
+    pub root: AssignedCell<F, F>,
+    pub nullifier_hash: AssignedCell<F, F>,
+    pub external_nullifier: AssignedCell<F, F>,
+}
+
+#[derive(Clone, Debug)]
+pub struct SemaphoreChip<F: FieldExt> {
+    config: SemaphoreConfig<F>,
+}
+
+impl<F: FieldExt> SemaphoreChip<F> {
+    pub fn new(config: SemaphoreConfig<F>) -> Self {
+        Self { config }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> SemaphoreConfig<F> {
+        let value = meta.advice_column();
+        meta.enable_equality(value);
+
+        SemaphoreConfig {
+            hash1: configure_pow5::<F, 2, 1>(meta),
+            hash2: configure_pow5::<F, 3, 2>(meta),
+            value,
+            swap: SwapChip::configure(meta),
+        }
+    }
+
+    // Prove that the identity belongs to the group with the returned root
+    // and derive its nullifier hash for `external_nullifier`.
+    pub fn membership(
+        &self,
+        mut layouter: impl Layouter<F>,
+        identity_nullifier: Value<F>,
+        identity_trapdoor: Value<F>,
+        external_nullifier: Value<F>,
+        siblings: &[Value<F>],
+        path_indices: &[Value<bool>],
+    ) -> Result<AssignedMembership<F>, Error> {
+        assert_eq!(siblings.len(), path_indices.len());
+        let value = self.config.value;
+
+        let (zero, identity_nullifier, identity_trapdoor, external_nullifier) = layouter
+            .assign_region(
+                || "identity",
+                |mut region| {
+                    Ok((
+                        region.assign_advice_from_constant(|| "zero", value, 0, F::zero())?,
+                        region.assign_advice(
+                            || "identity nullifier",
+                            value,
+                            1,
+                            || identity_nullifier,
+                        )?,
+                        region.assign_advice(
+                            || "identity trapdoor",
+                            value,
+                            2,
+                            || identity_trapdoor,
+                        )?,
+                        region.assign_advice(
+                            || "external nullifier",
+                            value,
+                            3,
+                            || external_nullifier,
+                        )?,
+                    ))
+                },
+            )?;
+
+        let secret = self.hash(
+            &self.config.hash2,
+            layouter.namespace(|| "secret"),
+            &zero,
+            [identity_nullifier.clone(), identity_trapdoor],
+        )?;
+        let mut node = self.hash(
+            &self.config.hash1,
+            layouter.namespace(|| "identity commitment"),
+            &zero,
+            [secret],
+        )?;
+
+        let swap_chip = SwapChip::new(self.config.swap.clone());
+        for (level, (sibling, bit)) in siblings.iter().zip(path_indices.iter()).enumerate() {
+            let (left, right) = swap_chip.swap(
+                layouter.namespace(|| format!("swap {}", level)),
+                &node,
+                *sibling,
+                *bit,
+            )?;
+            node = self.hash(
+                &self.config.hash2,
+                layouter.namespace(|| format!("node {}", level)),
+                &zero,
+                [left, right],
+            )?;
+        }
+
+        let nullifier_hash = self.hash(
+            &self.config.hash2,
+            layouter.namespace(|| "nullifier hash"),
+            &zero,
+            [external_nullifier.clone(), identity_nullifier],
+        )?;
+
+        Ok(AssignedMembership {
+            root: node,
+            nullifier_hash,
+            external_nullifier,
+        })
+    }
+
+    // In-circuit counterpart of `hash`, `zero` is the initial capacity word.
+    fn hash<const WIDTH: usize, const RATE: usize>(
+        &self,
+        config: &Pow5Config<F, WIDTH, RATE>,
+        mut layouter: impl Layouter<F>,
+        zero: &AssignedCell<F, F>,
+        message: [AssignedCell<F, F>; RATE],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let chip = Pow5Chip::construct(config.clone());
+        let state = std::iter::once(zero.clone())
+            .chain(message)
+            .map(StateWord::from)
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap();
+        let state = <Pow5Chip<F, WIDTH, RATE> as PoseidonInstructions<
+            F,
+            SemaphoreSpec<WIDTH, RATE>,
+            WIDTH,
+            RATE,
+        >>::permute(&chip, &mut layouter, &state)?;
+        Ok(state[0].clone().into())
+    }
+}
+
+fn configure_pow5<F: FieldExt, const WIDTH: usize, const RATE: usize>(
+    meta: &mut ConstraintSystem<F>,
+) -> Pow5Config<F, WIDTH, RATE> {
+    let state = (0..WIDTH).map(|_| meta.advice_column()).collect::<Vec<_>>();
+    let partial_sbox = meta.advice_column();
+
+    let rc_a = (0..WIDTH).map(|_| meta.fixed_column()).collect::<Vec<_>>();
+    let rc_b = (0..WIDTH).map(|_| meta.fixed_column()).collect::<Vec<_>>();
+
+    meta.enable_constant(rc_b[0]);
+
+    Pow5Chip::configure::<SemaphoreSpec<WIDTH, RATE>>(
+        meta,
+        state.try_into().unwrap(),
+        partial_sbox,
+        rc_a.try_into().unwrap(),
+        rc_b.try_into().unwrap(),
+    )
+}
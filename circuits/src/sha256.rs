@@ -0,0 +1,547 @@
+// SHA-256 compression in a circuit, for the SHA-254 Merkle paths of Filecoin
+// piece commitments. Words are 4 little endian bytes and, as in `blake2b`,
+// every bitwise operation is a lookup per byte in the shared byte table of
+// `bytes`. A rotation or shift by 8q + r bits moves the bytes by q and, for
+// r > 0, rotates every byte by r and recombines neighbouring bits with
+// masks. Additions mod 2^32 are the one gate of the chip, which checks up to
+// five summands against the sum bytes and a carry.
+//
+// A compression is about 17k lookups on top of the 2^17 rows of the table,
+// which is why the module is behind the `sha256` feature.
+use crate::bytes::{self, ByteChip, ByteConfig, ByteOp, ByteTable, Constants};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, Selector},
+    poly::Rotation,
+};
+
+pub const IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+// Block words from 64 bytes, big endian as in FIPS 180-4.
+pub fn block(bytes: &[u8; 64]) -> [u32; 16] {
+    let mut w = [0; 16];
+    for (word, chunk) in w.iter_mut().zip(bytes.chunks(4)) {
+        *word = u32::from_be_bytes(chunk.try_into().unwrap());
+    }
+    w
+}
+
+// The compression function of FIPS 180-4.
+pub fn compress(h: [u32; 8], block: [u32; 16]) -> [u32; 8] {
+    let mut w = [0u32; 64];
+    w[..16].copy_from_slice(&block);
+    for t in 16..64 {
+        let s0 = w[t - 15].rotate_right(7) ^ w[t - 15].rotate_right(18) ^ (w[t - 15] >> 3);
+        let s1 = w[t - 2].rotate_right(17) ^ w[t - 2].rotate_right(19) ^ (w[t - 2] >> 10);
+        w[t] = w[t - 16]
+            .wrapping_add(s0)
+            .wrapping_add(w[t - 7])
+            .wrapping_add(s1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut hh] = h;
+    for t in 0..64 {
+        let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+        let ch = (e & f) ^ (!e & g);
+        let t1 = hh
+            .wrapping_add(s1)
+            .wrapping_add(ch)
+            .wrapping_add(K[t])
+            .wrapping_add(w[t]);
+        let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        hh = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(t1);
+        d = c;
+        c = b;
+        b = a;
+        a = t1.wrapping_add(s0).wrapping_add(maj);
+    }
+
+    let mut out = h;
+    for (word, v) in out.iter_mut().zip([a, b, c, d, e, f, g, hh]) {
+        *word = word.wrapping_add(v);
+    }
+    out
+}
+
+// A 32-bit word as little endian bytes, each known to be below 256.
+#[derive(Clone, Debug)]
+pub struct Word<F: FieldExt>(pub [AssignedCell<F, F>; 4]);
+
+impl<F: FieldExt> Word<F> {
+    fn from_bytes(bytes: Vec<AssignedCell<F, F>>) -> Self {
+        Word(bytes.try_into().expect("a word is 4 bytes"))
+    }
+
+    // The word of 4 bytes of a message, most significant first.
+    pub fn from_be_bytes(bytes: &[AssignedCell<F, F>]) -> Self {
+        Word::from_bytes(bytes.iter().rev().cloned().collect())
+    }
+
+    pub fn to_be_bytes(&self) -> Vec<AssignedCell<F, F>> {
+        self.0.iter().rev().cloned().collect()
+    }
+
+    pub fn value(&self) -> Value<u32> {
+        self.0.iter().rev().fold(Value::known(0), |word, byte| {
+            word.zip(byte.value())
+                .map(|(word, byte)| word << 8 | bytes::byte(byte) as u32)
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Sha256Config {
+    bytes: ByteConfig,
+    // Bytes of the summed words, one word per row.
+    words: [Column<Advice>; 4],
+    carry: Column<Advice>,
+    q_add: Selector,
+}
+
+#[derive(Clone, Debug)]
+pub struct Sha256Chip<F: FieldExt> {
+    config: Sha256Config,
+    bytes: ByteChip<F>,
+}
+
+// Summands of the largest addition, h + Sigma1(e) + Ch(e, f, g) + K[t] + W[t].
+const SUMMANDS: usize = 5;
+
+impl<F: FieldExt> Sha256Chip<F> {
+    pub fn new(config: Sha256Config) -> Self {
+        let bytes = ByteChip::new(config.bytes.clone());
+        Self { config, bytes }
+    }
+
+    // Like `ByteChip::configure`, `constant` is the constants column of the
+    // circuit.
+    pub fn configure(meta: &mut ConstraintSystem<F>, constant: Column<Fixed>) -> Sha256Config {
+        let bytes = ByteChip::configure(meta, constant);
+        Self::configure_bytes(meta, bytes)
+    }
+
+    pub fn configure_with(
+        meta: &mut ConstraintSystem<F>,
+        table: ByteTable,
+        constant: Column<Fixed>,
+    ) -> Sha256Config {
+        let bytes = ByteChip::configure_with(meta, table, constant);
+        Self::configure_bytes(meta, bytes)
+    }
+
+    fn configure_bytes(meta: &mut ConstraintSystem<F>, bytes: ByteConfig) -> Sha256Config {
+        let words = [(); 4].map(|_| meta.advice_column());
+        for column in words.iter() {
+            meta.enable_equality(*column);
+        }
+        let carry = meta.advice_column();
+        meta.enable_equality(carry);
+        let q_add = meta.selector();
+
+        // Rows 0 to 4 hold the summands, row 5 the sum. The sum bytes and the
+        // carry are range checked through the table, which leaves no room for
+        // a sum that wraps around the field, so the sum is the one mod 2^32.
+        meta.create_gate("word sum", |meta| {
+            let q_add = meta.query_selector(q_add);
+            let mut word = |row: i32| {
+                words
+                    .iter()
+                    .enumerate()
+                    .map(|(i, column)| {
+                        meta.query_advice(*column, Rotation(row))
+                            * Expression::Constant(F::from(1 << (8 * i)))
+                    })
+                    .reduce(|acc, byte| acc + byte)
+                    .unwrap()
+            };
+            let summands = (0..SUMMANDS as i32)
+                .map(&mut word)
+                .reduce(|acc, word| acc + word)
+                .unwrap();
+            let sum = word(SUMMANDS as i32);
+            let carry = meta.query_advice(carry, Rotation::cur());
+
+            vec![q_add * (summands - sum - carry * Expression::Constant(F::from(1 << 32)))]
+        });
+
+        Sha256Config {
+            bytes,
+            words,
+            carry,
+            q_add,
+        }
+    }
+
+    // Load the byte table, once per circuit. Circuits sharing the table of
+    // another gadget load that one instead.
+    pub fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        self.bytes.load(layouter)
+    }
+
+    pub fn byte_chip(&self) -> &ByteChip<F> {
+        &self.bytes
+    }
+
+    fn range_check(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cells: &[AssignedCell<F, F>],
+    ) -> Result<(), Error> {
+        for (i, cell) in cells.iter().enumerate() {
+            self.bytes.apply(
+                layouter.namespace(|| format!("range {}", i)),
+                ByteOp::And,
+                cell,
+                cell,
+            )?;
+        }
+        Ok(())
+    }
+
+    // Witness bytes, e.g. of a message or a Merkle path, and range check
+    // them.
+    pub fn assign_bytes(
+        &self,
+        mut layouter: impl Layouter<F>,
+        values: &[Value<u8>],
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        let cells = layouter.assign_region(
+            || "load bytes",
+            |mut region| {
+                values
+                    .iter()
+                    .enumerate()
+                    .map(|(i, value)| {
+                        region.assign_advice(
+                            || "byte",
+                            self.config.words[i % 4],
+                            i / 4,
+                            || value.map(|byte| F::from(byte as u64)),
+                        )
+                    })
+                    .collect::<Result<Vec<_>, Error>>()
+            },
+        )?;
+        self.range_check(layouter.namespace(|| "range bytes"), &cells)?;
+        Ok(cells)
+    }
+
+    // Constant words, e.g. the initial hash value or a padding block.
+    pub fn constant_words(
+        &self,
+        mut layouter: impl Layouter<F>,
+        values: &[u32],
+    ) -> Result<Vec<Word<F>>, Error> {
+        let mut constants = Constants::new();
+        values
+            .iter()
+            .map(|value| {
+                let bytes = constants.bytes(&self.bytes, &mut layouter, &value.to_le_bytes())?;
+                Ok(Word::from_bytes(bytes))
+            })
+            .collect()
+    }
+
+    fn add(&self, mut layouter: impl Layouter<F>, summands: &[&Word<F>]) -> Result<Word<F>, Error> {
+        assert!((2..=SUMMANDS).contains(&summands.len()));
+        let total = summands.iter().fold(Value::known(0u64), |total, word| {
+            total
+                .zip(word.value())
+                .map(|(total, word)| total + word as u64)
+        });
+
+        let (bytes, carry) = layouter.assign_region(
+            || "add",
+            |mut region| {
+                self.config.q_add.enable(&mut region, 0)?;
+                for (row, word) in summands.iter().enumerate() {
+                    for (byte, column) in word.0.iter().zip(self.config.words) {
+                        byte.copy_advice(|| "summand", &mut region, column, row)?;
+                    }
+                }
+                for row in summands.len()..SUMMANDS {
+                    for column in self.config.words {
+                        region.assign_advice_from_constant(|| "zero", column, row, F::zero())?;
+                    }
+                }
+                let carry = region.assign_advice(
+                    || "carry",
+                    self.config.carry,
+                    0,
+                    || total.map(|total| F::from(total >> 32)),
+                )?;
+                let bytes = self
+                    .config
+                    .words
+                    .iter()
+                    .enumerate()
+                    .map(|(i, column)| {
+                        let byte = total.map(|total| F::from((total >> (8 * i)) & 0xff));
+                        region.assign_advice(|| "sum", *column, SUMMANDS, || byte)
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
+                Ok((bytes, carry))
+            },
+        )?;
+        self.range_check(layouter.namespace(|| "range sum"), &bytes)?;
+        self.range_check(layouter.namespace(|| "range carry"), &[carry])?;
+        Ok(Word::from_bytes(bytes))
+    }
+
+    fn bitwise(
+        &self,
+        mut layouter: impl Layouter<F>,
+        op: ByteOp,
+        a: &Word<F>,
+        b: &Word<F>,
+    ) -> Result<Word<F>, Error> {
+        let bytes =
+            a.0.iter()
+                .zip(b.0.iter())
+                .enumerate()
+                .map(|(i, (a, b))| {
+                    self.bytes
+                        .apply(layouter.namespace(|| format!("byte {}", i)), op, a, b)
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+        Ok(Word::from_bytes(bytes))
+    }
+
+    fn xor3(
+        &self,
+        mut layouter: impl Layouter<F>,
+        [a, b, c]: [&Word<F>; 3],
+    ) -> Result<Word<F>, Error> {
+        let ab = self.bitwise(layouter.namespace(|| "xor"), ByteOp::Xor, a, b)?;
+        self.bitwise(layouter.namespace(|| "xor"), ByteOp::Xor, &ab, c)
+    }
+
+    // Rotate right by `n` bits, or shift when `rotate` is unset. With
+    // n = 8q + r, byte i of the result holds the top 8 - r bits of byte
+    // i + q and the low r bits of byte i + q + 1 on top. Rotating every byte
+    // right by r puts both where they belong, the masks pick them and since
+    // the bits are disjoint XOR combines them. Bytes shifted in are zero.
+    fn shift(
+        &self,
+        mut layouter: impl Layouter<F>,
+        constants: &mut Constants<F>,
+        a: &Word<F>,
+        n: usize,
+        rotate: bool,
+    ) -> Result<Word<F>, Error> {
+        let (q, r) = (n / 8, (n % 8) as u8);
+        let source = |i: usize| {
+            if rotate {
+                Some(&a.0[(i + q) % 4])
+            } else {
+                a.0.get(i + q)
+            }
+        };
+
+        let mut rotated = vec![];
+        if r > 0 {
+            let amount = constants.byte(&self.bytes, &mut layouter, r)?;
+            for i in 0..4 {
+                rotated.push(match source(i) {
+                    Some(byte) => Some(self.bytes.apply(
+                        layouter.namespace(|| format!("rotate {}", i)),
+                        ByteOp::RotateRight,
+                        byte,
+                        &amount,
+                    )?),
+                    None => None,
+                });
+            }
+        }
+
+        let mut bytes = vec![];
+        for i in 0..4 {
+            let byte = match (source(i), r) {
+                (None, _) => constants.byte(&self.bytes, &mut layouter, 0)?,
+                (Some(byte), 0) => byte.clone(),
+                (Some(_), r) => {
+                    let low_mask = constants.byte(&self.bytes, &mut layouter, 0xff >> r)?;
+                    let high_mask = constants.byte(&self.bytes, &mut layouter, !(0xff >> r))?;
+                    let mut layouter = layouter.namespace(|| format!("combine {}", i));
+                    let low = self.bytes.apply(
+                        layouter.namespace(|| "low bits"),
+                        ByteOp::And,
+                        rotated[i].as_ref().unwrap(),
+                        &low_mask,
+                    )?;
+                    let next = if rotate {
+                        rotated.get((i + 1) % 4)
+                    } else {
+                        rotated.get(i + 1)
+                    };
+                    match next.and_then(Option::as_ref) {
+                        Some(next) => {
+                            let high = self.bytes.apply(
+                                layouter.namespace(|| "high bits"),
+                                ByteOp::And,
+                                next,
+                                &high_mask,
+                            )?;
+                            self.bytes.apply(
+                                layouter.namespace(|| "combine"),
+                                ByteOp::Xor,
+                                &low,
+                                &high,
+                            )?
+                        }
+                        None => low,
+                    }
+                }
+            };
+            bytes.push(byte);
+        }
+        Ok(Word::from_bytes(bytes))
+    }
+
+    // XOR of `a` rotated right by both `rotations` and by `third.0`, or
+    // shifted by it when `third.1` is unset: the Sigma and sigma functions.
+    fn sigma(
+        &self,
+        mut layouter: impl Layouter<F>,
+        constants: &mut Constants<F>,
+        a: &Word<F>,
+        rotations: [usize; 2],
+        third: (usize, bool),
+    ) -> Result<Word<F>, Error> {
+        let x = self.shift(
+            layouter.namespace(|| "first"),
+            constants,
+            a,
+            rotations[0],
+            true,
+        )?;
+        let y = self.shift(
+            layouter.namespace(|| "second"),
+            constants,
+            a,
+            rotations[1],
+            true,
+        )?;
+        let z = self.shift(
+            layouter.namespace(|| "third"),
+            constants,
+            a,
+            third.0,
+            third.1,
+        )?;
+        self.xor3(layouter.namespace(|| "xor"), [&x, &y, &z])
+    }
+
+    // Compress `block` into the chaining value `h`, both as words.
+    pub fn compress(
+        &self,
+        mut layouter: impl Layouter<F>,
+        h: &[Word<F>],
+        block: &[Word<F>],
+    ) -> Result<Vec<Word<F>>, Error> {
+        assert_eq!(h.len(), 8, "the chaining value is 8 words");
+        assert_eq!(block.len(), 16, "a block is 16 words");
+        let mut constants = Constants::new();
+
+        let mut w = block.to_vec();
+        for t in 16..64 {
+            let mut layouter = layouter.namespace(|| format!("schedule {}", t));
+            let s0 = self.sigma(
+                layouter.namespace(|| "sigma 0"),
+                &mut constants,
+                &w[t - 15],
+                [7, 18],
+                (3, false),
+            )?;
+            let s1 = self.sigma(
+                layouter.namespace(|| "sigma 1"),
+                &mut constants,
+                &w[t - 2],
+                [17, 19],
+                (10, false),
+            )?;
+            let next = self.add(
+                layouter.namespace(|| "w"),
+                &[&w[t - 16], &s0, &w[t - 7], &s1],
+            )?;
+            w.push(next);
+        }
+
+        let mut v = h.to_vec();
+        for t in 0..64 {
+            let mut layouter = layouter.namespace(|| format!("round {}", t));
+            let [a, b, c, d, e, f, g, hh] = [0, 1, 2, 3, 4, 5, 6, 7].map(|i| &v[i]);
+
+            let s1 = self.sigma(
+                layouter.namespace(|| "Sigma 1"),
+                &mut constants,
+                e,
+                [6, 11],
+                (25, true),
+            )?;
+            let not_e = {
+                let ones = constants.byte(&self.bytes, &mut layouter, 0xff)?;
+                let ones = Word::from_bytes(vec![ones; 4]);
+                self.bitwise(layouter.namespace(|| "not e"), ByteOp::Xor, e, &ones)?
+            };
+            let ef = self.bitwise(layouter.namespace(|| "e & f"), ByteOp::And, e, f)?;
+            let not_eg = self.bitwise(layouter.namespace(|| "!e & g"), ByteOp::And, &not_e, g)?;
+            let ch = self.bitwise(layouter.namespace(|| "ch"), ByteOp::Xor, &ef, &not_eg)?;
+            let k = Word::from_bytes(constants.bytes(
+                &self.bytes,
+                &mut layouter,
+                &K[t].to_le_bytes(),
+            )?);
+            let t1 = self.add(layouter.namespace(|| "t1"), &[hh, &s1, &ch, &k, &w[t]])?;
+
+            let s0 = self.sigma(
+                layouter.namespace(|| "Sigma 0"),
+                &mut constants,
+                a,
+                [2, 13],
+                (22, true),
+            )?;
+            let ab = self.bitwise(layouter.namespace(|| "a & b"), ByteOp::And, a, b)?;
+            let ac = self.bitwise(layouter.namespace(|| "a & c"), ByteOp::And, a, c)?;
+            let bc = self.bitwise(layouter.namespace(|| "b & c"), ByteOp::And, b, c)?;
+            let maj = self.xor3(layouter.namespace(|| "maj"), [&ab, &ac, &bc])?;
+
+            let new_e = self.add(layouter.namespace(|| "e"), &[d, &t1])?;
+            let new_a = self.add(layouter.namespace(|| "a"), &[&t1, &s0, &maj])?;
+            v = vec![
+                new_a,
+                a.clone(),
+                b.clone(),
+                c.clone(),
+                new_e,
+                e.clone(),
+                f.clone(),
+                g.clone(),
+            ];
+        }
+
+        h.iter()
+            .zip(v.iter())
+            .enumerate()
+            .map(|(i, (h, v))| self.add(layouter.namespace(|| format!("output {}", i)), &[h, v]))
+            .collect()
+    }
+}
@@ -0,0 +1,135 @@
+// Statement specifications. A spec names the public inputs of a circuit,
+// through its instance layout, and the relations the circuit has to enforce,
+// each annotated with the regions implementing it. `check` holds a spec
+// against the constraint report of the circuit, so a public input the
+// circuit never copies into its witness, or a relation whose regions were
+// renamed, dropped or left without constraints, shows up as a test failure
+// instead of as a proof that verifies for any value of that input. A region
+// counts as constraining its cells when it enables a selector or a copy
+// constraint binds one of them; that a gate is the right one still takes a
+// reviewer.
+use crate::layout::InstanceLayout;
+use crate::report::ConstraintReport;
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Relation {
+    pub name: &'static str,
+    // What the relation states, for auditors.
+    pub statement: &'static str,
+    // Regions implementing the relation, matched against the names in the
+    // report, namespaces included.
+    pub regions: &'static [&'static str],
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StatementSpec {
+    pub layout: InstanceLayout,
+    pub relations: Vec<Relation>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SpecViolation {
+    // A row of the layout isn't bound to any cell by a copy constraint.
+    UnconstrainedInput {
+        field: &'static str,
+        row: usize,
+    },
+    // A region annotating a relation assigns no cell.
+    MissingRelation {
+        relation: &'static str,
+        region: &'static str,
+    },
+    // The region assigns cells but enables no selector and none of them is
+    // copied anywhere, so they are free witnesses.
+    UnconstrainedRelation {
+        relation: &'static str,
+        region: &'static str,
+    },
+}
+
+impl StatementSpec {
+    pub fn new(layout: InstanceLayout) -> Self {
+        Self {
+            layout,
+            relations: vec![],
+        }
+    }
+
+    pub fn relation(
+        mut self,
+        name: &'static str,
+        statement: &'static str,
+        regions: &'static [&'static str],
+    ) -> Self {
+        self.relations.push(Relation {
+            name,
+            statement,
+            regions,
+        });
+        self
+    }
+
+    pub fn check(&self, report: &ConstraintReport) -> Result<(), Vec<SpecViolation>> {
+        let mut violations = vec![];
+        for field in self.layout.fields() {
+            for row in field.row..field.row + field.len {
+                if !report.instance_rows.contains(&row) {
+                    violations.push(SpecViolation::UnconstrainedInput {
+                        field: field.name,
+                        row,
+                    });
+                }
+            }
+        }
+        for relation in self.relations.iter() {
+            for region in relation.regions {
+                let assigned = report
+                    .regions
+                    .iter()
+                    .filter(|report| report.cells > 0 && report.name.contains(region))
+                    .collect::<Vec<_>>();
+                if assigned.is_empty() {
+                    violations.push(SpecViolation::MissingRelation {
+                        relation: relation.name,
+                        region,
+                    });
+                } else if !assigned
+                    .iter()
+                    .any(|report| report.selectors > 0 || report.copies > 0)
+                {
+                    violations.push(SpecViolation::UnconstrainedRelation {
+                        relation: relation.name,
+                        region,
+                    });
+                }
+            }
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+}
+
+impl fmt::Display for SpecViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpecViolation::UnconstrainedInput { field, row } => {
+                write!(f, "public input {} (row {}) is unconstrained", field, row)
+            }
+            SpecViolation::MissingRelation { relation, region } => {
+                write!(f, "relation {} has no region {}", relation, region)
+            }
+            SpecViolation::UnconstrainedRelation { relation, region } => {
+                write!(
+                    f,
+                    "relation {} has nothing constraining region {}",
+                    relation, region
+                )
+            }
+        }
+    }
+}
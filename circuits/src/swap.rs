@@ -0,0 +1,135 @@
+// Conditional swap of a node with its sibling, for walking Merkle paths
+// whose direction bits are part of the witness.
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+#[derive(Clone, Debug)]
+pub struct SwapConfig {
+    // bit, current, sibling, left, right
+    columns: [Column<Advice>; 5],
+    q_swap: Selector,
+}
+
+#[derive(Clone, Debug)]
+pub struct SwapChip<F: FieldExt> {
+    config: SwapConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> SwapChip<F> {
+    pub fn new(config: SwapConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> SwapConfig {
+        let columns = [(); 5].map(|_| meta.advice_column());
+        for column in columns.iter() {
+            meta.enable_equality(*column);
+        }
+        let q_swap = meta.selector();
+
+        // left and right are current and sibling, swapped when bit is set
+        meta.create_gate("swap", |meta| {
+            let q_swap = meta.query_selector(q_swap);
+            let [bit, current, sibling, left, right] =
+                columns.map(|column| meta.query_advice(column, Rotation::cur()));
+            let one = Expression::Constant(F::one());
+
+            vec![
+                q_swap.clone() * bit.clone() * (one - bit.clone()),
+                q_swap.clone()
+                    * (current.clone() + bit.clone() * (sibling.clone() - current.clone()) - left),
+                q_swap * (sibling.clone() + bit * (current - sibling) - right),
+            ]
+        });
+
+        SwapConfig { columns, q_swap }
+    }
+
+    // Order `current` and `sibling` as the children of their parent, `bit` is
+    // set when `current` is the right child.
+    pub fn swap(
+        &self,
+        mut layouter: impl Layouter<F>,
+        current: &AssignedCell<F, F>,
+        sibling: Value<F>,
+        bit: Value<bool>,
+    ) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>), Error> {
+        let [bit_column, current_column, sibling_column, left_column, right_column] =
+            self.config.columns;
+
+        layouter.assign_region(
+            || "swap",
+            |mut region| {
+                self.config.q_swap.enable(&mut region, 0)?;
+                region.assign_advice(
+                    || "bit",
+                    bit_column,
+                    0,
+                    || bit.map(|bit| if bit { F::one() } else { F::zero() }),
+                )?;
+                current.copy_advice(|| "current", &mut region, current_column, 0)?;
+                region.assign_advice(|| "sibling", sibling_column, 0, || sibling)?;
+
+                let swapped = bit.zip(current.value().copied()).zip(sibling).map(
+                    |((bit, current), sibling)| {
+                        if bit {
+                            (sibling, current)
+                        } else {
+                            (current, sibling)
+                        }
+                    },
+                );
+                Ok((
+                    region.assign_advice(|| "left", left_column, 0, || swapped.map(|s| s.0))?,
+                    region.assign_advice(|| "right", right_column, 0, || swapped.map(|s| s.1))?,
+                ))
+            },
+        )
+    }
+
+    // `when_set` if `bit` is set, `when_unset` otherwise, for a bit that is
+    // already assigned. The left output of a swap is exactly that.
+    pub fn select(
+        &self,
+        mut layouter: impl Layouter<F>,
+        bit: &AssignedCell<F, F>,
+        when_set: &AssignedCell<F, F>,
+        when_unset: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let [bit_column, current_column, sibling_column, left_column, right_column] =
+            self.config.columns;
+
+        layouter.assign_region(
+            || "select",
+            |mut region| {
+                self.config.q_swap.enable(&mut region, 0)?;
+                bit.copy_advice(|| "bit", &mut region, bit_column, 0)?;
+                when_unset.copy_advice(|| "current", &mut region, current_column, 0)?;
+                when_set.copy_advice(|| "sibling", &mut region, sibling_column, 0)?;
+
+                let set = bit.value().map(|bit| *bit == F::one());
+                let swapped = set
+                    .zip(when_unset.value().copied())
+                    .zip(when_set.value().copied())
+                    .map(|((set, unset), set_value)| {
+                        if set {
+                            (set_value, unset)
+                        } else {
+                            (unset, set_value)
+                        }
+                    });
+                region.assign_advice(|| "right", right_column, 0, || swapped.map(|s| s.1))?;
+                region.assign_advice(|| "left", left_column, 0, || swapped.map(|s| s.0))
+            },
+        )
+    }
+}
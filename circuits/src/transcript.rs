@@ -0,0 +1,469 @@
+// Transcripts proofs can be made with. A proof only verifies with the
+// transcript it was made with, so the kind has to travel with the proof or be
+// fixed by the circuit the verifier expects. Every transcript is a
+// `TranscriptScheme`, which is what the proving functions are generic over;
+// `TranscriptKind` only names the ones shipped here so a kind can be picked
+// at runtime.
+//
+// Besides halo2's Blake2b transcript there is a Keccak256 one, for
+// verifiers where Keccak256 is cheap and Blake2b isn't. It mirrors the
+// Blake2b transcript: the same domain tag, every absorbed value prefixed
+// with its kind and points absorbed as their affine coordinates. Keccak256
+// only gives 32 bytes, so a challenge hashes the state twice with different
+// suffixes to get the 64 bytes Challenge255 reduces to a scalar. This
+// absorption is the crate's own; there is no EVM verifier that replays it,
+// and verifiers generated by other tools use transcripts of their own.
+//
+// The Poseidon transcript is for proofs verified inside another circuit,
+// where replaying Blake2b or Keccak256 would dwarf the rest of the verifier.
+// Every absorbed value is chained into the state with the crate's Poseidon
+// hash, state = Poseidon(state, value), and a challenge is the state after
+// chaining a zero. Base field coordinates don't fit a scalar, so points are
+// absorbed as the 128-bit limbs of their little-endian encoding, least
+// significant first, x before y. Nothing is tagged: the order of the values
+// is fixed by the verifying key. `TranscriptChip` does the same absorption
+// in-circuit.
+use crate::poseidon::{self, PoseidonConfig};
+use halo2_proofs::{
+    arithmetic::{Coordinates, CurveAffine, FieldExt},
+    circuit::{AssignedCell, Layouter},
+    plonk::{Advice, Column, Error},
+    transcript::{
+        Blake2bRead, Blake2bWrite, Challenge255, EncodedChallenge, Transcript, TranscriptRead,
+        TranscriptReadBuffer, TranscriptWrite, TranscriptWriterBuffer,
+    },
+};
+use halo2curves::group::{
+    ff::{Field, PrimeField},
+    GroupEncoding,
+};
+use sha3::{Digest, Keccak256};
+use std::io::{self, Read, Write};
+use std::marker::PhantomData;
+
+// The challenge encoding and the writer and reader of one transcript.
+pub trait TranscriptScheme<C: CurveAffine> {
+    type Challenge: EncodedChallenge<C>;
+    type Writer<W: Write>: TranscriptWriterBuffer<W, C, Self::Challenge>;
+    type Reader<R: Read>: TranscriptReadBuffer<R, C, Self::Challenge>;
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Blake2bTranscript;
+
+impl<C: CurveAffine> TranscriptScheme<C> for Blake2bTranscript {
+    type Challenge = Challenge255<C>;
+    type Writer<W: Write> = Blake2bWrite<W, C, Challenge255<C>>;
+    type Reader<R: Read> = Blake2bRead<R, C, Challenge255<C>>;
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Keccak256Transcript;
+
+impl<C: CurveAffine> TranscriptScheme<C> for Keccak256Transcript {
+    type Challenge = Challenge255<C>;
+    type Writer<W: Write> = Keccak256Write<W, C>;
+    type Reader<R: Read> = Keccak256Read<R, C>;
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PoseidonTranscript;
+
+impl<C: CurveAffine> TranscriptScheme<C> for PoseidonTranscript {
+    type Challenge = PoseidonChallenge<C>;
+    type Writer<W: Write> = PoseidonWrite<W, C>;
+    type Reader<R: Read> = PoseidonRead<R, C>;
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TranscriptKind {
+    #[default]
+    Blake2b,
+    Keccak256,
+    Poseidon,
+}
+
+const DOMAIN: &[u8] = b"Halo2-Transcript";
+
+fn point_at_infinity() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Other,
+        "cannot write points at infinity to the transcript",
+    )
+}
+
+fn read_point<R: Read, C: CurveAffine>(reader: &mut R) -> io::Result<C> {
+    let mut compressed = C::Repr::default();
+    reader.read_exact(compressed.as_mut())?;
+    Option::from(C::from_bytes(&compressed))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "invalid point encoding in proof"))
+}
+
+fn read_scalar<R: Read, C: CurveAffine>(reader: &mut R) -> io::Result<C::Scalar> {
+    let mut data = <C::Scalar as PrimeField>::Repr::default();
+    reader.read_exact(data.as_mut())?;
+    Option::from(C::Scalar::from_repr(data)).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::Other,
+            "invalid field element encoding in proof",
+        )
+    })
+}
+
+const PREFIX_CHALLENGE: u8 = 0;
+const PREFIX_POINT: u8 = 1;
+const PREFIX_SCALAR: u8 = 2;
+const PREFIX_CHALLENGE_LO: u8 = 10;
+const PREFIX_CHALLENGE_HI: u8 = 11;
+
+#[derive(Clone)]
+struct KeccakState(Keccak256);
+
+impl KeccakState {
+    fn new() -> Self {
+        Self(Keccak256::new_with_prefix(DOMAIN))
+    }
+
+    fn squeeze<C: CurveAffine>(&mut self) -> Challenge255<C> {
+        self.0.update([PREFIX_CHALLENGE]);
+        let mut lo = self.0.clone();
+        lo.update([PREFIX_CHALLENGE_LO]);
+        let mut hi = self.0.clone();
+        hi.update([PREFIX_CHALLENGE_HI]);
+
+        let mut wide = [0u8; 64];
+        wide[..32].copy_from_slice(&lo.finalize());
+        wide[32..].copy_from_slice(&hi.finalize());
+        Challenge255::new(&wide)
+    }
+
+    fn point<C: CurveAffine>(&mut self, point: C) -> io::Result<()> {
+        self.0.update([PREFIX_POINT]);
+        let coordinates: Coordinates<C> =
+            Option::from(point.coordinates()).ok_or_else(point_at_infinity)?;
+        self.0.update(coordinates.x().to_repr().as_ref());
+        self.0.update(coordinates.y().to_repr().as_ref());
+        Ok(())
+    }
+
+    fn scalar<C: CurveAffine>(&mut self, scalar: C::Scalar) {
+        self.0.update([PREFIX_SCALAR]);
+        self.0.update(scalar.to_repr().as_ref());
+    }
+}
+
+pub struct Keccak256Write<W: Write, C: CurveAffine> {
+    state: KeccakState,
+    writer: W,
+    _marker: PhantomData<C>,
+}
+
+impl<W: Write, C: CurveAffine> Transcript<C, Challenge255<C>> for Keccak256Write<W, C> {
+    fn squeeze_challenge(&mut self) -> Challenge255<C> {
+        self.state.squeeze()
+    }
+
+    fn common_point(&mut self, point: C) -> io::Result<()> {
+        self.state.point(point)
+    }
+
+    fn common_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
+        self.state.scalar::<C>(scalar);
+        Ok(())
+    }
+}
+
+impl<W: Write, C: CurveAffine> TranscriptWrite<C, Challenge255<C>> for Keccak256Write<W, C> {
+    fn write_point(&mut self, point: C) -> io::Result<()> {
+        self.common_point(point)?;
+        self.writer.write_all(point.to_bytes().as_ref())
+    }
+
+    fn write_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
+        self.common_scalar(scalar)?;
+        self.writer.write_all(scalar.to_repr().as_ref())
+    }
+}
+
+impl<W: Write, C: CurveAffine> TranscriptWriterBuffer<W, C, Challenge255<C>>
+    for Keccak256Write<W, C>
+{
+    fn init(writer: W) -> Self {
+        Self {
+            state: KeccakState::new(),
+            writer,
+            _marker: PhantomData,
+        }
+    }
+
+    fn finalize(self) -> W {
+        self.writer
+    }
+}
+
+pub struct Keccak256Read<R: Read, C: CurveAffine> {
+    state: KeccakState,
+    reader: R,
+    _marker: PhantomData<C>,
+}
+
+impl<R: Read, C: CurveAffine> Transcript<C, Challenge255<C>> for Keccak256Read<R, C> {
+    fn squeeze_challenge(&mut self) -> Challenge255<C> {
+        self.state.squeeze()
+    }
+
+    fn common_point(&mut self, point: C) -> io::Result<()> {
+        self.state.point(point)
+    }
+
+    fn common_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
+        self.state.scalar::<C>(scalar);
+        Ok(())
+    }
+}
+
+impl<R: Read, C: CurveAffine> TranscriptRead<C, Challenge255<C>> for Keccak256Read<R, C> {
+    fn read_point(&mut self) -> io::Result<C> {
+        let point = read_point(&mut self.reader)?;
+        self.common_point(point)?;
+        Ok(point)
+    }
+
+    fn read_scalar(&mut self) -> io::Result<C::Scalar> {
+        let scalar = read_scalar::<_, C>(&mut self.reader)?;
+        self.common_scalar(scalar)?;
+        Ok(scalar)
+    }
+}
+
+impl<R: Read, C: CurveAffine> TranscriptReadBuffer<R, C, Challenge255<C>> for Keccak256Read<R, C> {
+    fn init(reader: R) -> Self {
+        Self {
+            state: KeccakState::new(),
+            reader,
+            _marker: PhantomData,
+        }
+    }
+}
+
+// Initial state of the Poseidon transcript, "quarry".
+const POSEIDON_DOMAIN: u64 = 0x7175_6172_7279;
+
+pub const LIMB_BITS: usize = 128;
+
+// Limbs a point is absorbed as by the Poseidon transcript.
+pub fn point_limbs<C: CurveAffine>(point: C) -> io::Result<Vec<C::Scalar>> {
+    let coordinates: Coordinates<C> =
+        Option::from(point.coordinates()).ok_or_else(point_at_infinity)?;
+    Ok([coordinates.x(), coordinates.y()]
+        .into_iter()
+        .flat_map(|coordinate| {
+            coordinate
+                .to_repr()
+                .as_ref()
+                .chunks(LIMB_BITS / 8)
+                .map(|chunk| {
+                    let mut limb = [0u8; LIMB_BITS / 8];
+                    limb[..chunk.len()].copy_from_slice(chunk);
+                    C::Scalar::from_u128(u128::from_le_bytes(limb))
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect())
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct PoseidonChallenge<C: CurveAffine>(C::Scalar);
+
+impl<C: CurveAffine> EncodedChallenge<C> for PoseidonChallenge<C> {
+    type Input = C::Scalar;
+
+    fn new(challenge_input: &C::Scalar) -> Self {
+        Self(*challenge_input)
+    }
+
+    fn get_scalar(&self) -> C::Scalar {
+        self.0
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct PoseidonState<C: CurveAffine>(C::Scalar);
+
+impl<C: CurveAffine> PoseidonState<C> {
+    fn new() -> Self {
+        Self(C::Scalar::from(POSEIDON_DOMAIN))
+    }
+
+    fn absorb(&mut self, value: C::Scalar) {
+        self.0 = poseidon::hash([self.0, value]);
+    }
+
+    fn squeeze(&mut self) -> PoseidonChallenge<C> {
+        self.absorb(C::Scalar::zero());
+        PoseidonChallenge::new(&self.0)
+    }
+
+    fn point(&mut self, point: C) -> io::Result<()> {
+        for limb in point_limbs(point)? {
+            self.absorb(limb);
+        }
+        Ok(())
+    }
+}
+
+pub struct PoseidonWrite<W: Write, C: CurveAffine> {
+    state: PoseidonState<C>,
+    writer: W,
+}
+
+impl<W: Write, C: CurveAffine> Transcript<C, PoseidonChallenge<C>> for PoseidonWrite<W, C> {
+    fn squeeze_challenge(&mut self) -> PoseidonChallenge<C> {
+        self.state.squeeze()
+    }
+
+    fn common_point(&mut self, point: C) -> io::Result<()> {
+        self.state.point(point)
+    }
+
+    fn common_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
+        self.state.absorb(scalar);
+        Ok(())
+    }
+}
+
+impl<W: Write, C: CurveAffine> TranscriptWrite<C, PoseidonChallenge<C>> for PoseidonWrite<W, C> {
+    fn write_point(&mut self, point: C) -> io::Result<()> {
+        self.common_point(point)?;
+        self.writer.write_all(point.to_bytes().as_ref())
+    }
+
+    fn write_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
+        self.common_scalar(scalar)?;
+        self.writer.write_all(scalar.to_repr().as_ref())
+    }
+}
+
+impl<W: Write, C: CurveAffine> TranscriptWriterBuffer<W, C, PoseidonChallenge<C>>
+    for PoseidonWrite<W, C>
+{
+    fn init(writer: W) -> Self {
+        Self {
+            state: PoseidonState::new(),
+            writer,
+        }
+    }
+
+    fn finalize(self) -> W {
+        self.writer
+    }
+}
+
+pub struct PoseidonRead<R: Read, C: CurveAffine> {
+    state: PoseidonState<C>,
+    reader: R,
+}
+
+impl<R: Read, C: CurveAffine> Transcript<C, PoseidonChallenge<C>> for PoseidonRead<R, C> {
+    fn squeeze_challenge(&mut self) -> PoseidonChallenge<C> {
+        self.state.squeeze()
+    }
+
+    fn common_point(&mut self, point: C) -> io::Result<()> {
+        self.state.point(point)
+    }
+
+    fn common_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
+        self.state.absorb(scalar);
+        Ok(())
+    }
+}
+
+impl<R: Read, C: CurveAffine> TranscriptRead<C, PoseidonChallenge<C>> for PoseidonRead<R, C> {
+    fn read_point(&mut self) -> io::Result<C> {
+        let point = read_point(&mut self.reader)?;
+        self.common_point(point)?;
+        Ok(point)
+    }
+
+    fn read_scalar(&mut self) -> io::Result<C::Scalar> {
+        let scalar = read_scalar::<_, C>(&mut self.reader)?;
+        self.common_scalar(scalar)?;
+        Ok(scalar)
+    }
+}
+
+impl<R: Read, C: CurveAffine> TranscriptReadBuffer<R, C, PoseidonChallenge<C>>
+    for PoseidonRead<R, C>
+{
+    fn init(reader: R) -> Self {
+        Self {
+            state: PoseidonState::new(),
+            reader,
+        }
+    }
+}
+
+// In-circuit Poseidon transcript. Squeezed challenges equal the native ones
+// when the same cells are absorbed in the same order, points as the cells of
+// their `point_limbs`.
+#[derive(Clone, Debug)]
+pub struct TranscriptChip<F: FieldExt> {
+    config: PoseidonConfig<F>,
+    // Any advice column with equality enabled, to load constants.
+    value: Column<Advice>,
+    state: AssignedCell<F, F>,
+}
+
+impl<F: FieldExt> TranscriptChip<F> {
+    pub fn new(
+        config: PoseidonConfig<F>,
+        value: Column<Advice>,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<Self, Error> {
+        let state = layouter.assign_region(
+            || "transcript domain",
+            |mut region| {
+                region.assign_advice_from_constant(|| "domain", value, 0, F::from(POSEIDON_DOMAIN))
+            },
+        )?;
+        Ok(Self {
+            config,
+            value,
+            state,
+        })
+    }
+
+    pub fn absorb(
+        &mut self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+    ) -> Result<(), Error> {
+        self.state = poseidon::hash_assigned(
+            &self.config,
+            layouter.namespace(|| "absorb"),
+            [self.state.clone(), value.clone()],
+        )?;
+        Ok(())
+    }
+
+    pub fn absorb_point(
+        &mut self,
+        mut layouter: impl Layouter<F>,
+        limbs: &[AssignedCell<F, F>],
+    ) -> Result<(), Error> {
+        for (i, limb) in limbs.iter().enumerate() {
+            self.absorb(layouter.namespace(|| format!("limb {}", i)), limb)?;
+        }
+        Ok(())
+    }
+
+    pub fn squeeze(&mut self, mut layouter: impl Layouter<F>) -> Result<AssignedCell<F, F>, Error> {
+        let zero = layouter.assign_region(
+            || "load zero",
+            |mut region| region.assign_advice_from_constant(|| "zero", self.value, 0, F::zero()),
+        )?;
+        self.absorb(layouter.namespace(|| "squeeze"), &zero)?;
+        Ok(self.state.clone())
+    }
+}
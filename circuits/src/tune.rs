@@ -0,0 +1,101 @@
+// Window size of the ECC scalar multiplications. Wider windows need fewer
+// additions but larger aux tables, and where the optimum lies depends on how
+// many signatures share the tables, so instead of fixing it we time a few
+// proofs for every candidate at keygen and rank them by the median, which a
+// single slow run doesn't move. The aux tables end up in fixed columns, so
+// the fingerprint of the chosen key pins the window size: a peer that tuned
+// to a different size ends up with a different registry entry.
+use crate::error::{CircuitError, QuarryError};
+use crate::proof::{keygen, prove};
+use crate::registry::{vk_fingerprint, Fingerprint};
+use halo2_proofs::{
+    plonk::{Circuit, ProvingKey},
+    poly::kzg::commitment::ParamsKZG,
+};
+use halo2curves::bn256::{Bn256, Fr, G1Affine};
+use rand::RngCore;
+use std::ops::RangeInclusive;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, info_span};
+
+pub const WINDOW_SIZES: RangeInclusive<usize> = 2..=6;
+
+pub struct WindowChoice {
+    pub window_size: usize,
+    pub prove_time: Duration,
+    pub pk: ProvingKey<G1Affine>,
+    pub fingerprint: Fingerprint,
+}
+
+// Generate keys for `circuit(window_size)` at every size in `sizes` and keep
+// the one with the fastest median over `runs` proofs. Sizes that don't fit in
+// the parameters are skipped.
+pub fn tune_window_size<C: Circuit<Fr> + Clone>(
+    params: &ParamsKZG<Bn256>,
+    sizes: impl IntoIterator<Item = usize>,
+    runs: usize,
+    circuit: impl Fn(usize) -> C,
+    instances: &[Fr],
+    mut rng: impl RngCore,
+) -> Result<WindowChoice, QuarryError> {
+    let _span = info_span!("tune_window_size").entered();
+
+    let sizes = sizes.into_iter().collect::<Vec<_>>();
+    if sizes.is_empty() || runs == 0 {
+        return Err(CircuitError::InvalidParameters(format!(
+            "{} window sizes to try with {} runs each",
+            sizes.len(),
+            runs
+        ))
+        .into());
+    }
+
+    let mut best: Option<WindowChoice> = None;
+    let mut last_err = None;
+    for window_size in sizes {
+        let witness = circuit(window_size);
+        let pk = match keygen(params, &witness.without_witnesses()) {
+            Ok(pk) => pk,
+            Err(err @ QuarryError::Circuit(CircuitError::NotEnoughRows { .. })) => {
+                debug!(window_size, "window doesn't fit");
+                last_err = Some(err);
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
+
+        let mut times = Vec::with_capacity(runs);
+        for _ in 0..runs {
+            let start = Instant::now();
+            prove(params, &pk, witness.clone(), instances, &mut rng)?;
+            times.push(start.elapsed());
+        }
+        times.sort_unstable();
+        let prove_time = times[runs / 2];
+        debug!(window_size, ?prove_time, "timed window");
+
+        if best
+            .as_ref()
+            .map_or(true, |best| prove_time < best.prove_time)
+        {
+            best = Some(WindowChoice {
+                window_size,
+                prove_time,
+                fingerprint: vk_fingerprint(pk.get_vk()),
+                pk,
+            });
+        }
+    }
+
+    let best = best.ok_or_else(|| {
+        last_err.unwrap_or_else(|| {
+            CircuitError::InvalidParameters("no window sizes to try".to_string()).into()
+        })
+    })?;
+    info!(
+        window_size = best.window_size,
+        prove_time = ?best.prove_time,
+        "chose window size"
+    );
+    Ok(best)
+}
@@ -0,0 +1,270 @@
+// Private committee votes. Every seat casts a ballot by publishing the
+// commitment Poseidon(vote, salt) and sends the opening to the aggregator
+// only, so the votes themselves never become public. The aggregator proves
+// that the tally is over the openings of the published ballots: it exposes
+// the Merkle root over the ballots, the number of yes votes, and whether
+// the seats voting yes satisfy the committee's quorum policy, whose weights
+// and thresholds decide the outcome.
+//
+// The committee attests to the ballot root like to any other payload, which
+// is what ties each ballot to its seat. Unlike MACI there is no coordinator
+// key: the aggregator learns every vote, and a member can prove its vote to
+// a third party by handing over its opening.
+use crate::layout::InstanceLayout;
+use crate::merkle::{self, MerkleChip};
+use crate::policy::{self, QuorumPolicy};
+use crate::poseidon::{self, PoseidonConfig};
+use crate::secret::Secret;
+use crate::spec::StatementSpec;
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    plonk::{Circuit, ConstraintSystem, Error},
+};
+use halo2curves::bn256::Fr;
+use maingate::{MainGate, MainGateConfig, MainGateInstructions, RegionCtx, Term};
+
+// Rows of the instance column, see `layout`.
+pub const BALLOTS_ROOT: usize = 0;
+pub const POLICY_COMMITMENT: usize = 1;
+pub const YES: usize = 2;
+pub const PASSED: usize = 3;
+
+pub fn layout() -> InstanceLayout {
+    InstanceLayout::new("tally", 1)
+        .field("ballots_root", 1)
+        .field("policy_commitment", 1)
+        .field("yes", 1)
+        .field("passed", 1)
+}
+
+pub fn spec() -> StatementSpec {
+    StatementSpec::new(layout())
+        .relation(
+            "ballots",
+            "ballots_root is the Merkle root over Poseidon(vote, salt) of every \
+             seat, with every vote a bit",
+            &["tally", "ballot 0", "ballots root"],
+        )
+        .relation(
+            "tally",
+            "yes counts the yes votes and passed is whether the seats voting yes \
+             satisfy the policy under policy_commitment",
+            &["tally", "policy commitment"],
+        )
+}
+
+pub fn ballot_commitment(vote: bool, salt: Fr) -> Fr {
+    poseidon::hash([Fr::from(vote as u64), salt])
+}
+
+// Ballot of a seat that didn't vote, which counts as a no.
+pub fn empty_ballot() -> Fr {
+    ballot_commitment(false, Fr::zero())
+}
+
+// Ballots of one vote as the aggregator sees them. Openings are kept as
+// secrets since they are the votes.
+#[derive(Clone, Debug)]
+pub struct BallotBox {
+    ballots: Vec<Option<Fr>>,
+    openings: Vec<Option<Secret<(bool, Fr)>>>,
+}
+
+impl BallotBox {
+    // `seats` must be a power of two, like the leaves of the ballot root.
+    pub fn new(seats: usize) -> Self {
+        assert!(seats.is_power_of_two(), "seats must be a power of two");
+        Self {
+            ballots: vec![None; seats],
+            openings: vec![None; seats],
+        }
+    }
+
+    // Ballots can't be replaced once cast.
+    pub fn cast(&mut self, seat: usize, ballot: Fr) -> bool {
+        match self.ballots.get_mut(seat) {
+            Some(slot @ None) => {
+                *slot = Some(ballot);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // Openings are only accepted for the ballot of their seat.
+    pub fn open(&mut self, seat: usize, vote: bool, salt: Fr) -> bool {
+        match (self.ballots.get(seat), self.openings.get_mut(seat)) {
+            (Some(Some(ballot)), Some(slot @ None)) if *ballot == ballot_commitment(vote, salt) => {
+                *slot = Some(Secret::new((vote, salt)));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // Seats that cast a ballot but didn't open it yet. A tally can't be
+    // proved before they do, since their ballot is in the root.
+    pub fn unopened(&self) -> Vec<usize> {
+        self.ballots
+            .iter()
+            .zip(self.openings.iter())
+            .enumerate()
+            .filter(|(_, (ballot, opening))| ballot.is_some() && opening.is_none())
+            .map(|(seat, _)| seat)
+            .collect()
+    }
+
+    // Ballot and opening of every seat, empty ballots for the seats that
+    // didn't vote.
+    fn seats(&self) -> Option<Vec<(Fr, bool, Fr)>> {
+        self.ballots
+            .iter()
+            .zip(self.openings.iter())
+            .map(|(ballot, opening)| match (ballot, opening) {
+                (None, _) => Some((empty_ballot(), false, Fr::zero())),
+                (Some(ballot), Some(opening)) => {
+                    let (vote, salt) = *opening.expose();
+                    Some((*ballot, vote, salt))
+                }
+                (Some(_), None) => None,
+            })
+            .collect()
+    }
+
+    pub fn ballots_root(&self) -> Fr {
+        let ballots = self
+            .ballots
+            .iter()
+            .map(|ballot| ballot.unwrap_or_else(empty_ballot))
+            .collect::<Vec<_>>();
+        merkle::root(&ballots)
+    }
+
+    pub fn votes(&self) -> Option<Vec<bool>> {
+        Some(self.seats()?.into_iter().map(|(_, vote, _)| vote).collect())
+    }
+
+    pub fn instances(&self, policy: &QuorumPolicy) -> Option<Vec<Fr>> {
+        let votes = self.votes()?;
+        let yes = votes.iter().filter(|&&vote| vote).count();
+        let passed =
+            policy.max_seat().map_or(true, |seat| seat < votes.len()) && policy.satisfied(&votes);
+        let instances = layout()
+            .encode(&[
+                ("ballots_root", &[self.ballots_root()]),
+                ("policy_commitment", &[policy.commitment()]),
+                ("yes", &[Fr::from(yes as u64)]),
+                ("passed", &[Fr::from(passed as u64)]),
+            ])
+            .expect("tally instances match the layout");
+        Some(instances)
+    }
+
+    pub fn circuit<const SEATS: usize>(&self, policy: QuorumPolicy) -> Option<TallyCircuit<SEATS>> {
+        if self.ballots.len() != SEATS || policy.max_seat().map_or(false, |seat| seat >= SEATS) {
+            return None;
+        }
+        let seats = self.seats()?;
+        Some(TallyCircuit {
+            votes: Value::known(seats.iter().map(|(_, vote, _)| *vote).collect()),
+            salts: Value::known(seats.iter().map(|(_, _, salt)| *salt).collect()),
+            policy,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TallyCircuit<const SEATS: usize> {
+    pub votes: Value<Vec<bool>>,
+    pub salts: Value<Vec<Fr>>,
+    // Only the shape of the policy is part of the circuit, see `policy`.
+    pub policy: QuorumPolicy,
+}
+
+#[derive(Clone, Debug)]
+pub struct TallyConfig {
+    main_gate: MainGateConfig,
+    poseidon: PoseidonConfig<Fr>,
+}
+
+impl<const SEATS: usize> Circuit<Fr> for TallyCircuit<SEATS> {
+    type Config = TallyConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            votes: Value::unknown(),
+            salts: Value::unknown(),
+            policy: self.policy.clone(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        assert!(SEATS.is_power_of_two(), "SEATS must be a power of two");
+        TallyConfig {
+            main_gate: MainGate::<Fr>::configure(meta),
+            poseidon: poseidon::configure(meta),
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let main_gate = MainGate::<Fr>::new(config.main_gate.clone());
+
+        let (votes, salts, yes, passed, policy_encoding) = layouter.assign_region(
+            || "tally",
+            |region| {
+                let ctx = &mut RegionCtx::new(region, 0);
+
+                let mut votes = Vec::with_capacity(SEATS);
+                let mut salts = Vec::with_capacity(SEATS);
+                for i in 0..SEATS {
+                    let vote = self.votes.as_ref().map(|votes| Fr::from(votes[i] as u64));
+                    votes.push(main_gate.assign_bit(ctx, vote)?);
+                    salts.push(main_gate.assign_value(ctx, self.salts.as_ref().map(|s| s[i]))?);
+                }
+
+                let terms = votes
+                    .iter()
+                    .map(|vote| Term::Assigned(vote, Fr::one()))
+                    .collect::<Vec<_>>();
+                let yes = main_gate.compose(ctx, &terms, Fr::zero())?;
+                let (passed, policy_encoding) = self.policy.assign(&main_gate, ctx, &votes)?;
+
+                Ok((votes, salts, yes, passed, policy_encoding))
+            },
+        )?;
+
+        let ballots = votes
+            .into_iter()
+            .zip(salts)
+            .enumerate()
+            .map(|(i, (vote, salt))| {
+                poseidon::hash_assigned(
+                    &config.poseidon,
+                    layouter.namespace(|| format!("ballot {}", i)),
+                    [vote, salt],
+                )
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let merkle_chip = MerkleChip::new(config.poseidon.clone());
+        let root = merkle_chip.root(layouter.namespace(|| "ballots root"), &ballots)?;
+        let policy_commitment = policy::commit_assigned(
+            &config.poseidon,
+            layouter.namespace(|| "policy commitment"),
+            &policy_encoding,
+        )?;
+
+        main_gate.expose_public(layouter.namespace(|| "ballots root"), root, BALLOTS_ROOT)?;
+        main_gate.expose_public(
+            layouter.namespace(|| "policy commitment"),
+            policy_commitment,
+            POLICY_COMMITMENT,
+        )?;
+        main_gate.expose_public(layouter.namespace(|| "yes"), yes, YES)?;
+        main_gate.expose_public(layouter.namespace(|| "passed"), passed, PASSED)
+    }
+}
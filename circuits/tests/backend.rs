@@ -0,0 +1,49 @@
+// One field generic circuit through every backend, by way of the traits only.
+use halo2_proofs::poly::kzg::commitment::ParamsKZG;
+use quarry_circuits::{
+    backend::{Ipa, Kzg, Prover, Verifier},
+    ipa,
+    mmr::{self, InclusionCircuit, Mmr},
+    transcript::TranscriptKind,
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+const K: u32 = 13;
+
+fn round_trip<B: Prover + Verifier>(backend: B, params: &B::Params, rng: &mut StdRng) {
+    let mut mmr = Mmr::new();
+    for leaf in 1..=5 {
+        mmr.push(B::Field::from(leaf));
+    }
+    let leaf = B::Field::from(3);
+    let circuit = InclusionCircuit::new(&mmr.prove(2), leaf);
+    let instances = mmr::layout()
+        .encode(&[("root", &[mmr.root().unwrap()]), ("leaf", &[leaf])])
+        .unwrap();
+
+    let pk = backend.keygen(params, &circuit).unwrap();
+    let proof = backend
+        .prove(params, &pk, circuit, &instances, &mut *rng)
+        .unwrap();
+    let vk = backend.verifying_key(&pk);
+    backend.verify(params, vk, &instances, &proof).unwrap();
+
+    let mut wrong = instances;
+    wrong[mmr::LEAF] = B::Field::from(4);
+    assert!(backend.verify(params, vk, &wrong, &proof).is_err());
+}
+
+#[test]
+fn kzg_backend_round_trips() {
+    let mut rng = StdRng::seed_from_u64(690);
+    let params = ParamsKZG::setup(K, &mut rng);
+    for transcript in [TranscriptKind::Blake2b, TranscriptKind::Keccak256] {
+        round_trip(Kzg { transcript }, &params, &mut rng);
+    }
+}
+
+#[test]
+fn ipa_backend_round_trips() {
+    let mut rng = StdRng::seed_from_u64(690);
+    round_trip(Ipa, &ipa::setup(K), &mut rng);
+}
@@ -0,0 +1,84 @@
+// The Blake2b compression chip against blake2b_simd.
+use halo2_proofs::dev::MockProver;
+use halo2curves::bn256::Fr;
+use quarry_circuits::blake2b::{self, block, compress, initial_state, CompressionCircuit};
+
+const K: u32 = 18;
+
+fn digest(h: [u64; 8]) -> Vec<u8> {
+    h.iter().flat_map(|word| word.to_le_bytes()).collect()
+}
+
+fn simd(personal: &[u8; 16], message: &[u8]) -> Vec<u8> {
+    blake2b_simd::Params::new()
+        .hash_length(64)
+        .personal(personal)
+        .hash(message)
+        .as_bytes()
+        .to_vec()
+}
+
+#[test]
+fn native_compression_matches_blake2b_simd() {
+    for personal in [[0; 16], *b"Halo2-Transcript"] {
+        for message in [&b""[..], b"abc", &[0xa5u8; 128]] {
+            let h = initial_state(64, personal);
+            let out = compress(h, block(message), message.len() as u128, true);
+            assert_eq!(digest(out), simd(&personal, message));
+        }
+
+        // two blocks, the first one not the last
+        let message = (0..200u32).map(|i| i as u8).collect::<Vec<_>>();
+        let h = compress(
+            initial_state(64, personal),
+            block(&message[..128]),
+            128,
+            false,
+        );
+        let out = compress(h, block(&message[128..]), 200, true);
+        assert_eq!(digest(out), simd(&personal, &message));
+    }
+}
+
+#[test]
+fn circuit_computes_the_compression() {
+    let message = b"quarry";
+    let h = initial_state(64, *b"Halo2-Transcript");
+    let m = block(message);
+    let t = message.len() as u128;
+
+    let instances = blake2b::instances(h, m, t, true);
+    let expected = simd(b"Halo2-Transcript", message)
+        .into_iter()
+        .map(|byte| Fr::from(byte as u64))
+        .collect::<Vec<_>>();
+    assert_eq!(instances, expected);
+
+    let circuit = CompressionCircuit::new(h, m, t, true);
+    let prover = MockProver::run(K, &circuit, vec![instances]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+}
+
+#[test]
+fn wrong_outputs_are_rejected() {
+    let h = initial_state(64, [0; 16]);
+    let m = block(b"quarry");
+    let instances = blake2b::instances(h, m, 6, true);
+
+    // a flipped output bit
+    let mut tampered = instances.clone();
+    tampered[17] += Fr::from(1 << 3);
+    let circuit = CompressionCircuit::new(h, m, 6, true);
+    let prover = MockProver::run(K, &circuit, vec![tampered]).unwrap();
+    assert!(prover.verify().is_err());
+
+    // another block with the outputs of the first
+    let circuit = CompressionCircuit::new(h, block(b"quarrz"), 6, true);
+    let prover = MockProver::run(K, &circuit, vec![instances.clone()]).unwrap();
+    assert!(prover.verify().is_err());
+
+    // the same block compressed as not the last one
+    let circuit = CompressionCircuit::new(h, m, 6, false);
+    let prover = MockProver::run(K, &circuit, vec![instances]).unwrap();
+    assert!(prover.verify().is_err());
+}
@@ -0,0 +1,100 @@
+// Byte operations against the shared lookup table.
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance},
+};
+use halo2curves::bn256::Fr;
+use quarry_circuits::bytes::{ByteChip, ByteConfig, ByteOp};
+
+const K: u32 = 18;
+
+// Exposes a ^ b, a & b, a >>> 3 and, through a second chip sharing the
+// table, a ^ (a & b).
+#[derive(Clone, Default)]
+struct OpsCircuit {
+    a: Value<u64>,
+    b: Value<u64>,
+}
+
+impl Circuit<Fr> for OpsCircuit {
+    type Config = (ByteConfig, ByteConfig, Column<Advice>, Column<Instance>);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        let value = meta.advice_column();
+        meta.enable_equality(value);
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        let constant = meta.fixed_column();
+        meta.enable_constant(constant);
+
+        let bytes = ByteChip::configure(meta, constant);
+        let shared = ByteChip::configure_with(meta, bytes.table(), constant);
+        (bytes, shared, value, instance)
+    }
+
+    fn synthesize(
+        &self,
+        (bytes, shared, value, instance): Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let chip = ByteChip::new(bytes);
+        chip.load(&mut layouter)?;
+        let shared = ByteChip::new(shared);
+
+        let (a, b) = layouter.assign_region(
+            || "load bytes",
+            |mut region| {
+                let a = region.assign_advice(|| "a", value, 0, || self.a.map(Fr::from))?;
+                let b = region.assign_advice(|| "b", value, 1, || self.b.map(Fr::from))?;
+                Ok((a, b))
+            },
+        )?;
+
+        let xor = chip.apply(layouter.namespace(|| "xor"), ByteOp::Xor, &a, &b)?;
+        let and = chip.apply(layouter.namespace(|| "and"), ByteOp::And, &a, &b)?;
+        let rotated = chip.rotate_right(layouter.namespace(|| "rotate"), &a, 3)?;
+        let mixed = shared.apply(layouter.namespace(|| "mixed"), ByteOp::Xor, &a, &and)?;
+        for (row, cell) in [xor, and, rotated, mixed].iter().enumerate() {
+            layouter.constrain_instance(cell.cell(), instance, row)?;
+        }
+        Ok(())
+    }
+}
+
+fn run(a: u64, b: u64, expected: [u64; 4]) -> Result<(), Vec<halo2_proofs::dev::VerifyFailure>> {
+    let circuit = OpsCircuit {
+        a: Value::known(a),
+        b: Value::known(b),
+    };
+    let instances = expected.iter().copied().map(Fr::from).collect();
+    MockProver::run(K, &circuit, vec![instances])
+        .unwrap()
+        .verify()
+}
+
+#[test]
+fn operations_match_their_native_result() {
+    let (a, b) = (0b1011_0110u8, 0b0110_0011u8);
+    let expected = [a ^ b, a & b, a.rotate_right(3), a ^ (a & b)].map(u64::from);
+    assert_eq!(
+        expected,
+        [0b1101_0101, 0b0010_0010, 0b1101_0110, 0b1001_0100]
+    );
+    assert_eq!(run(a as u64, b as u64, expected), Ok(()));
+    assert_eq!(run(0xff, 0, [0xff, 0, 0xff, 0xff]), Ok(()));
+}
+
+#[test]
+fn wrong_results_and_wide_operands_are_rejected() {
+    let (a, b) = (0b1011_0110u64, 0b0110_0011u64);
+    // the XOR claimed as an OR
+    assert!(run(a, b, [a | b, a & b, 0b1101_0110, a ^ (a & b)]).is_err());
+    // an operand beyond a byte isn't in the table, whatever the results
+    assert!(run(256 + a, b, [a ^ b, a & b, 0b1101_0110, a ^ (a & b)]).is_err());
+}
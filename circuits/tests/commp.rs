@@ -0,0 +1,69 @@
+// Piece commitments against known Filecoin values.
+use quarry_circuits::commp::{self, fr32_pad, fr32_unpad, padded_piece_size};
+
+fn bytes(hex: &str) -> Vec<u8> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+#[test]
+fn zero_piece_of_2_kib() {
+    // baga6ea4seaqpy7usqklokfx2vxuynmupslkeutzexe2uqurdg5vhtebhxqmpqmy
+    let commp = commp::compute(&[0u8; 2032]);
+    assert_eq!(
+        commp.to_vec(),
+        bytes("fc7e928296e516faade986b28f92d44a4f24b935485223376a799027bc18f833")
+    );
+    assert_eq!(
+        commp::piece_cid(&commp),
+        bytes(
+            "0181e203922020\
+             fc7e928296e516faade986b28f92d44a4f24b935485223376a799027bc18f833"
+        )
+    );
+}
+
+#[test]
+fn smallest_zero_piece() {
+    assert_eq!(
+        commp::compute(&[]).to_vec(),
+        bytes("3731bb99ac689f66eef5973e4a94da188f4ddcae580724fc6f3fd60dfd488333")
+    );
+}
+
+#[test]
+fn fr32_padding_round_trips() {
+    // the two padding bits end every 32 byte node
+    let padded = fr32_pad(&[0xff; 127]);
+    for node in padded.chunks(32) {
+        assert_eq!(node[..31], [0xff; 31]);
+        assert_eq!(node[31], 0x3f);
+    }
+    assert_eq!(fr32_unpad(&padded), vec![0xff; 127]);
+
+    let unpadded = (0..127 * 4)
+        .map(|i| (i * 131 % 251) as u8)
+        .collect::<Vec<_>>();
+    let padded = fr32_pad(&unpadded);
+    assert_eq!(padded.len(), 128 * 4);
+    assert_eq!(fr32_unpad(&padded), unpadded);
+}
+
+#[test]
+fn padded_piece_sizes_at_power_of_two_boundaries() {
+    for (size, padded) in [
+        (0, 128),
+        (1, 128),
+        (127, 128),
+        (128, 256),
+        (254, 256),
+        (255, 512),
+        (2032, 2048),
+        (2033, 4096),
+        (4064, 4096),
+    ] {
+        assert_eq!(padded_piece_size(size), padded, "size {}", size);
+    }
+}
@@ -0,0 +1,170 @@
+// Declared rows of the parts against what they assign, and the committee as
+// a composite of its parts.
+use ff::Field;
+use halo2_proofs::{arithmetic::CurveAffine, dev::MockProver};
+use halo2curves::bn256::Fr;
+use halo2curves::group::Curve;
+use halo2curves::secp256k1::{Fq, Secp256k1Affine};
+use quarry_circuits::{
+    committee::{self, CommitteeCircuit},
+    compose::{Composite, SubCircuit},
+    ecdsa,
+    mmr::{self, InclusionCircuit, Mmr},
+    payload::{self, PayloadBuilder},
+    policy::QuorumPolicy,
+    report::{report, ConstraintReport},
+    rollup::{RollupBuilder, Transition, TransitionCircuit},
+    secret::Secret,
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+type E = Secp256k1Affine;
+const N_MAX: usize = 2;
+
+// Rows taken by the regions of the part at `index` of a composite.
+fn part_rows(report: &ConstraintReport, index: usize) -> usize {
+    let prefix = format!("part {}/", index);
+    report
+        .regions
+        .iter()
+        .filter(|region| region.name.contains(&prefix))
+        .map(|region| region.rows)
+        .sum()
+}
+
+fn committee(rng: &mut StdRng, msg_hash: Fq) -> (CommitteeCircuit<E, Fr, N_MAX>, Vec<Fr>) {
+    let secrets = (0..N_MAX)
+        .map(|_| Secret::new(Fq::random(&mut *rng)))
+        .collect::<Vec<_>>();
+    let members = secrets
+        .iter()
+        .map(|sk| (E::generator() * sk.expose()).to_affine())
+        .collect::<Vec<_>>();
+    let active = [true, false];
+    let signatures = secrets
+        .iter()
+        .zip(active)
+        .map(|(sk, active)| active.then(|| ecdsa::sign::<E>(sk, msg_hash, &mut *rng)))
+        .collect::<Vec<_>>();
+    let policy = QuorumPolicy::k_of_n(1, 0..N_MAX);
+    let instances =
+        committee::instances::<E, Fr, N_MAX>(&members, msg_hash, Fr::zero(), &active, &policy, 1);
+    let aux_generator = (E::generator() * Fq::random(&mut *rng)).to_affine();
+    let circuit = CommitteeCircuit::<E, Fr, N_MAX>::new(
+        &members,
+        &signatures,
+        policy,
+        msg_hash,
+        Fr::zero(),
+        1,
+        aux_generator,
+        2,
+        &mut *rng,
+    )
+    .unwrap();
+    (circuit, instances)
+}
+
+#[test]
+fn committee_parts_stay_within_their_rows() {
+    let (circuit, _) = committee(&mut StdRng::seed_from_u64(652), Fq::from(7));
+    let parts = circuit.parts();
+    let report = report::<_, Fr>(&circuit).unwrap();
+
+    assert_eq!(
+        SubCircuit::<Fr>::instances(&parts),
+        committee::layout(N_MAX).len()
+    );
+    assert!(report.rows <= SubCircuit::<Fr>::rows(&parts));
+    assert!(Composite(parts.clone()).min_k::<Fr>() >= report.min_k);
+
+    // The signature part measures itself, the others take wires and are
+    // counted in the composite.
+    let signatures = report::<_, Fr>(&Composite(parts.0.clone())).unwrap();
+    assert!(signatures.rows <= SubCircuit::<Fr>::rows(&parts.0));
+    assert!(part_rows(&report, 1) <= SubCircuit::<Fr>::rows(&parts.1));
+    assert!(part_rows(&report, 2) <= SubCircuit::<Fr>::rows(&parts.2));
+    assert!(part_rows(&report, 3) <= SubCircuit::<Fr>::rows(&parts.3));
+}
+
+#[test]
+fn committee_proves_as_a_composite() {
+    let (circuit, instances) = committee(&mut StdRng::seed_from_u64(652), Fq::from(7));
+    let k = report::<_, Fr>(&circuit).unwrap().min_k;
+
+    let prover = MockProver::run(k, &circuit, vec![instances.clone()]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    // the bitmap is wired from the signatures into the attestation digest
+    let mut tampered = instances;
+    tampered[committee::SIGNERS_BITMAP] = Fr::from(3);
+    let prover = MockProver::run(k, &circuit, vec![tampered]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn parts_taking_wires_need_the_parts_putting_them() {
+    let (circuit, _) = committee(&mut StdRng::seed_from_u64(652), Fq::from(7));
+    let (signatures, members, _, _) = circuit.parts();
+    assert!(report::<_, Fr>(&Composite((signatures.clone(), members.clone()))).is_ok());
+    assert!(report::<_, Fr>(&Composite((members, signatures))).is_err());
+}
+
+#[test]
+fn signed_transitions_are_wired_to_the_committee_message() {
+    let mut builder = RollupBuilder::new(Fr::from(1));
+    builder.push_batch(Fr::from(3), Fr::from(2));
+    let (payload, msg_hash) = payload::build::<E>(&mut builder, 1).unwrap();
+    assert_eq!(builder.build(2), Some(payload));
+    let transition = builder.pending_transition().unwrap();
+    let (circuit, committee_instances) = committee(&mut StdRng::seed_from_u64(686), msg_hash);
+
+    let signed = |transition: Transition| {
+        let composite = Composite((circuit.parts(), TransitionCircuit::signed(transition)));
+        let mut instances = committee_instances.clone();
+        instances.extend(transition.instances());
+        let k = report::<_, Fr>(&composite).unwrap().min_k;
+        MockProver::run(k, &composite, vec![instances])
+            .unwrap()
+            .verify()
+    };
+    assert_eq!(signed(transition), Ok(()));
+    // a transition the committee didn't sign
+    assert!(signed(Transition {
+        new_state_root: Fr::from(4),
+        ..transition
+    })
+    .is_err());
+
+    // the state root only moves once the attestation is confirmed
+    assert_eq!(builder.state_root(), Fr::from(1));
+    assert_eq!(builder.confirm(), Some(transition));
+    assert_eq!(builder.state_root(), Fr::from(2));
+    assert_eq!(builder.build(2), None);
+}
+
+#[test]
+fn small_parts_prove_at_their_declared_k() {
+    let mut mmr = Mmr::new();
+    for leaf in 1..=5 {
+        mmr.push(Fr::from(leaf));
+    }
+    let inclusion = InclusionCircuit::new(&mmr.prove(2), Fr::from(3));
+    let transition = Transition {
+        prev_state_root: Fr::from(1),
+        new_state_root: Fr::from(2),
+        batch_commitment: Fr::from(3),
+    };
+    let parts = (inclusion.clone(), TransitionCircuit::new(transition));
+
+    assert!(report(&Composite(parts.0.clone())).unwrap().rows <= parts.0.rows());
+    assert!(report(&Composite(parts.1.clone())).unwrap().rows <= parts.1.rows());
+
+    let mut instances = mmr::layout()
+        .encode(&[("root", &[mmr.root().unwrap()]), ("leaf", &[Fr::from(3)])])
+        .unwrap();
+    instances.extend(transition.instances());
+    let composite = Composite(parts);
+    let prover = MockProver::run(composite.min_k::<Fr>(), &composite, vec![instances]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+}
@@ -0,0 +1,239 @@
+// Shape of every production circuit against the baseline in
+// `constraints.baseline`, so a change or a dependency bump that makes the
+// prover slower or needs a larger k fails here instead of in production.
+// Rows may grow by `ROW_TOLERANCE_PERCENT`, columns and k not at all. After an
+// intended change, rerun with QUARRY_BLESS=1 to rewrite the baseline and
+// commit it with the change. A circuit missing from the baseline fails as
+// well, so a new circuit lands with its recorded shape. The test is ignored
+// until the first baseline is committed:
+//
+//     QUARRY_BLESS=1 cargo test --test constraints -- --ignored
+use halo2_proofs::{arithmetic::CurveAffine, circuit::Value};
+use halo2curves::bn256::Fr;
+use halo2curves::secp256k1::Secp256k1Affine;
+use quarry_circuits::{
+    committee::CommitteeCircuit,
+    compose::Composite,
+    erasure::EncodingCircuit,
+    mmr::{InclusionCircuit, Mmr},
+    oracle::OracleCircuit,
+    policy::QuorumPolicy,
+    randomness::RevealCircuit,
+    report::{report, ConstraintReport},
+    rollup::{Transition, TransitionCircuit},
+    vote::TallyCircuit,
+};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+const ROW_TOLERANCE_PERCENT: usize = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Shape {
+    rows: usize,
+    min_k: u32,
+    advice_columns: usize,
+    fixed_columns: usize,
+    lookups: usize,
+}
+
+impl Shape {
+    fn of(report: &ConstraintReport) -> Self {
+        Self {
+            rows: report.rows,
+            min_k: report.min_k,
+            advice_columns: report.advice_columns,
+            fixed_columns: report.fixed_columns,
+            lookups: report.lookups,
+        }
+    }
+
+    fn encode(&self) -> String {
+        format!(
+            "rows={} min_k={} advice={} fixed={} lookups={}",
+            self.rows, self.min_k, self.advice_columns, self.fixed_columns, self.lookups
+        )
+    }
+
+    fn decode(fields: &str) -> Option<Self> {
+        let fields = fields
+            .split_whitespace()
+            .map(|field| field.split_once('='))
+            .collect::<Option<BTreeMap<_, _>>>()?;
+        let get = |name: &str| fields.get(name)?.parse::<usize>().ok();
+        Some(Self {
+            rows: get("rows")?,
+            min_k: get("min_k")? as u32,
+            advice_columns: get("advice")?,
+            fixed_columns: get("fixed")?,
+            lookups: get("lookups")?,
+        })
+    }
+
+    // What got worse than `baseline` allows.
+    fn regressions(&self, baseline: &Shape) -> Vec<String> {
+        let mut regressions = vec![];
+        let allowed = baseline.rows + baseline.rows * ROW_TOLERANCE_PERCENT / 100;
+        if self.rows > allowed {
+            regressions.push(format!("rows {} -> {}", baseline.rows, self.rows));
+        }
+        for (name, before, after) in [
+            ("min_k", baseline.min_k as usize, self.min_k as usize),
+            ("advice", baseline.advice_columns, self.advice_columns),
+            ("fixed", baseline.fixed_columns, self.fixed_columns),
+            ("lookups", baseline.lookups, self.lookups),
+        ] {
+            if after > before {
+                regressions.push(format!("{} {} -> {}", name, before, after));
+            }
+        }
+        regressions
+    }
+}
+
+fn baseline_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("constraints.baseline")
+}
+
+fn read_baseline() -> BTreeMap<String, Shape> {
+    let baseline = match fs::read_to_string(baseline_path()) {
+        Ok(baseline) => baseline,
+        Err(_) => return BTreeMap::new(),
+    };
+    baseline
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let (name, fields) = line.split_once(' ').expect("baseline line has a name");
+            let shape = Shape::decode(fields).expect("baseline line has every field");
+            (name.to_string(), shape)
+        })
+        .collect()
+}
+
+fn write_baseline(shapes: &BTreeMap<String, Shape>) {
+    let mut baseline = "# Written by QUARRY_BLESS=1 cargo test --test constraints\n".to_string();
+    for (name, shape) in shapes {
+        baseline.push_str(&format!("{} {}\n", name, shape.encode()));
+    }
+    fs::write(baseline_path(), baseline).unwrap();
+}
+
+// The circuits at the sizes they are deployed with in the tests.
+fn shapes() -> BTreeMap<String, Shape> {
+    type E = Secp256k1Affine;
+    let committee = CommitteeCircuit::<E, Fr, 4> {
+        members: vec![Value::unknown(); 4],
+        signatures: vec![Value::unknown(); 4],
+        active: vec![Value::unknown(); 4],
+        policy: QuorumPolicy::k_of_n(2, 0..3),
+        msg_hash: Value::unknown(),
+        prev_attestation: Value::unknown(),
+        epoch: 1,
+        aux_generator: E::generator(),
+        window_size: 2,
+    };
+    let oracle = OracleCircuit::<E, 3> {
+        members: vec![Value::unknown(); 3],
+        values: vec![Value::unknown(); 3],
+        signatures: vec![Value::unknown(); 3],
+        epoch: 1,
+        aux_generator: E::generator(),
+        window_size: 2,
+    };
+    let randomness = RevealCircuit::<4> {
+        seeds: Value::unknown(),
+        salts: Value::unknown(),
+        commitments: Value::unknown(),
+        revealed: Value::unknown(),
+    };
+    let mut mmr = Mmr::new();
+    for leaf in 1..=5 {
+        mmr.push(Fr::from(leaf));
+    }
+    let inclusion = InclusionCircuit::new(&mmr.prove(2), Fr::from(3));
+    let transition = Composite(TransitionCircuit::new(Transition {
+        prev_state_root: Fr::from(1),
+        new_state_root: Fr::from(2),
+        batch_commitment: Fr::from(3),
+    }));
+
+    let encoding = EncodingCircuit::<4, 8> {
+        data: Value::unknown(),
+    };
+    let tally = TallyCircuit::<4> {
+        votes: Value::unknown(),
+        salts: Value::unknown(),
+        policy: QuorumPolicy::weighted(3, &[3, 1, 1, 1]),
+    };
+
+    [
+        ("committee", report::<_, Fr>(&committee)),
+        ("oracle", report::<_, Fr>(&oracle)),
+        ("randomness", report(&randomness)),
+        ("mmr-inclusion", report(&inclusion)),
+        ("rollup-transition", report(&transition)),
+        ("erasure-encoding", report(&encoding)),
+        ("tally", report(&tally)),
+    ]
+    .into_iter()
+    .map(|(name, report)| (name.to_string(), Shape::of(&report.unwrap())))
+    .collect()
+}
+
+#[test]
+#[ignore = "constraints.baseline is not recorded yet, record it with QUARRY_BLESS=1 and --ignored"]
+fn circuits_stay_within_baseline() {
+    let shapes = shapes();
+    if env::var_os("QUARRY_BLESS").is_some() {
+        write_baseline(&shapes);
+        return;
+    }
+
+    let baseline = read_baseline();
+    let mut regressions = vec![];
+    for (name, shape) in shapes.iter() {
+        match baseline.get(name) {
+            Some(before) => regressions.extend(
+                shape
+                    .regressions(before)
+                    .into_iter()
+                    .map(|regression| format!("{}: {}", name, regression)),
+            ),
+            None => regressions.push(format!("{}: no baseline, {}", name, shape.encode())),
+        }
+    }
+    assert!(
+        regressions.is_empty(),
+        "constraint regressions, rerun with QUARRY_BLESS=1 if intended: {}",
+        regressions.join(", ")
+    );
+}
+
+#[test]
+fn row_growth_within_tolerance_passes() {
+    let baseline = Shape {
+        rows: 1000,
+        min_k: 11,
+        advice_columns: 5,
+        fixed_columns: 3,
+        lookups: 0,
+    };
+    let within = Shape {
+        rows: 1020,
+        ..baseline
+    };
+    let beyond = Shape {
+        rows: 1021,
+        advice_columns: 6,
+        ..baseline
+    };
+    assert!(within.regressions(&baseline).is_empty());
+    assert_eq!(
+        beyond.regressions(&baseline),
+        vec!["rows 1000 -> 1021".to_string(), "advice 5 -> 6".to_string()]
+    );
+    assert_eq!(Shape::decode(&baseline.encode()), Some(baseline));
+}
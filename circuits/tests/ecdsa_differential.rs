@@ -0,0 +1,195 @@
+// Differential test of EcdsaChip against k256: signatures made by k256, as
+// well as tampered ones, must be accepted by the circuit exactly when k256
+// accepts them. k256 only accepts the low-s form of a signature while the
+// circuit accepts both, so the reference accepts one iff k256 accepts it
+// with s or -s.
+use ecc::GeneralEccChip;
+use ff::{Field, PrimeField};
+use halo2_proofs::{
+    arithmetic::CurveAffine,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Circuit, ConstraintSystem, Error},
+};
+use halo2curves::bn256::Fr;
+use halo2curves::group::{Curve, Group};
+use halo2curves::secp256k1::{Fp, Secp256k1Affine};
+use integer::{IntegerInstructions, Range};
+use k256::ecdsa::signature::hazmat::{PrehashSigner, PrehashVerifier};
+use k256::ecdsa::{Signature, SigningKey, VerifyingKey};
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use maingate::RegionCtx;
+use quarry_circuits::ecdsa::{
+    verify_signature, AssignedEcdsaSig, AssignedPublicKey, EcdsaChip, EcdsaConfig, BIT_LEN_LIMB,
+    MUL_PAIRS, NUMBER_OF_LIMBS,
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+const K: u32 = 18;
+
+type E = Secp256k1Affine;
+type Scalar = <E as CurveAffine>::ScalarExt;
+
+#[derive(Clone, Copy)]
+struct VerifyCircuit {
+    public_key: Value<E>,
+    signature: Value<(Scalar, Scalar)>,
+    msg_hash: Value<Scalar>,
+    aux_generator: E,
+}
+
+impl Circuit<Fr> for VerifyCircuit {
+    type Config = EcdsaConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            public_key: Value::unknown(),
+            signature: Value::unknown(),
+            msg_hash: Value::unknown(),
+            aux_generator: self.aux_generator,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        EcdsaConfig::configure::<E, Fr>(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let mut ecc_chip =
+            GeneralEccChip::<E, Fr, NUMBER_OF_LIMBS, BIT_LEN_LIMB>::new(config.ecc_chip_config());
+        layouter.assign_region(
+            || "assign aux values",
+            |region| {
+                let ctx = &mut RegionCtx::new(region, 0);
+                ecc_chip.assign_aux_generator(ctx, Value::known(self.aux_generator))?;
+                ecc_chip.assign_aux(ctx, 2, MUL_PAIRS)?;
+                Ok(())
+            },
+        )?;
+
+        let ecdsa_chip = EcdsaChip::new(ecc_chip.clone(), 2);
+        let scalar_chip = ecc_chip.scalar_field_chip();
+        layouter.assign_region(
+            || "verify",
+            |region| {
+                let ctx = &mut RegionCtx::new(region, 0);
+                let mut scalar = |value: Value<Scalar>| {
+                    scalar_chip.assign_integer(
+                        ctx,
+                        ecc_chip.new_unassigned_scalar(value),
+                        Range::Remainder,
+                    )
+                };
+                let r = scalar(self.signature.map(|signature| signature.0))?;
+                let s = scalar(self.signature.map(|signature| signature.1))?;
+                let msg_hash = scalar(self.msg_hash)?;
+                let point = ecc_chip.assign_point(ctx, self.public_key)?;
+                ecdsa_chip.verify(
+                    ctx,
+                    &AssignedEcdsaSig { r, s },
+                    &AssignedPublicKey { point },
+                    &msg_hash,
+                )
+            },
+        )?;
+        config.config_range(&mut layouter)
+    }
+}
+
+fn accepted_by_circuit(
+    public_key: E,
+    msg_hash: Scalar,
+    signature: (Scalar, Scalar),
+    aux_generator: E,
+) -> bool {
+    let circuit = VerifyCircuit {
+        public_key: Value::known(public_key),
+        signature: Value::known(signature),
+        msg_hash: Value::known(msg_hash),
+        aux_generator,
+    };
+    MockProver::run(K, &circuit, vec![vec![]]).map_or(false, |prover| prover.verify().is_ok())
+}
+
+// k256 encodes field elements big-endian, halo2curves little-endian.
+fn to_k256(scalar: Scalar) -> [u8; 32] {
+    let mut bytes = scalar.to_repr();
+    bytes.reverse();
+    bytes
+}
+
+fn from_k256<F: PrimeField<Repr = [u8; 32]>>(bytes: &[u8]) -> F {
+    let mut repr = [0; 32];
+    repr.copy_from_slice(bytes);
+    repr.reverse();
+    F::from_repr(repr).unwrap()
+}
+
+fn public_key(key: &VerifyingKey) -> E {
+    let point = key.to_encoded_point(false);
+    E::from_xy(
+        from_k256::<Fp>(point.x().unwrap()),
+        from_k256::<Fp>(point.y().unwrap()),
+    )
+    .unwrap()
+}
+
+fn accepted_by_k256(key: &VerifyingKey, msg_hash: Scalar, (r, s): (Scalar, Scalar)) -> bool {
+    [s, -s].into_iter().any(|s| {
+        Signature::from_scalars(to_k256(r), to_k256(s)).map_or(false, |signature| {
+            key.verify_prehash(&to_k256(msg_hash), &signature).is_ok()
+        })
+    })
+}
+
+#[test]
+fn circuit_agrees_with_k256() {
+    let mut rng = StdRng::seed_from_u64(657);
+    let signing_key = SigningKey::from_bytes(&to_k256(Scalar::random(&mut rng)).into()).unwrap();
+    let verifying_key = *signing_key.verifying_key();
+    let other_key = *SigningKey::from_bytes(&to_k256(Scalar::random(&mut rng)).into())
+        .unwrap()
+        .verifying_key();
+    let msg_hash = Scalar::random(&mut rng);
+    let signature: Signature = signing_key.sign_prehash(&to_k256(msg_hash)).unwrap();
+    let (r, s) = signature.split_bytes();
+    let (r, s) = (from_k256::<Scalar>(&r), from_k256::<Scalar>(&s));
+    let aux_generator = <E as CurveAffine>::CurveExt::random(&mut rng).to_affine();
+
+    let cases = [
+        ("valid", verifying_key, msg_hash, (r, s)),
+        // ECDSA signatures are malleable, both must agree on accepting it.
+        ("negated s", verifying_key, msg_hash, (r, -s)),
+        (
+            "other message",
+            verifying_key,
+            msg_hash + Scalar::one(),
+            (r, s),
+        ),
+        ("other key", other_key, msg_hash, (r, s)),
+        ("tweaked r", verifying_key, msg_hash, (r + Scalar::one(), s)),
+        ("tweaked s", verifying_key, msg_hash, (r, s.double())),
+    ];
+    for (name, key, hash, signature) in cases {
+        let expected = accepted_by_k256(&key, hash, signature);
+        let key = public_key(&key);
+        assert_eq!(
+            accepted_by_circuit(key, hash, signature, aux_generator),
+            expected,
+            "{}",
+            name
+        );
+        assert_eq!(
+            verify_signature::<E>(key, hash, signature),
+            expected,
+            "{}",
+            name
+        );
+    }
+    assert!(accepted_by_k256(&verifying_key, msg_hash, (r, s)));
+}
@@ -0,0 +1,73 @@
+// Reconstruction of erasure coded data from any large enough set of shards.
+use ff::Field;
+use halo2_proofs::dev::MockProver;
+use halo2curves::bn256::Fr;
+use quarry_circuits::{
+    erasure::{self, encode, interpolate, EncodingCircuit, Reconstruction},
+    merkle,
+};
+
+fn data() -> Vec<Fr> {
+    (1..=4).map(|i| Fr::from(i * 1000 + 7)).collect()
+}
+
+#[test]
+fn any_data_len_shards_recover_the_data() {
+    let data = data();
+    let shards = encode(&data, 8);
+    assert_eq!(shards[0], data[0]);
+    for picked in [[0, 1, 2, 3], [4, 5, 6, 7], [1, 3, 6, 7]] {
+        let picked = picked.map(|i| (i, shards[i]));
+        assert_eq!(interpolate(4, &picked), Some(data.clone()));
+    }
+    assert_eq!(interpolate(4, &[(0, shards[0]), (1, shards[1])]), None);
+    assert_eq!(interpolate(2, &[(5, shards[5]), (5, shards[5])]), None);
+}
+
+#[test]
+fn reconstruction_checks_the_data_root() {
+    let data = data();
+    let shards = encode(&data, 8);
+
+    let mut reconstruction = Reconstruction::new(merkle::root(&data), 4, 8);
+    for i in [7, 2, 5] {
+        assert!(reconstruction.add(i, shards[i]));
+    }
+    assert!(!reconstruction.is_ready());
+    assert!(!reconstruction.add(8, shards[0]));
+    assert!(!reconstruction.add(2, shards[3]));
+    assert!(reconstruction.add(0, shards[0]));
+    assert_eq!(reconstruction.missing(), vec![1, 3, 4, 6]);
+    assert_eq!(reconstruction.reconstruct(), Some(data.clone()));
+
+    // some shard no longer agrees with the others
+    let mut corrupt = reconstruction.clone();
+    corrupt.add(6, shards[6] + Fr::one());
+    assert_eq!(corrupt.reconstruct(), None);
+
+    let mut wrong_root = Reconstruction::new(Fr::from(1), 4, 8);
+    for (i, shard) in shards.iter().enumerate() {
+        assert!(wrong_root.add(i, *shard));
+    }
+    assert_eq!(wrong_root.reconstruct(), None);
+}
+
+#[test]
+fn encoding_circuit_binds_the_shards_root() {
+    let data = data();
+    let circuit = EncodingCircuit::<4, 8>::new(data.clone().try_into().unwrap());
+    let instances = erasure::instances(&data, 8);
+    MockProver::run(10, &circuit, vec![instances.clone()])
+        .unwrap()
+        .assert_satisfied();
+
+    // the root over shards that aren't an encoding of the data
+    let mut shards = encode(&data, 8);
+    shards[5] += Fr::one();
+    let mut wrong = instances;
+    wrong[erasure::SHARDS_ROOT] = merkle::root(&shards);
+    assert!(MockProver::run(10, &circuit, vec![wrong])
+        .unwrap()
+        .verify()
+        .is_err());
+}
@@ -0,0 +1,115 @@
+// Which failures are worth retrying, and constructors reporting bad input
+// instead of panicking.
+use ff::Field;
+use halo2_proofs::{arithmetic::CurveAffine, poly::kzg::commitment::ParamsKZG};
+use halo2curves::bn256::{Bn256, Fr};
+use halo2curves::secp256k1::{Fq, Secp256k1Affine};
+use quarry_circuits::{
+    committee::CommitteeCircuit,
+    error::{CircuitError, NetworkError, ProofError, SubmitError},
+    policy::QuorumPolicy,
+    proof::{keygen, prove, verify},
+    randomness::{seed_commitment, CommitReveal},
+    tune::tune_window_size,
+    QuarryError,
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+#[test]
+fn unreadable_proofs_are_not_retried() {
+    let mut rng = StdRng::seed_from_u64(620);
+    let (seed, salt) = (Fr::random(&mut rng), Fr::random(&mut rng));
+    let mut round = CommitReveal::new(1);
+    round.commit(0, seed_commitment(seed, salt));
+    round.reveal(0, seed, salt);
+    let circuit = round.circuit::<1>().unwrap();
+    let instances = round.instances().unwrap();
+
+    let params = ParamsKZG::<Bn256>::setup(10, &mut rng);
+    let pk = keygen(&params, &circuit).unwrap();
+    let proof = prove(&params, &pk, circuit, &instances, &mut rng).unwrap();
+
+    for unreadable in [proof[..proof.len() / 2].to_vec(), vec![0xff; proof.len()]] {
+        let err = verify(&params, pk.get_vk(), &instances, &unreadable).unwrap_err();
+        assert!(matches!(err, QuarryError::Proof(_)), "{}", err);
+        assert!(!err.is_retryable(), "{}", err);
+    }
+
+    let mut wrong = instances;
+    wrong[0] += Fr::one();
+    let err = verify(&params, pk.get_vk(), &wrong, &proof).unwrap_err();
+    assert!(matches!(err, QuarryError::Proof(ProofError::Invalid)));
+    assert!(!err.is_retryable());
+}
+
+#[test]
+fn only_transient_failures_are_retryable() {
+    let unavailable: QuarryError = NetworkError::Unavailable("peer timed out".into()).into();
+    let stale: QuarryError = SubmitError::Stale("base fee rose".into()).into();
+    let rejected: QuarryError = SubmitError::Rejected("proof refused".into()).into();
+    let malformed: QuarryError = NetworkError::Malformed("bad cbor".into()).into();
+    assert!(unavailable.is_retryable());
+    assert!(stale.is_retryable());
+    assert!(!rejected.is_retryable());
+    assert!(!malformed.is_retryable());
+}
+
+#[test]
+fn committee_witness_checks_its_input() {
+    type E = Secp256k1Affine;
+    let members = vec![E::generator(); 3];
+    let build = |members: &[E], signatures: &[Option<(Fq, Fq)>], policy| {
+        CommitteeCircuit::<E, Fr, 2>::new(
+            members,
+            signatures,
+            policy,
+            Fq::from(7),
+            Fr::zero(),
+            1,
+            E::generator(),
+            2,
+            StdRng::seed_from_u64(620),
+        )
+    };
+
+    for result in [
+        build(&members, &[None; 3], QuorumPolicy::k_of_n(1, 0..2)),
+        build(&members[..2], &[None; 1], QuorumPolicy::k_of_n(1, 0..2)),
+        build(&members[..2], &[None; 2], QuorumPolicy::k_of_n(1, 0..3)),
+    ] {
+        assert!(matches!(result, Err(CircuitError::InvalidWitness(_))));
+    }
+    assert!(build(&members[..2], &[None; 2], QuorumPolicy::k_of_n(1, 0..2)).is_ok());
+}
+
+#[test]
+fn tuning_needs_sizes_and_runs() {
+    let mut rng = StdRng::seed_from_u64(658);
+    let (seed, salt) = (Fr::random(&mut rng), Fr::random(&mut rng));
+    let mut round = CommitReveal::new(1);
+    round.commit(0, seed_commitment(seed, salt));
+    round.reveal(0, seed, salt);
+    let circuit = round.circuit::<1>().unwrap();
+    let instances = round.instances().unwrap();
+    let params = ParamsKZG::<Bn256>::setup(10, &mut rng);
+
+    // the reveal circuit has no windows, which leaves only the bookkeeping
+    let tune = |sizes: &[usize], runs| {
+        tune_window_size(
+            &params,
+            sizes.to_vec(),
+            runs,
+            |_| circuit.clone(),
+            &instances,
+            &mut StdRng::seed_from_u64(658),
+        )
+    };
+    for (sizes, runs) in [(&[][..], 3), (&[2, 3][..], 0)] {
+        assert!(matches!(
+            tune(sizes, runs),
+            Err(QuarryError::Circuit(CircuitError::InvalidParameters(_)))
+        ));
+    }
+    let choice = tune(&[2, 3], 3).unwrap();
+    assert!([2, 3].contains(&choice.window_size));
+}
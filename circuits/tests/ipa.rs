@@ -0,0 +1,35 @@
+// The field generic circuits proved over Vesta with the IPA backend.
+use halo2curves::pasta::Fp;
+use quarry_circuits::{
+    ipa::{keygen, prove, setup, verify},
+    mmr::{self, InclusionCircuit, Mmr},
+    QuarryError,
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+const K: u32 = 13;
+
+#[test]
+fn mmr_inclusion_proves_over_pasta() {
+    let mut rng = StdRng::seed_from_u64(689);
+    let mut mmr = Mmr::new();
+    for leaf in 1..=5 {
+        mmr.push(Fp::from(leaf));
+    }
+    let circuit = InclusionCircuit::new(&mmr.prove(2), Fp::from(3));
+    let instances = mmr::layout()
+        .encode(&[("root", &[mmr.root().unwrap()]), ("leaf", &[Fp::from(3)])])
+        .unwrap();
+
+    let params = setup(K);
+    let pk = keygen(&params, &circuit).unwrap();
+    let proof = prove(&params, &pk, circuit, &instances, &mut rng).unwrap();
+    verify(&params, pk.get_vk(), &instances, &proof).unwrap();
+
+    let mut wrong = instances.clone();
+    wrong[mmr::LEAF] = Fp::from(4);
+    assert!(matches!(
+        verify(&params, pk.get_vk(), &wrong, &proof),
+        Err(QuarryError::Proof(_))
+    ));
+}
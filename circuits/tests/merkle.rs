@@ -0,0 +1,76 @@
+// The incremental tree against the Merkle root over all of its leaves.
+use ff::Field;
+use halo2_proofs::arithmetic::CurveAffine;
+use halo2curves::bn256::Fr;
+use halo2curves::group::Curve;
+use halo2curves::secp256k1::{Fq, Secp256k1Affine};
+use quarry_circuits::{
+    committee,
+    merkle::{self, IncrementalTree, MerkleProof},
+};
+
+fn leaves(count: u64) -> Vec<Fr> {
+    (1..=count).map(|i| Fr::from(i * 31 + 5)).collect()
+}
+
+#[test]
+fn root_matches_the_padded_tree_at_every_size() {
+    let zero = Fr::from(655);
+    let mut tree = IncrementalTree::new(3, zero);
+    assert_eq!(tree.root(), merkle::root(&[zero; 8]));
+    for (i, leaf) in leaves(8).into_iter().enumerate() {
+        assert_eq!(tree.push(leaf), Some(i));
+        let mut padded = leaves(i as u64 + 1);
+        padded.resize(8, zero);
+        assert_eq!(tree.root(), merkle::root(&padded));
+    }
+    assert_eq!(tree.push(Fr::one()), None);
+    assert_eq!(tree.len(), tree.capacity());
+}
+
+#[test]
+fn proofs_verify_and_round_trip() {
+    let mut tree = IncrementalTree::new(4, Fr::zero());
+    for leaf in leaves(11) {
+        tree.push(leaf);
+    }
+    let root = tree.root();
+    for (i, leaf) in leaves(11).into_iter().enumerate() {
+        let proof = tree.prove(i);
+        assert_eq!(proof.siblings.len(), 4);
+        assert!(proof.verify(leaf, root));
+        assert!(!proof.verify(leaf + Fr::one(), root));
+        assert_eq!(MerkleProof::from_bytes(&proof.to_bytes()), Some(proof));
+    }
+
+    // an index past the depth of the path
+    let mut proof = tree.prove(3);
+    proof.index += 16;
+    assert!(!proof.verify(leaves(4)[3], root));
+    assert_eq!(MerkleProof::<Fr>::from_bytes(&[0; 7]), None);
+}
+
+#[test]
+fn committee_registry_holds_the_members_root() {
+    const N_MAX: usize = 4;
+    let members = (2..=4u64)
+        .map(|i| (Secp256k1Affine::generator() * Fq::from(i)).to_affine())
+        .collect::<Vec<_>>();
+    let registry = committee::registry::<_, Fr, N_MAX>(&members).unwrap();
+
+    let mut padded = members
+        .iter()
+        .map(committee::member_leaf::<_, Fr>)
+        .collect::<Vec<_>>();
+    let padding = committee::padding_key::<Secp256k1Affine>();
+    padded.push(committee::member_leaf::<_, Fr>(&padding));
+    assert_eq!(registry.root(), merkle::root(&padded));
+    assert_eq!(
+        registry.root(),
+        committee::members_root::<_, Fr, N_MAX>(&members)
+    );
+    assert!(registry.prove(1).verify(padded[1], registry.root()));
+
+    let too_many = [members.clone(), members].concat();
+    assert!(committee::registry::<_, Fr, N_MAX>(&too_many).is_err());
+}
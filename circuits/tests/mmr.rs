@@ -0,0 +1,70 @@
+// Inclusion proofs over ranges of any size below the height of the circuit.
+use halo2_proofs::{dev::MockProver, poly::kzg::commitment::ParamsKZG};
+use halo2curves::bn256::{Bn256, Fr};
+use quarry_circuits::{
+    mmr::{self, peak_heights, InclusionCircuit, Mmr},
+    proof::{keygen, prove, verify},
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+const HEIGHT: usize = 4;
+const K: u32 = 11;
+
+fn range(size: u64) -> Mmr<Fr> {
+    let mut mmr = Mmr::new();
+    for leaf in 1..=size {
+        mmr.push(Fr::from(leaf));
+    }
+    mmr
+}
+
+fn instances(mmr: &Mmr<Fr>, leaf: Fr) -> Vec<Fr> {
+    mmr::layout()
+        .encode(&[("root", &[mmr.root().unwrap()]), ("leaf", &[leaf])])
+        .unwrap()
+}
+
+#[test]
+fn one_key_proves_every_size() {
+    let mut rng = StdRng::seed_from_u64(633);
+    let params = ParamsKZG::<Bn256>::setup(K, &mut rng);
+    let empty = InclusionCircuit::with_height(HEIGHT, &range(1).prove(0), Fr::from(1));
+    let pk = keygen(&params, &empty).unwrap();
+
+    for (size, index) in [(1, 0), (5, 2), (5, 4), (8, 7), (15, 13)] {
+        let mmr = range(size);
+        let leaf = Fr::from(index as u64 + 1);
+        let circuit = InclusionCircuit::with_height(HEIGHT, &mmr.prove(index), leaf);
+        let instances = instances(&mmr, leaf);
+        let proof = prove(&params, &pk, circuit, &instances, &mut rng).unwrap();
+        verify(&params, pk.get_vk(), &instances, &proof).unwrap();
+    }
+}
+
+#[test]
+fn inclusion_needs_the_leaf_and_an_existing_peak() {
+    let mmr = range(5);
+    let circuit = InclusionCircuit::with_height(HEIGHT, &mmr.prove(4), Fr::from(5));
+    let instances = instances(&mmr, Fr::from(5));
+    MockProver::run(K, &circuit, vec![instances.clone()])
+        .unwrap()
+        .assert_satisfied();
+
+    let mut wrong = instances.clone();
+    wrong[mmr::LEAF] = Fr::from(4);
+    let prover = MockProver::run(K, &circuit, vec![wrong]).unwrap();
+    assert!(prover.verify().is_err());
+
+    // leaf 5 is the peak of height 0, a range of 5 has none of height 1
+    assert_eq!(peak_heights(5), vec![2, 0]);
+    let mut no_peak = circuit.clone();
+    no_peak.path.peak_height = no_peak.path.peak_height.map(|_| 1);
+    let prover = MockProver::run(K, &no_peak, vec![instances.clone()]).unwrap();
+    assert!(prover.verify().is_err());
+
+    // the root binds the size
+    let mut resized = circuit;
+    resized.path.size = resized.path.size.map(|_| 13);
+    let prover = MockProver::run(K, &resized, vec![instances]).unwrap();
+    assert!(prover.verify().is_err());
+}
@@ -0,0 +1,105 @@
+// The oracle circuit on signed observations, against forged messages, and
+// the payloads of its builder.
+use ff::{Field, PrimeField};
+use halo2_proofs::{arithmetic::CurveAffine, dev::MockProver};
+use halo2curves::bn256::Fr;
+use halo2curves::group::Curve;
+use halo2curves::secp256k1::{Fq, Secp256k1Affine};
+use quarry_circuits::{
+    ecdsa,
+    oracle::{self, ObservationBuilder, OracleCircuit},
+    payload::PayloadBuilder,
+    report::report,
+    secret::Secret,
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+type E = Secp256k1Affine;
+const MEMBERS: usize = 3;
+const EPOCH: u64 = 3;
+const VALUES: [u64; MEMBERS] = [5, 9, 7];
+
+fn members(rng: &mut StdRng) -> (Vec<Secret<Fq>>, Vec<E>) {
+    let secrets = (0..MEMBERS)
+        .map(|_| Secret::new(Fq::random(&mut *rng)))
+        .collect::<Vec<_>>();
+    let members = secrets
+        .iter()
+        .map(|sk| (E::generator() * sk.expose()).to_affine())
+        .collect();
+    (secrets, members)
+}
+
+fn circuit(members: &[E], signatures: &[(Fq, Fq)], rng: &mut StdRng) -> OracleCircuit<E, MEMBERS> {
+    let aux_generator = (E::generator() * Fq::random(&mut *rng)).to_affine();
+    OracleCircuit::new(members, &VALUES, signatures, EPOCH, aux_generator, 2).unwrap()
+}
+
+#[test]
+fn oracle_exposes_the_signed_median() {
+    let rng = &mut StdRng::seed_from_u64(685);
+    let (secrets, members) = members(rng);
+    let signatures = secrets
+        .iter()
+        .zip(VALUES)
+        .map(|(sk, value)| oracle::sign_observation::<E>(sk, EPOCH, value, &mut *rng))
+        .collect::<Vec<_>>();
+    let circuit = circuit(&members, &signatures, rng);
+    let k = report::<_, Fr>(&circuit).unwrap().min_k;
+
+    let instances = oracle::instances::<E, Fr>(&members, EPOCH, &VALUES);
+    assert_eq!(instances[oracle::MEDIAN], Fr::from(7));
+    let prover = MockProver::run(k, &circuit, vec![instances.clone()]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    let mut tampered = instances;
+    tampered[oracle::MEDIAN] = Fr::from(9);
+    let prover = MockProver::run(k, &circuit, vec![tampered]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn messages_off_by_the_native_modulus_are_rejected() {
+    let rng = &mut StdRng::seed_from_u64(685);
+    let (secrets, members) = members(rng);
+    // The BN254 scalar modulus as a secp256k1 scalar, which it fits.
+    let modulus = Fq::from_repr((-Fr::one()).to_repr()).unwrap() + Fq::one();
+    let signatures = secrets
+        .iter()
+        .zip(VALUES)
+        .enumerate()
+        .map(|(i, (sk, value))| {
+            let mut msg = oracle::observation::<E>(EPOCH, value);
+            if i == 0 {
+                msg += modulus;
+            }
+            ecdsa::sign::<E>(sk, msg, &mut *rng)
+        })
+        .collect::<Vec<_>>();
+    let circuit = circuit(&members, &signatures, rng);
+    let k = report::<_, Fr>(&circuit).unwrap().min_k;
+
+    let instances = oracle::instances::<E, Fr>(&members, EPOCH, &VALUES);
+    let prover = MockProver::run(k, &circuit, vec![instances]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn builder_attests_to_every_observation_once() {
+    let mut builder = ObservationBuilder::new();
+    assert_eq!(builder.build(EPOCH), None);
+
+    builder.observe(4);
+    builder.observe(7);
+    let payload = builder.build(EPOCH).unwrap();
+    assert_eq!(
+        oracle::payload_observation::<E>(EPOCH, &payload),
+        Some(oracle::observation::<E>(EPOCH, 7))
+    );
+    assert_eq!(
+        builder.msg_hash::<E>(EPOCH, &payload),
+        oracle::observation::<E>(EPOCH, 7)
+    );
+    assert_eq!(builder.build(EPOCH + 1), None);
+    assert_eq!(oracle::payload_observation::<E>(EPOCH, &payload[1..]), None);
+}
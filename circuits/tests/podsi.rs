@@ -0,0 +1,99 @@
+// Sub-piece inclusion in a circuit against the native PoDSI verifier.
+use halo2_proofs::{circuit::Value, dev::MockProver};
+use halo2curves::bn256::Fr;
+use quarry_circuits::{
+    commp::{self, Aggregate},
+    podsi::{self, SubPieceCircuit},
+    report::report,
+    sha256,
+};
+use sha2::{Digest, Sha256};
+
+const K: u32 = 18;
+
+// A 512 byte aggregate with a 256 byte piece first, which leaves the 128
+// byte piece at index 2, two levels below the root.
+fn aggregate() -> (Aggregate, [u8; 32]) {
+    let first = commp::compute(&[1; 200]);
+    let second = commp::compute(b"quarry");
+    (
+        Aggregate::new(512, &[(first, 256), (second, 128)]).unwrap(),
+        second,
+    )
+}
+
+#[test]
+fn native_compression_matches_sha2() {
+    for message in [&b""[..], b"abc", &[0x5a; 55]] {
+        let mut padded = [0u8; 64];
+        padded[..message.len()].copy_from_slice(message);
+        padded[message.len()] = 0x80;
+        padded[56..].copy_from_slice(&(message.len() as u64 * 8).to_be_bytes());
+        let digest = sha256::compress(sha256::IV, sha256::block(&padded))
+            .iter()
+            .flat_map(|word| word.to_be_bytes())
+            .collect::<Vec<_>>();
+        assert_eq!(digest, Sha256::digest(message).to_vec());
+    }
+}
+
+#[test]
+fn circuit_proves_what_the_native_verifier_accepts() {
+    let (aggregate, piece) = aggregate();
+    let proof = aggregate.prove(1).unwrap();
+    assert_eq!(proof.index, 2);
+    assert!(proof.verify(&piece, 128, &aggregate.commp(), 512));
+
+    let circuit = SubPieceCircuit::<2>::new(&proof, piece).unwrap();
+    let instances = podsi::instances(&piece, &aggregate.commp(), proof.index);
+    let prover = MockProver::run(K, &circuit, vec![instances]).unwrap();
+    assert_eq!(prover.verify(), Ok(()));
+
+    assert!(SubPieceCircuit::<3>::new(&proof, piece).is_err());
+}
+
+#[test]
+fn tampered_proofs_are_rejected() {
+    let (aggregate, piece) = aggregate();
+    let proof = aggregate.prove(1).unwrap();
+    let circuit = SubPieceCircuit::<2>::new(&proof, piece).unwrap();
+    let rejects = |instances: Vec<Fr>, circuit: &SubPieceCircuit<2>| {
+        let prover = MockProver::run(K, circuit, vec![instances]).unwrap();
+        assert!(prover.verify().is_err());
+    };
+
+    // another aggregate root
+    let mut root = aggregate.commp();
+    root[5] ^= 1;
+    rejects(podsi::instances(&piece, &root, 2), &circuit);
+
+    // the piece claimed at another index, within the levels and past them
+    for index in [3, 6] {
+        rejects(
+            podsi::instances(&piece, &aggregate.commp(), index),
+            &circuit,
+        );
+    }
+
+    // a sibling that isn't on the path
+    let mut tampered = circuit.clone();
+    let mut sibling = proof.siblings[1];
+    sibling[0] ^= 0x80;
+    tampered.siblings[1] = Value::known(sibling);
+    rejects(podsi::instances(&piece, &aggregate.commp(), 2), &tampered);
+
+    // another piece
+    let other = commp::compute(b"quarrz");
+    let circuit = SubPieceCircuit::<2>::new(&proof, other).unwrap();
+    rejects(podsi::instances(&piece, &aggregate.commp(), 2), &circuit);
+}
+
+#[test]
+fn sub_piece_circuit_meets_spec() {
+    let (aggregate, piece) = aggregate();
+    let circuit = SubPieceCircuit::<2>::new(&aggregate.prove(1).unwrap(), piece).unwrap();
+    let spec = podsi::spec();
+    let report = report::<_, Fr>(&circuit).unwrap();
+    assert_eq!(spec.check(&report), Ok(()));
+    assert!(report.min_k <= K);
+}
@@ -0,0 +1,110 @@
+// Commit-reveal rounds that close at their deadline without every reveal.
+use halo2_proofs::dev::MockProver;
+use halo2curves::bn256::Fr;
+use quarry_circuits::randomness::{
+    combine, randomness_row, revealed_row, seed_commitment, CommitReveal, Phase,
+};
+
+const N: usize = 4;
+
+fn reveals() -> Vec<(Fr, Fr)> {
+    (0..N as u64)
+        .map(|i| (Fr::from(10 + i), Fr::from(20 + i)))
+        .collect()
+}
+
+// Every member commits, only `revealing` reveal before the deadline.
+fn round(revealing: &[usize]) -> CommitReveal {
+    let reveals = reveals();
+    let mut round = CommitReveal::new(N);
+    for (member, (seed, salt)) in reveals.iter().enumerate() {
+        assert!(round.commit(member, seed_commitment(*seed, *salt)));
+    }
+    assert_eq!(round.phase(), Phase::Reveal);
+    for &member in revealing {
+        let (seed, salt) = reveals[member];
+        assert!(round.reveal(member, seed, salt));
+    }
+    round
+}
+
+#[test]
+fn withheld_reveals_stall_until_the_deadline() {
+    let seeds = reveals()
+        .into_iter()
+        .map(|(seed, _)| seed)
+        .collect::<Vec<_>>();
+    let mut round = round(&[1, 3]);
+    assert_eq!(round.randomness(), None);
+    assert!(round.excluded().is_empty());
+
+    round.close();
+    assert_eq!(round.phase(), Phase::Closed);
+    assert_eq!(round.excluded(), vec![0, 2]);
+    assert_eq!(round.randomness(), Some(combine(&[seeds[1], seeds[3]])));
+    let (seed, salt) = reveals()[0];
+    assert!(!round.reveal(0, seed, salt));
+
+    assert_eq!(
+        self::round(&[0, 1, 2, 3]).randomness(),
+        Some(combine(&seeds))
+    );
+}
+
+#[test]
+fn missing_commitments_are_excluded_at_the_commit_deadline() {
+    let (seed, salt) = reveals()[2];
+    let mut round = CommitReveal::new(N);
+    assert!(round.commit(2, seed_commitment(seed, salt)));
+    assert!(!round.reveal(2, seed, salt));
+    round.close();
+    assert!(!round.commit(0, seed_commitment(seed, salt)));
+    assert!(round.reveal(2, seed, salt));
+    round.close();
+    assert_eq!(round.excluded(), vec![0, 1, 3]);
+    assert_eq!(round.randomness(), Some(seed));
+
+    let instances = round.instances().unwrap();
+    MockProver::run(10, &round.circuit::<N>().unwrap(), vec![instances])
+        .unwrap()
+        .assert_satisfied();
+}
+
+#[test]
+fn circuit_combines_only_the_revealed_seeds() {
+    let mut round = round(&[1, 3]);
+    assert!(round.circuit::<N>().is_none());
+    round.close();
+    let circuit = round.circuit::<N>().unwrap();
+    let instances = round.instances().unwrap();
+    assert_eq!(
+        instances[revealed_row(N)..randomness_row(N)],
+        [0u64, 1, 0, 1].map(Fr::from)
+    );
+    MockProver::run(10, &circuit, vec![instances.clone()])
+        .unwrap()
+        .assert_satisfied();
+
+    // claiming an excluded member revealed, or dropping a reveal
+    for (member, revealed) in [(0, 1u64), (3, 0)] {
+        let mut tampered = instances.clone();
+        tampered[revealed_row(N) + member] = Fr::from(revealed);
+        let prover = MockProver::run(10, &circuit, vec![tampered]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    let mut tampered = instances;
+    tampered[randomness_row(N)] =
+        combine(&reveals().into_iter().map(|(s, _)| s).collect::<Vec<_>>());
+    let prover = MockProver::run(10, &circuit, vec![tampered]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn nobody_revealing_gives_no_randomness() {
+    let mut round = round(&[]);
+    round.close();
+    assert_eq!(round.excluded(), vec![0, 1, 2, 3]);
+    assert_eq!(round.randomness(), None);
+    assert!(round.instances().is_none());
+}
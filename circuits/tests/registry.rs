@@ -0,0 +1,119 @@
+// Keys pinned by the registry, and the versions two parties agree on.
+use ff::Field;
+use halo2_proofs::{circuit::Value, poly::kzg::commitment::ParamsKZG};
+use halo2curves::bn256::{Bn256, Fr};
+use quarry_circuits::{
+    error::ProofError,
+    proof::{keygen, keygen_registered, prove, verify_registered},
+    randomness::{self, seed_commitment, CommitReveal, RevealCircuit},
+    registry::{vk_fingerprint, CircuitRegistry},
+    QuarryError,
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+const K: u32 = 10;
+
+fn round(rng: &mut StdRng, members: usize) -> CommitReveal {
+    let mut round = CommitReveal::new(members);
+    let reveals = (0..members)
+        .map(|_| (Fr::random(&mut *rng), Fr::random(&mut *rng)))
+        .collect::<Vec<_>>();
+    for (member, (seed, salt)) in reveals.iter().enumerate() {
+        round.commit(member, seed_commitment(*seed, *salt));
+    }
+    for (member, (seed, salt)) in reveals.iter().enumerate() {
+        round.reveal(member, *seed, *salt);
+    }
+    round
+}
+
+fn unregistered(result: Result<impl Sized, QuarryError>) -> bool {
+    matches!(result, Err(QuarryError::Proof(ProofError::Unregistered(_))))
+}
+
+#[test]
+fn peers_agree_on_the_highest_common_version() {
+    let mut ours = CircuitRegistry::new();
+    ours.insert("randomness", 10, [1; 32], &[2, 1]);
+    ours.insert("committee", 18, [2; 32], &[1]);
+    ours.insert("tally", 12, [3; 32], &[1]);
+    let mut theirs = CircuitRegistry::new();
+    theirs.insert("randomness", 10, [1; 32], &[1, 2, 3]);
+    theirs.insert("committee", 18, [9; 32], &[1]);
+
+    assert_eq!(ours.get("randomness").unwrap().versions, vec![1, 2]);
+    assert!(ours.accepts("randomness", 1, &[1; 32]));
+    assert!(!ours.accepts("randomness", 3, &[1; 32]));
+    assert!(!ours.accepts("committee", 1, &[9; 32]));
+    // a different key for the committee, no tally on their side
+    assert_eq!(ours.negotiate(&theirs), vec![("randomness".to_string(), 2)]);
+}
+
+#[test]
+fn keys_are_checked_against_the_registry() {
+    let mut rng = StdRng::seed_from_u64(639);
+    let params = ParamsKZG::<Bn256>::setup(K, &mut rng);
+    let layout = randomness::layout(4);
+    let empty = RevealCircuit::<4> {
+        seeds: Value::unknown(),
+        salts: Value::unknown(),
+        commitments: Value::unknown(),
+        revealed: Value::unknown(),
+    };
+
+    let mut registry = CircuitRegistry::new();
+    assert!(unregistered(keygen_registered(
+        &params, &empty, &registry, &layout
+    )));
+    let vk = keygen(&params, &empty).unwrap().get_vk().clone();
+    registry.register(layout.circuit, K, &vk, &[layout.version]);
+
+    let pk = keygen_registered(&params, &empty, &registry, &layout).unwrap();
+    assert_eq!(vk_fingerprint(pk.get_vk()), vk_fingerprint(&vk));
+    let round = round(&mut rng, 4);
+    let instances = round.instances().unwrap();
+    let proof = prove(
+        &params,
+        &pk,
+        round.circuit::<4>().unwrap(),
+        &instances,
+        &mut rng,
+    )
+    .unwrap();
+    verify_registered(&params, pk.get_vk(), &registry, &layout, &instances, &proof).unwrap();
+
+    // the key of another circuit under the same name
+    let other = RevealCircuit::<2> {
+        seeds: Value::unknown(),
+        salts: Value::unknown(),
+        commitments: Value::unknown(),
+        revealed: Value::unknown(),
+    };
+    let other_pk = keygen(&params, &other).unwrap();
+    assert!(unregistered(verify_registered(
+        &params,
+        other_pk.get_vk(),
+        &registry,
+        &layout,
+        &instances,
+        &proof
+    )));
+    assert!(unregistered(keygen_registered(
+        &params,
+        &other,
+        &registry,
+        &randomness::layout(2)
+    )));
+
+    // a version nobody registered
+    let mut newer = layout.clone();
+    newer.version += 1;
+    assert!(unregistered(verify_registered(
+        &params,
+        pk.get_vk(),
+        &registry,
+        &newer,
+        &instances,
+        &proof
+    )));
+}
@@ -0,0 +1,95 @@
+// Compatibility of the Semaphore gadget with circomlib's Poseidon.
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Circuit, Column, ConstraintSystem, Error, Instance},
+};
+use halo2curves::bn256::Fr;
+use quarry_circuits::semaphore::{self, SemaphoreChip, SemaphoreConfig};
+
+// circomlib poseidon([1, 2]) and poseidon([1]).
+const POSEIDON_1_2: &str = "115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a";
+const POSEIDON_1: &str = "29176100eaa962bdc1fe6c654d6a3c130e96a4d1168b33848b897dc502820133";
+
+fn fr(hex: &str) -> Fr {
+    let mut repr = [0u8; 32];
+    for (i, byte) in repr.iter_mut().rev().enumerate() {
+        *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap();
+    }
+    Fr::from_repr(repr).unwrap()
+}
+
+#[test]
+fn native_hash_matches_circomlib() {
+    assert_eq!(semaphore::hash2(Fr::from(1), Fr::from(2)), fr(POSEIDON_1_2));
+    assert_eq!(semaphore::hash1(Fr::from(1)), fr(POSEIDON_1));
+}
+
+// The nullifier hash of a membership is poseidon([external, identity]), so
+// with 1 and 2 it is the vector above.
+#[derive(Clone)]
+struct NullifierCircuit {
+    identity_nullifier: Value<Fr>,
+    external_nullifier: Value<Fr>,
+}
+
+impl Circuit<Fr> for NullifierCircuit {
+    type Config = (SemaphoreConfig<Fr>, Column<Instance>);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            identity_nullifier: Value::unknown(),
+            external_nullifier: Value::unknown(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        (SemaphoreChip::configure(meta), instance)
+    }
+
+    fn synthesize(
+        &self,
+        (config, instance): Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let chip = SemaphoreChip::new(config);
+        let membership = chip.membership(
+            layouter.namespace(|| "membership"),
+            self.identity_nullifier,
+            Value::known(Fr::from(3)),
+            self.external_nullifier,
+            &[],
+            &[],
+        )?;
+        layouter.constrain_instance(membership.nullifier_hash.cell(), instance, 0)?;
+        layouter.constrain_instance(membership.root.cell(), instance, 1)
+    }
+}
+
+#[test]
+fn circuit_hash_matches_circomlib() {
+    let circuit = NullifierCircuit {
+        identity_nullifier: Value::known(Fr::from(2)),
+        external_nullifier: Value::known(Fr::from(1)),
+    };
+    // a group of one member has its identity commitment as root
+    let root = semaphore::identity_commitment(Fr::from(2), Fr::from(3));
+    MockProver::run(9, &circuit, vec![vec![fr(POSEIDON_1_2), root]])
+        .unwrap()
+        .assert_satisfied();
+
+    let wrong = NullifierCircuit {
+        identity_nullifier: Value::known(Fr::from(2)),
+        external_nullifier: Value::known(Fr::from(4)),
+    };
+    assert!(
+        MockProver::run(9, &wrong, vec![vec![fr(POSEIDON_1_2), root]])
+            .unwrap()
+            .verify()
+            .is_err()
+    );
+}
@@ -0,0 +1,199 @@
+// Every circuit against its statement spec: all public inputs constrained and
+// every relation implemented by the regions it names.
+use halo2_proofs::{
+    arithmetic::CurveAffine,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance},
+};
+use halo2curves::bn256::Fr;
+use halo2curves::secp256k1::Secp256k1Affine;
+use quarry_circuits::{
+    committee::{self, CommitteeCircuit},
+    compose::Composite,
+    erasure::{self, EncodingCircuit},
+    layout::InstanceLayout,
+    mmr::{self, InclusionCircuit, Mmr},
+    oracle::{self, OracleCircuit},
+    policy::QuorumPolicy,
+    randomness::{self, RevealCircuit},
+    report::report,
+    rollup::{self, Transition, TransitionCircuit},
+    spec::{SpecViolation, StatementSpec},
+    vote::{self, TallyCircuit},
+};
+
+fn assert_meets(spec: &StatementSpec, result: Result<(), Vec<SpecViolation>>) {
+    if let Err(violations) = result {
+        let violations = violations
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        panic!("{}: {}", spec.layout, violations.join(", "));
+    }
+}
+
+#[test]
+fn committee_meets_spec() {
+    type E = Secp256k1Affine;
+    let circuit = CommitteeCircuit::<E, Fr, 4> {
+        members: vec![Value::unknown(); 4],
+        signatures: vec![Value::unknown(); 4],
+        active: vec![Value::unknown(); 4],
+        policy: QuorumPolicy::k_of_n(2, 0..3),
+        msg_hash: Value::unknown(),
+        prev_attestation: Value::unknown(),
+        epoch: 1,
+        aux_generator: E::generator(),
+        window_size: 2,
+    };
+    let spec = committee::spec(4);
+    assert_meets(&spec, spec.check(&report::<_, Fr>(&circuit).unwrap()));
+}
+
+#[test]
+fn oracle_meets_spec() {
+    type E = Secp256k1Affine;
+    let circuit = OracleCircuit::<E, 3> {
+        members: vec![Value::unknown(); 3],
+        values: vec![Value::unknown(); 3],
+        signatures: vec![Value::unknown(); 3],
+        epoch: 1,
+        aux_generator: E::generator(),
+        window_size: 2,
+    };
+    let spec = oracle::spec();
+    assert_meets(&spec, spec.check(&report::<_, Fr>(&circuit).unwrap()));
+}
+
+#[test]
+fn randomness_meets_spec() {
+    let circuit = RevealCircuit::<4> {
+        seeds: Value::unknown(),
+        salts: Value::unknown(),
+        commitments: Value::unknown(),
+        revealed: Value::unknown(),
+    };
+    let spec = randomness::spec(4);
+    assert_meets(&spec, spec.check(&report(&circuit).unwrap()));
+}
+
+#[test]
+fn mmr_inclusion_meets_spec() {
+    let mut mmr = Mmr::new();
+    for leaf in 1..=5 {
+        mmr.push(Fr::from(leaf));
+    }
+    let circuit = InclusionCircuit::new(&mmr.prove(2), Fr::from(3));
+    let spec = mmr::spec();
+    assert_meets(&spec, spec.check(&report(&circuit).unwrap()));
+}
+
+#[test]
+fn rollup_transition_meets_spec() {
+    let circuit = Composite(TransitionCircuit::new(Transition {
+        prev_state_root: Fr::from(1),
+        new_state_root: Fr::from(2),
+        batch_commitment: Fr::from(3),
+    }));
+    let spec = rollup::spec();
+    assert_meets(&spec, spec.check(&report(&circuit).unwrap()));
+}
+
+#[test]
+fn erasure_encoding_meets_spec() {
+    let circuit = EncodingCircuit::<4, 8> {
+        data: Value::unknown(),
+    };
+    let spec = erasure::spec();
+    assert_meets(&spec, spec.check(&report(&circuit).unwrap()));
+}
+
+#[test]
+fn tally_meets_spec() {
+    let circuit = TallyCircuit::<4> {
+        votes: Value::unknown(),
+        salts: Value::unknown(),
+        policy: QuorumPolicy::weighted(3, &[3, 1, 1, 1]),
+    };
+    let spec = vote::spec();
+    assert_meets(&spec, spec.check(&report(&circuit).unwrap()));
+}
+
+#[test]
+fn unconstrained_inputs_are_caught() {
+    let circuit = RevealCircuit::<4> {
+        seeds: Value::unknown(),
+        salts: Value::unknown(),
+        commitments: Value::unknown(),
+        revealed: Value::unknown(),
+    };
+    let spec = StatementSpec::new(randomness::layout(4).field("extra", 1)).relation(
+        "missing",
+        "a relation nothing implements",
+        &["no such region"],
+    );
+    assert_eq!(
+        spec.check(&report(&circuit).unwrap()),
+        Err(vec![
+            SpecViolation::UnconstrainedInput {
+                field: "extra",
+                row: 9
+            },
+            SpecViolation::MissingRelation {
+                relation: "missing",
+                region: "no such region"
+            },
+        ])
+    );
+}
+
+// Copies its input into one region and assigns a free witness in another.
+#[derive(Clone, Default)]
+struct DanglingCircuit;
+
+impl Circuit<Fr> for DanglingCircuit {
+    type Config = (Column<Advice>, Column<Instance>);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        let advice = meta.advice_column();
+        let instance = meta.instance_column();
+        meta.enable_equality(advice);
+        meta.enable_equality(instance);
+        (advice, instance)
+    }
+
+    fn synthesize(
+        &self,
+        (advice, instance): Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let input = layouter.assign_region(
+            || "bound",
+            |mut region| region.assign_advice(|| "input", advice, 0, || Value::unknown()),
+        )?;
+        layouter.assign_region(
+            || "dangling",
+            |mut region| region.assign_advice(|| "free", advice, 0, || Value::unknown()),
+        )?;
+        layouter.constrain_instance(input.cell(), instance, 0)
+    }
+}
+
+#[test]
+fn unconstrained_relations_are_caught() {
+    let spec = StatementSpec::new(InstanceLayout::new("dangling", 1).field("input", 1))
+        .relation("bound", "input is copied in", &["bound"])
+        .relation("free", "a relation on a free witness", &["dangling"]);
+    assert_eq!(
+        spec.check(&report(&DanglingCircuit).unwrap()),
+        Err(vec![SpecViolation::UnconstrainedRelation {
+            relation: "free",
+            region: "dangling"
+        }])
+    );
+}
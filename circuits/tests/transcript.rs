@@ -0,0 +1,202 @@
+use ff::Field;
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::MockProver,
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance},
+    poly::kzg::commitment::ParamsKZG,
+    transcript::{
+        Blake2bRead, Blake2bWrite, Challenge255, EncodedChallenge, Transcript,
+        TranscriptWriterBuffer,
+    },
+};
+use halo2curves::bn256::{Bn256, Fr, G1Affine};
+use halo2curves::group::{prime::PrimeCurveAffine, Curve};
+use quarry_circuits::{
+    poseidon::{self, PoseidonConfig},
+    proof::{keygen, prove_with, prove_with_transcript, verify_with, verify_with_transcript},
+    randomness::{seed_commitment, CommitReveal, RevealCircuit},
+    transcript::{
+        point_limbs, PoseidonTranscript, PoseidonWrite, TranscriptChip, TranscriptKind,
+        TranscriptScheme,
+    },
+};
+use rand::{rngs::StdRng, SeedableRng};
+use std::io::{Read, Write};
+
+const N: usize = 2;
+const K: u32 = 10;
+const KINDS: [TranscriptKind; 3] = [
+    TranscriptKind::Blake2b,
+    TranscriptKind::Keccak256,
+    TranscriptKind::Poseidon,
+];
+
+fn round(rng: &mut StdRng) -> (RevealCircuit<N>, Vec<Fr>) {
+    let reveals = (0..N)
+        .map(|_| (Fr::random(&mut *rng), Fr::random(&mut *rng)))
+        .collect::<Vec<_>>();
+    let mut round = CommitReveal::new(N);
+    for (member, (seed, salt)) in reveals.iter().enumerate() {
+        round.commit(member, seed_commitment(*seed, *salt));
+    }
+    for (member, (seed, salt)) in reveals.iter().enumerate() {
+        round.reveal(member, *seed, *salt);
+    }
+    (round.circuit::<N>().unwrap(), round.instances().unwrap())
+}
+
+#[test]
+fn proofs_only_verify_with_their_transcript() {
+    let mut rng = StdRng::seed_from_u64(648);
+    let (circuit, instances) = round(&mut rng);
+    let params = ParamsKZG::<Bn256>::setup(K, &mut rng);
+    let pk = keygen(&params, &circuit).unwrap();
+
+    for made_with in KINDS {
+        let proof = prove_with(
+            &params,
+            &pk,
+            circuit.clone(),
+            &instances,
+            &mut rng,
+            made_with,
+        )
+        .unwrap();
+        for checked_with in KINDS {
+            let result = verify_with(&params, pk.get_vk(), &instances, &proof, checked_with);
+            assert_eq!(
+                result.is_ok(),
+                made_with == checked_with,
+                "proof made with {:?} checked with {:?}",
+                made_with,
+                checked_with,
+            );
+        }
+    }
+}
+
+#[test]
+fn transcripts_bind_the_instances() {
+    let mut rng = StdRng::seed_from_u64(649);
+    let (circuit, instances) = round(&mut rng);
+    let params = ParamsKZG::<Bn256>::setup(K, &mut rng);
+    let pk = keygen(&params, &circuit).unwrap();
+
+    let mut tampered = instances.clone();
+    tampered[0] += Fr::one();
+    for kind in KINDS {
+        let proof = prove_with(&params, &pk, circuit.clone(), &instances, &mut rng, kind).unwrap();
+        assert!(verify_with(&params, pk.get_vk(), &tampered, &proof, kind).is_err());
+    }
+}
+
+// A transcript defined outside the crate, which happens to be halo2's own.
+struct External;
+
+impl TranscriptScheme<G1Affine> for External {
+    type Challenge = Challenge255<G1Affine>;
+    type Writer<W: Write> = Blake2bWrite<W, G1Affine, Challenge255<G1Affine>>;
+    type Reader<R: Read> = Blake2bRead<R, G1Affine, Challenge255<G1Affine>>;
+}
+
+#[test]
+fn transcripts_outside_the_crate_prove() {
+    let mut rng = StdRng::seed_from_u64(650);
+    let (circuit, instances) = round(&mut rng);
+    let params = ParamsKZG::<Bn256>::setup(K, &mut rng);
+    let pk = keygen(&params, &circuit).unwrap();
+
+    let proof =
+        prove_with_transcript::<_, External>(&params, &pk, circuit, &instances, &mut rng).unwrap();
+    verify_with_transcript::<External>(&params, pk.get_vk(), &instances, &proof).unwrap();
+    verify_with(
+        &params,
+        pk.get_vk(),
+        &instances,
+        &proof,
+        TranscriptKind::Blake2b,
+    )
+    .unwrap();
+    assert!(
+        verify_with_transcript::<PoseidonTranscript>(&params, pk.get_vk(), &instances, &proof)
+            .is_err()
+    );
+}
+
+// Absorbs a point and a scalar in-circuit and exposes the challenge.
+#[derive(Clone)]
+struct ReplayCircuit {
+    limbs: Vec<Value<Fr>>,
+    scalar: Value<Fr>,
+}
+
+impl Circuit<Fr> for ReplayCircuit {
+    type Config = (PoseidonConfig<Fr>, Column<Advice>, Column<Instance>);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            limbs: vec![Value::unknown(); self.limbs.len()],
+            scalar: Value::unknown(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        let value = meta.advice_column();
+        meta.enable_equality(value);
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        (poseidon::configure(meta), value, instance)
+    }
+
+    fn synthesize(
+        &self,
+        (poseidon, value, instance): Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let (limbs, scalar) = layouter.assign_region(
+            || "load values",
+            |mut region| {
+                let limbs = self
+                    .limbs
+                    .iter()
+                    .enumerate()
+                    .map(|(i, limb)| region.assign_advice(|| "limb", value, i, || *limb))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                let scalar =
+                    region.assign_advice(|| "scalar", value, limbs.len(), || self.scalar)?;
+                Ok((limbs, scalar))
+            },
+        )?;
+
+        let mut transcript =
+            TranscriptChip::new(poseidon, value, layouter.namespace(|| "transcript"))?;
+        transcript.absorb_point(layouter.namespace(|| "point"), &limbs)?;
+        transcript.absorb(layouter.namespace(|| "scalar"), &scalar)?;
+        let challenge = transcript.squeeze(layouter.namespace(|| "challenge"))?;
+        layouter.constrain_instance(challenge.cell(), instance, 0)
+    }
+}
+
+#[test]
+fn poseidon_transcript_matches_in_circuit() {
+    let point = (G1Affine::generator() * Fr::from(5)).to_affine();
+    let scalar = Fr::from(7);
+
+    let mut native = PoseidonWrite::<Vec<u8>, G1Affine>::init(vec![]);
+    native.common_point(point).unwrap();
+    native.common_scalar(scalar).unwrap();
+    let challenge = native.squeeze_challenge().get_scalar();
+
+    let circuit = ReplayCircuit {
+        limbs: point_limbs(point)
+            .unwrap()
+            .into_iter()
+            .map(Value::known)
+            .collect(),
+        scalar: Value::known(scalar),
+    };
+    MockProver::run(K, &circuit, vec![vec![challenge]])
+        .unwrap()
+        .assert_satisfied();
+}
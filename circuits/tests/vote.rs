@@ -0,0 +1,83 @@
+// Private tallies: the proof binds the count and the outcome to the
+// published ballots without revealing the votes.
+use halo2_proofs::dev::MockProver;
+use halo2curves::bn256::Fr;
+use quarry_circuits::{
+    policy::QuorumPolicy,
+    vote::{self, ballot_commitment, BallotBox, PASSED, YES},
+};
+
+// Seat 0 weighs as much as the three others together.
+fn policy() -> QuorumPolicy {
+    QuorumPolicy::weighted(3, &[3, 1, 1, 1])
+}
+
+fn ballot_box(votes: &[(usize, bool)]) -> BallotBox {
+    let mut ballots = BallotBox::new(4);
+    for &(seat, vote) in votes {
+        let salt = Fr::from(100 + seat as u64);
+        assert!(ballots.cast(seat, ballot_commitment(vote, salt)));
+        assert!(ballots.open(seat, vote, salt));
+    }
+    ballots
+}
+
+#[test]
+fn ballots_are_bound_to_their_opening() {
+    let mut ballots = BallotBox::new(4);
+    assert!(ballots.cast(1, ballot_commitment(true, Fr::from(5))));
+    assert!(!ballots.cast(1, ballot_commitment(false, Fr::from(5))));
+    assert!(!ballots.cast(4, ballot_commitment(true, Fr::from(5))));
+    assert_eq!(ballots.unopened(), vec![1]);
+    assert_eq!(ballots.votes(), None);
+    assert!(ballots.circuit::<4>(policy()).is_none());
+
+    assert!(!ballots.open(1, false, Fr::from(5)));
+    assert!(!ballots.open(2, true, Fr::from(5)));
+    assert!(ballots.open(1, true, Fr::from(5)));
+    assert!(ballots.unopened().is_empty());
+    assert_eq!(ballots.votes(), Some(vec![false, true, false, false]));
+}
+
+#[test]
+fn tally_follows_the_policy_weights() {
+    // seat 3 doesn't vote
+    for (votes, yes, passed) in [
+        (vec![(0, true), (1, false), (2, false)], 1, true),
+        (vec![(0, false), (1, true), (2, true)], 2, false),
+    ] {
+        let ballots = ballot_box(&votes);
+        let instances = ballots.instances(&policy()).unwrap();
+        assert_eq!(instances.len(), vote::layout().len());
+        assert_eq!(instances[YES], Fr::from(yes));
+        assert_eq!(instances[PASSED], Fr::from(passed as u64));
+
+        let circuit = ballots.circuit::<4>(policy()).unwrap();
+        MockProver::run(12, &circuit, vec![instances])
+            .unwrap()
+            .assert_satisfied();
+    }
+}
+
+#[test]
+fn tampered_tally_is_rejected() {
+    let ballots = ballot_box(&[(0, false), (1, true), (2, true)]);
+    let circuit = ballots.circuit::<4>(policy()).unwrap();
+    let instances = ballots.instances(&policy()).unwrap();
+
+    for (row, value) in [(YES, Fr::from(3)), (PASSED, Fr::from(1))] {
+        let mut tampered = instances.clone();
+        tampered[row] = value;
+        let prover = MockProver::run(12, &circuit, vec![tampered]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    // a vote other than the one behind the published ballot
+    let mut flipped = circuit.clone();
+    flipped.votes = flipped.votes.map(|mut votes| {
+        votes[0] = true;
+        votes
+    });
+    let prover = MockProver::run(12, &flipped, vec![instances]).unwrap();
+    assert!(prover.verify().is_err());
+}